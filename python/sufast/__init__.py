@@ -48,6 +48,7 @@ from .exceptions import (
     HTTPException,
     WebSocketException,
     RequestValidationError,
+    RouteRegistrationError,
 )
 
 # WebSocket
@@ -133,6 +134,7 @@ __all__ = [
     "HTTPException",
     "WebSocketException",
     "RequestValidationError",
+    "RouteRegistrationError",
     # WebSocket
     "WebSocket",
     "ConnectionManager",
@@ -58,7 +58,7 @@ from functools import wraps
 from datetime import datetime, timezone
 
 from .request import Request, Response, json_response, html_response
-from .exceptions import HTTPException, STATUS_PHRASES
+from .exceptions import HTTPException, RouteRegistrationError, STATUS_PHRASES
 from .websocket import WebSocket, WebSocketRoute, WebSocketState
 from .background import BackgroundTasks
 from .openapi import OpenAPIGenerator, extract_route_params, extract_function_info
@@ -458,7 +458,8 @@ class Sufast:
                 ctypes.c_uint16,
                 ctypes.c_char_p,
             ]
-            self._rust_core.add_static_route.restype = ctypes.c_bool
+            # Registration functions return a status code (0 = ok)
+            self._rust_core.add_static_route.restype = ctypes.c_int32
 
             # Dynamic route registration
             self._rust_core.add_dynamic_route.argtypes = [
@@ -467,7 +468,11 @@ class Sufast:
                 ctypes.c_char_p,
                 ctypes.c_uint64,
             ]
-            self._rust_core.add_dynamic_route.restype = ctypes.c_bool
+            self._rust_core.add_dynamic_route.restype = ctypes.c_int32
+            self._rust_core.last_error_message.argtypes = []
+            self._rust_core.last_error_message.restype = ctypes.c_void_p
+            self._rust_core.free_sufast_string.argtypes = [ctypes.c_void_p]
+            self._rust_core.free_sufast_string.restype = None
 
             # Python callback
             self._PythonCallbackType = ctypes.CFUNCTYPE(
@@ -488,6 +493,8 @@ class Sufast:
             self._rust_core.get_performance_stats.restype = ctypes.POINTER(
                 ctypes.c_char
             )
+            self._rust_core.free_sufast_string.argtypes = [ctypes.POINTER(ctypes.c_char)]
+            self._rust_core.free_sufast_string.restype = None
 
             # Cache
             self._rust_core.clear_cache.argtypes = []
@@ -520,6 +527,10 @@ class Sufast:
                 except Exception:
                     extra_params = {}
 
+                # Request metadata travels alongside the path params
+                request_headers = extra_params.pop("headers", {})
+                request_query = extra_params.pop("query", {})
+
                 # Find route
                 result = router.find(method, path)
                 if not result:
@@ -542,6 +553,10 @@ class Sufast:
                         for pname in sig.parameters:
                             if pname in params:
                                 kwargs[pname] = params[pname]
+                            elif pname == "headers":
+                                kwargs[pname] = request_headers
+                            elif pname == "query":
+                                kwargs[pname] = request_query
 
                         if asyncio.iscoroutinefunction(handler):
                             loop = asyncio.new_event_loop()
@@ -606,6 +621,7 @@ class Sufast:
         try:
             if route.is_static and route.method == "GET":
                 # Try to pre-compute static response
+                status = None
                 try:
                     if route.is_async:
                         loop = asyncio.new_event_loop()
@@ -616,39 +632,60 @@ class Sufast:
 
                     formatted = self._format_handler_response(resp, route.status_code)
                     body = formatted.get("body", "")
-                    status = formatted.get("status", 200)
                     ct = formatted.get("headers", {}).get(
                         "Content-Type", "application/json"
                     )
 
                     route_key = f"{route.method}:{route.path}"
-                    self._rust_core.add_static_route(
+                    status = self._rust_core.add_static_route(
                         route_key.encode("utf-8"),
                         body.encode("utf-8") if isinstance(body, str) else body,
-                        status,
+                        formatted.get("status", 200),
                         ct.encode("utf-8"),
                     )
-                    route.tier = "static"
-                    return
                 except Exception:
                     pass  # Fall through to dynamic
+                if status is not None:
+                    self._check_rust_registration(status, route)
+                    route.tier = "static"
+                    return
 
             # Register as dynamic route
-            self._rust_core.add_dynamic_route(
+            status = self._rust_core.add_dynamic_route(
                 route.method.encode("utf-8"),
                 route.path.encode("utf-8"),
                 route.name.encode("utf-8"),
                 route.cache_ttl,
             )
+            self._check_rust_registration(status, route)
 
             if route.cache_ttl > 0:
                 route.tier = "cached"
             else:
                 route.tier = "dynamic"
 
+        except RouteRegistrationError:
+            raise
         except Exception:
             pass
 
+    def _check_rust_registration(self, status: int, route: RouteEntry):
+        """Raise if the Rust core rejected a route (e.g. the `set_max_routes` cap was hit)."""
+        # 0 = registered, 5 = registered over an existing route
+        if status in (0, 5):
+            return
+        detail = None
+        message_ptr = self._rust_core.last_error_message()
+        if message_ptr:
+            try:
+                detail = ctypes.string_at(message_ptr).decode("utf-8")
+            finally:
+                self._rust_core.free_sufast_string(message_ptr)
+        raise RouteRegistrationError(
+            f"Rust core rejected {route.method} {route.path} (status {status})"
+            + (f": {detail}" if detail else "")
+        )
+
     # ===========================================================
     # Route Registration
     # ===========================================================
@@ -1443,7 +1480,10 @@ class Sufast:
             try:
                 ptr = self._rust_core.get_performance_stats()
                 if ptr:
-                    rust_json = ctypes.string_at(ptr).decode("utf-8")
+                    try:
+                        rust_json = ctypes.string_at(ptr).decode("utf-8")
+                    finally:
+                        self._rust_core.free_sufast_string(ptr)
                     stats["rust_stats"] = json.loads(rust_json)
             except Exception:
                 pass
@@ -22,6 +22,14 @@ import logging
 from pathlib import Path
 
 # === RUST CORE INTEGRATION ===
+# Route registration status codes returned by the Rust core (FfiStatus)
+FFI_OK = 0
+FFI_REPLACED = 5
+
+# Prefix of render_template failures (matches TEMPLATE_ERROR_MARKER in the Rust core)
+TEMPLATE_ERROR_MARKER = "SUFAST_TEMPLATE_ERROR:"
+
+
 class RustCore:
     """Optimized Rust core integration for performance"""
     
@@ -91,27 +99,40 @@ class RustCore:
         try:
             # Define callback function type (match Rust exactly)
             self.PythonHandlerType = ctypes.CFUNCTYPE(
-                ctypes.c_char_p,                # Return: *const c_char
-                ctypes.c_char_p,                # Param 1: *const c_char
-                ctypes.c_char_p                 # Param 2: *const c_char  
+                ctypes.c_char_p,                # Return: response JSON {body, status, headers}
+                ctypes.c_char_p,                # Param 1: method
+                ctypes.c_char_p,                # Param 2: path
+                ctypes.c_char_p                 # Param 3: path params JSON
             )
             
             # Function signatures for ultra-optimized core
-            self.lib.set_python_handler.argtypes = [self.PythonHandlerType]
-            self.lib.set_python_handler.restype = ctypes.c_bool
+            self.lib.set_python_callback.argtypes = [self.PythonHandlerType]
+            self.lib.set_python_callback.restype = None
             
-            self.lib.add_route.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint64]
-            self.lib.add_route.restype = ctypes.c_bool
+            # Registrations return an FfiStatus code
+            self.lib.add_dynamic_route.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint64]
+            self.lib.add_dynamic_route.restype = ctypes.c_int32
             
-            self.lib.add_static_route.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
-            self.lib.add_static_route.restype = ctypes.c_bool
+            self.lib.add_static_route.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint16, ctypes.c_char_p]
+            self.lib.add_static_route.restype = ctypes.c_int32
             
             # Start server function
             self.lib.start_sufast_server.argtypes = [ctypes.c_char_p, ctypes.c_uint16]
             self.lib.start_sufast_server.restype = ctypes.c_int
             
             self.lib.get_performance_stats.argtypes = []
-            self.lib.get_performance_stats.restype = ctypes.c_char_p
+            # Raw pointer (not c_char_p) so it can be handed back to free_sufast_string
+            self.lib.get_performance_stats.restype = ctypes.c_void_p
+            
+            self.lib.free_sufast_string.argtypes = [ctypes.c_void_p]
+            self.lib.free_sufast_string.restype = None
+            
+            self.lib.set_template_dir.argtypes = [ctypes.c_char_p]
+            self.lib.set_template_dir.restype = ctypes.c_bool
+            
+            # Raw pointer so it can be released with free_sufast_string
+            self.lib.render_template.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+            self.lib.render_template.restype = ctypes.c_void_p
             
             self.lib.clear_cache.argtypes = []
             self.lib.clear_cache.restype = ctypes.c_bool
@@ -131,10 +152,12 @@ class RustCore:
             if self.is_loaded and self.lib:
                 response_json = json.dumps(response_data)
                 result = self.lib.add_static_route(
-                    path.encode('utf-8'),
-                    response_json.encode('utf-8')
+                    f"GET:{path}".encode('utf-8'),
+                    response_json.encode('utf-8'),
+                    200,
+                    b"application/json"
                 )
-                if result:
+                if result in (FFI_OK, FFI_REPLACED):
                     return True
             
             # Python fallback with caching
@@ -155,13 +178,13 @@ class RustCore:
             
             # Add to Rust core if available
             if self.is_loaded and self.lib:
-                result = self.lib.add_route(
+                result = self.lib.add_dynamic_route(
                     method.encode('utf-8'),
                     path.encode('utf-8'),
                     "python".encode('utf-8'),
                     cache_ttl
                 )
-                if result:
+                if result in (FFI_OK, FFI_REPLACED):
                     pass  # Route added to Rust core
             
             # Store handler in Python
@@ -178,6 +201,27 @@ class RustCore:
         except Exception as e:
             return False
     
+    def render_template(self, template_name: str, context: dict = None) -> str:
+        """Render a template with the Rust template engine"""
+        if not self.is_loaded or not self.lib:
+            raise RuntimeError("Rust core is not loaded")
+        
+        result_ptr = self.lib.render_template(
+            template_name.encode('utf-8'),
+            json.dumps(context or {}).encode('utf-8')
+        )
+        try:
+            result = ctypes.string_at(result_ptr).decode('utf-8')
+        finally:
+            self.lib.free_sufast_string(result_ptr)
+        
+        if result.startswith(TEMPLATE_ERROR_MARKER):
+            kind, _, message = result[len(TEMPLATE_ERROR_MARKER):].partition(':')
+            if kind == 'not_found':
+                raise FileNotFoundError(f"Template not found: {message}")
+            raise ValueError(f"Template {kind.replace('_', ' ')}: {message}")
+        return result
+    
     def get_performance_stats(self) -> dict:
         """Get comprehensive performance statistics"""
         try:
@@ -202,7 +246,10 @@ class RustCore:
                 try:
                     rust_stats_ptr = self.lib.get_performance_stats()
                     if rust_stats_ptr:
-                        rust_stats_str = ctypes.string_at(rust_stats_ptr).decode('utf-8')
+                        try:
+                            rust_stats_str = ctypes.string_at(rust_stats_ptr).decode('utf-8')
+                        finally:
+                            self.lib.free_sufast_string(rust_stats_ptr)
                         rust_stats = json.loads(rust_stats_str)
                         stats['sufast_optimization']['rust_stats'] = rust_stats
                         
@@ -282,18 +329,21 @@ class SufastUltraOptimized:
             # Create a global response storage to keep strings alive
             self._current_response = None
             
+            # The Rust core expects {body, status, headers} back from the callback
+            def respond(body: str, status: int = 200) -> bytes:
+                return json.dumps({
+                    'body': body,
+                    'status': status,
+                    'headers': {'Content-Type': 'application/json'}
+                }).encode('utf-8')
+            
             # Create the actual callback function with string return
-            def python_handler_impl(request_data_ptr, path_ptr):
+            def python_handler_impl(method_ptr, path_ptr, params_ptr):
                 try:
                     # Convert C strings to Python strings
-                    request_data = ctypes.string_at(request_data_ptr).decode('utf-8')
+                    method = ctypes.string_at(method_ptr).decode('utf-8')
                     path = ctypes.string_at(path_ptr).decode('utf-8')
                     
-                    # Parse request data
-                    import json
-                    request_info = json.loads(request_data)
-                    method = request_info.get('method', 'GET')
-                    
                     # Find matching route
                     route = self._find_matching_route(method, path)
                     
@@ -322,7 +372,7 @@ class SufastUltraOptimized:
                             
                             # Store response globally and return it
                             self._current_response = response_json
-                            return response_json.encode('utf-8')
+                            return respond(response_json)
                             
                         except Exception as e:
                             error_response = json.dumps({
@@ -332,17 +382,17 @@ class SufastUltraOptimized:
                                 'method': method
                             })
                             self._current_response = error_response
-                            return error_response.encode('utf-8')
+                            return respond(error_response, 500)
                     
                     # No matching route found
                     error_response = '{"error": "Route not found"}'
                     self._current_response = error_response
-                    return error_response.encode('utf-8')
+                    return respond(error_response, 404)
                     
                 except Exception as e:
                     error_response = '{"error": "Internal server error"}'
                     self._current_response = error_response
-                    return error_response.encode('utf-8')
+                    return respond(error_response, 500)
             
             # Create the callback function with proper typing
             python_handler = self.rust_core.PythonHandlerType(python_handler_impl)
@@ -351,9 +401,7 @@ class SufastUltraOptimized:
             self._python_handler_func = python_handler
             
             # Register with Rust core
-            if self.rust_core.lib.set_python_handler:
-                # Pass the function directly (it's already the correct type)
-                result = self.rust_core.lib.set_python_handler(python_handler)
+            self.rust_core.lib.set_python_callback(python_handler)
             
         except Exception as e:
             pass
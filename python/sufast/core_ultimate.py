@@ -7,6 +7,8 @@ Three-tier architecture:
 """
 
 import ctypes
+import inspect
+import itertools
 import json
 import re
 import threading
@@ -14,6 +16,27 @@ from pathlib import Path
 from .middleware import MiddlewareStack
 from .request import Request, Response
 
+# Route registration status codes returned by the Rust core (FfiStatus)
+FFI_OK = 0
+FFI_REPLACED = 5
+FFI_STATUS_NAMES = {
+    1: "null_arg",
+    2: "invalid_utf8",
+    3: "bad_pattern",
+    4: "capacity",
+    5: "replaced",
+    6: "not_found",
+    7: "invalid_header",
+}
+
+# Prefix of render_template failures (matches TEMPLATE_ERROR_MARKER in the Rust core)
+TEMPLATE_ERROR_MARKER = "SUFAST_TEMPLATE_ERROR:"
+
+
+def _registration_ok(status: int) -> bool:
+    """ctypes restype for registration functions: map the status code to a bool."""
+    return status in (FFI_OK, FFI_REPLACED)
+
 class Sufast:
     """Ultimate Sufast framework with three-tier performance optimization."""
     print("🚀 Welcome to Sufast - The Ultimate Python Web Framework")
@@ -28,7 +51,12 @@ class Sufast:
         self.docs_enabled = False  # Track if docs should be available
         self.rust_core = None
         self._response_storage = threading.local()
+        self._streams = {}  # stream id -> generator for chunked responses
+        self._stream_buffers = {}  # last chunk per stream, alive until Rust copies it
+        self._response_transforms = {}  # prefix -> ctypes callback, kept alive while registered
+        self._stream_ids = itertools.count(1)
         self.middleware_stack = MiddlewareStack()  # Initialize middleware stack
+        self._fallback_handler = None  # called for requests no route matches
         self._load_ultimate_rust_core()
         
     def _load_ultimate_rust_core(self):
@@ -57,13 +85,39 @@ class Sufast:
             self.rust_core.add_static_route.argtypes = [
                 ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint16, ctypes.c_char_p
             ]
-            self.rust_core.add_static_route.restype = ctypes.c_bool
+            self.rust_core.add_static_route.restype = _registration_ok
+            self.rust_core.add_static_route_with_cache.argtypes = [
+                ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint16, ctypes.c_char_p,
+                ctypes.c_char_p, ctypes.c_char_p
+            ]
+            self.rust_core.add_static_route_with_cache.restype = _registration_ok
             
             # Dynamic route registration for 5K+ RPS 
             self.rust_core.add_dynamic_route.argtypes = [
                 ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint64
             ]
-            self.rust_core.add_dynamic_route.restype = ctypes.c_bool
+            self.rust_core.add_dynamic_route.restype = _registration_ok
+            self.rust_core.add_dynamic_route_with_vary.argtypes = [
+                ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint64, ctypes.c_char_p
+            ]
+            self.rust_core.add_dynamic_route_with_vary.restype = _registration_ok
+            self.rust_core.add_dynamic_route_with_timeout.argtypes = [
+                ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_uint64, ctypes.c_char_p,
+                ctypes.c_uint64
+            ]
+            self.rust_core.add_dynamic_route_with_timeout.restype = _registration_ok
+            self.rust_core.set_request_timeout.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_request_timeout.restype = None
+            
+            # Detail for the last failed registration on this thread
+            self.rust_core.last_error_message.argtypes = []
+            self.rust_core.last_error_message.restype = ctypes.c_void_p
+            
+            # Batch dynamic route registration (JSON array + byte length)
+            self.rust_core.add_routes_batch.argtypes = [ctypes.c_char_p, ctypes.c_size_t]
+            self.rust_core.add_routes_batch.restype = ctypes.c_int64
+            self.rust_core.warm_cache.argtypes = [ctypes.c_char_p]
+            self.rust_core.warm_cache.restype = ctypes.c_int64
             
             # Ultra-fast Python callback registration (3 parameters)
             PythonCallback = ctypes.CFUNCTYPE(ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p)
@@ -73,6 +127,12 @@ class Sufast:
             # Fast server start
             self.rust_core.start_ultra_fast_server.argtypes = [ctypes.c_char_p, ctypes.c_uint16]
             self.rust_core.start_ultra_fast_server.restype = ctypes.c_int
+            self.rust_core.start_sufast_server_tls.argtypes = [
+                ctypes.c_char_p, ctypes.c_uint16, ctypes.c_char_p, ctypes.c_char_p
+            ]
+            self.rust_core.start_sufast_server_tls.restype = ctypes.c_int
+            self.rust_core.start_sufast_server_multi.argtypes = [ctypes.c_char_p, ctypes.c_uint16, ctypes.c_uint32]
+            self.rust_core.start_sufast_server_multi.restype = ctypes.c_int
             
             # Performance stats
             self.rust_core.get_performance_stats.argtypes = []
@@ -85,10 +145,92 @@ class Sufast:
             # Static route precompilation
             self.rust_core.precompile_static_routes.argtypes = []
             self.rust_core.precompile_static_routes.restype = ctypes.c_uint64
+            self.rust_core.set_cache_sweep_interval.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_cache_sweep_interval.restype = None
+            self.rust_core.set_database_url.argtypes = [ctypes.c_char_p]
+            self.rust_core.set_database_url.restype = ctypes.c_int32
+            self.rust_core.set_cors_config.argtypes = [ctypes.c_char_p]
+            self.rust_core.set_cors_config.restype = ctypes.c_bool
+            self.rust_core.set_template_dir.argtypes = [ctypes.c_char_p]
+            self.rust_core.set_template_dir.restype = ctypes.c_bool
+            self.rust_core.render_template.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+            self.rust_core.render_template.restype = ctypes.POINTER(ctypes.c_char)
+            self.rust_core.list_routes.argtypes = []
+            self.rust_core.list_routes.restype = ctypes.POINTER(ctypes.c_char)
+            self.rust_core.free_sufast_string.argtypes = [ctypes.POINTER(ctypes.c_char)]
+            self.rust_core.free_sufast_string.restype = None
+            
+            # Route count cap (0 = unlimited)
+            self.rust_core.set_max_routes.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_max_routes.restype = None
+            
+            # POST -> PUT/PATCH/DELETE override for HTML forms (off by default)
+            self.rust_core.enable_method_override.argtypes = [ctypes.c_bool]
+            self.rust_core.enable_method_override.restype = None
+            # Idempotency-Key replays (off by default)
+            self.rust_core.enable_idempotency_keys.argtypes = [ctypes.c_bool]
+            self.rust_core.enable_idempotency_keys.restype = None
+            self.rust_core.set_idempotency_ttl.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_idempotency_ttl.restype = None
+            self.rust_core.set_idempotency_max_entries.argtypes = [ctypes.c_size_t]
+            self.rust_core.set_idempotency_max_entries.restype = None
+            self.rust_core.set_cache_ttl_jitter.argtypes = [ctypes.c_double]
+            self.rust_core.set_cache_ttl_jitter.restype = ctypes.c_bool
+            self.rust_core.set_slow_request_threshold_ms.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_slow_request_threshold_ms.restype = None
+            self.rust_core.set_max_path_length.argtypes = [ctypes.c_size_t]
+            self.rust_core.set_max_path_length.restype = None
+            self.rust_core.set_max_inflight.argtypes = [ctypes.c_size_t]
+            self.rust_core.set_max_inflight.restype = None
+            self.rust_core.set_max_requests_per_connection.argtypes = [ctypes.c_uint64]
+            self.rust_core.set_max_requests_per_connection.restype = None
+            self.rust_core.set_maintenance_mode.argtypes = [ctypes.c_bool]
+            self.rust_core.set_maintenance_mode.restype = None
+            self.rust_core.set_maintenance_allowlist.argtypes = [ctypes.c_char_p]
+            self.rust_core.set_maintenance_allowlist.restype = ctypes.c_int32
+            self.rust_core.set_listener_options.argtypes = [
+                ctypes.c_uint32, ctypes.c_bool, ctypes.c_bool, ctypes.c_bool
+            ]
+            self.rust_core.set_listener_options.restype = None
+            self.rust_core.set_worker_threads.argtypes = [ctypes.c_uint32]
+            self.rust_core.set_worker_threads.restype = None
             
+            # Registration report (rejected patterns, route limit)
+            self.rust_core.validate_routes.argtypes = []
+            self.rust_core.validate_routes.restype = ctypes.POINTER(ctypes.c_char)
+            self.rust_core.list_static_routes.argtypes = []
+            self.rust_core.list_static_routes.restype = ctypes.POINTER(ctypes.c_char)
+            self.rust_core.clear_static_routes.argtypes = []
+            self.rust_core.clear_static_routes.restype = ctypes.c_uint64
+            
+            # Route removal (hot reload / deprovisioning)
+            self.rust_core.remove_route.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+            self.rust_core.remove_route.restype = ctypes.c_bool
+            self.rust_core.remove_dynamic_route.argtypes = [ctypes.c_char_p]
+            self.rust_core.remove_dynamic_route.restype = ctypes.c_bool
+            
+            # Long polling: parked GETs woken by publish_long_poll
+            self.rust_core.add_long_poll_route.argtypes = [ctypes.c_char_p, ctypes.c_uint64]
+            self.rust_core.add_long_poll_route.restype = ctypes.c_int32
+            self.rust_core.publish_long_poll.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+            self.rust_core.publish_long_poll.restype = ctypes.c_int32
+            
+            
+            # Chunked responses: Rust pulls chunks of generator handlers
+            PythonStreamCallback = ctypes.CFUNCTYPE(ctypes.c_void_p, ctypes.c_uint64, ctypes.POINTER(ctypes.c_int64))
+            self.rust_core.set_python_stream_callback.argtypes = [PythonStreamCallback]
+            self.rust_core.set_python_stream_callback.restype = None
+            
+            # Response transforms: JSON in, JSON out (buffer owned by Python)
+            self._transform_callback_type = ctypes.CFUNCTYPE(ctypes.c_void_p, ctypes.c_char_p)
+            self.rust_core.add_response_transform.argtypes = [ctypes.c_char_p, self._transform_callback_type]
+            self.rust_core.add_response_transform.restype = ctypes.c_int32
+            self.rust_core.clear_response_transforms.argtypes = []
+            self.rust_core.clear_response_transforms.restype = None
             
             # Register ultra-fast Python callback
             self._register_ultimate_callback()
+            self._register_stream_callback(PythonStreamCallback)
             
             # Precompile static routes for maximum performance
             precompiled_count = self.rust_core.precompile_static_routes()
@@ -116,7 +258,10 @@ class Sufast:
                 
                 # Convert response to optimized JSON format
                 if isinstance(response, dict):
-                    if 'body' in response and 'status' in response:
+                    if 'stream' in response:
+                        # Streaming response: Rust pulls the body via the stream callback
+                        response_json = json.dumps(response)
+                    elif 'body' in response and 'status' in response:
                         # Already formatted response - check content type for HTML
                         headers = response.get('headers', {})
                         content_type = headers.get('Content-Type', '')
@@ -186,6 +331,63 @@ class Sufast:
         # Register with Rust
         self.rust_core.set_python_callback(callback_func)
 
+    def fallback(self, func):
+        """Register the handler for requests that match no route.
+
+        It is called with the Request and returns a Response or a {"body", "status", "headers"}
+        dict. Without one, unmatched requests get a 404 with {"error": "Not Found"}.
+        """
+        self._fallback_handler = func
+        return func
+
+    def _register_stream_callback(self, callback_type):
+        """Register the chunk callback Rust uses to pull streaming response bodies."""
+        def stream_callback(stream_id, len_ptr):
+            self._stream_buffers.pop(stream_id, None)
+            if not len_ptr:
+                # Client disconnected: close the generator so its cleanup runs
+                generator = self._streams.pop(stream_id, None)
+                if generator is not None and hasattr(generator, 'close'):
+                    generator.close()
+                return None
+            
+            generator = self._streams.get(stream_id)
+            if generator is None:
+                len_ptr[0] = -1
+                return None
+            
+            try:
+                chunk = next(generator)
+            except StopIteration:
+                self._streams.pop(stream_id, None)
+                len_ptr[0] = 0
+                return None
+            except Exception as e:
+                print(f"❌ Stream {stream_id} error: {e}")
+                self._streams.pop(stream_id, None)
+                len_ptr[0] = -1
+                return None
+            
+            data = chunk.encode('utf-8') if isinstance(chunk, str) else bytes(chunk)
+            buffer = ctypes.create_string_buffer(data, len(data))
+            self._stream_buffers[stream_id] = buffer
+            len_ptr[0] = len(data)
+            return ctypes.addressof(buffer)
+        
+        # Keep a reference so the callback isn't garbage collected
+        self._stream_callback = callback_type(stream_callback)
+        self.rust_core.set_python_stream_callback(self._stream_callback)
+
+    def _open_stream(self, iterator, status=200, headers=None):
+        """Register a generator as a streaming response body and describe it for Rust."""
+        stream_id = next(self._stream_ids)
+        self._streams[stream_id] = iter(iterator)
+        return {
+            "status": status,
+            "headers": headers or {"Content-Type": "text/plain; charset=utf-8"},
+            "stream": stream_id,
+        }
+
     def add_middleware(self, middleware):
         """Add middleware to the middleware stack.
         
@@ -257,17 +459,19 @@ class Sufast:
                         break
             
             if response is None:
-                # Return 404 if no route found
-                response = {
-                    "body": json.dumps({
-                        "error": "Route not found",
-                        "path": path,
-                        "method": method,
-                        "available_routes": list(self.routes.keys())
-                    }),
-                    "status": 404,
-                    "headers": {"Content-Type": "application/json"}
-                }
+                if self._fallback_handler is not None:
+                    response = self._fallback_handler(request)
+                else:
+                    # Nothing about the app's routes goes to unauthenticated clients
+                    response = {
+                        "body": json.dumps({"error": "Not Found"}),
+                        "status": 404,
+                        "headers": {"Content-Type": "application/json"}
+                    }
+        
+        # Generator handlers stream their body chunk by chunk
+        if inspect.isgenerator(response):
+            return self._open_stream(response)
         
         # Convert response to Response object if needed
         if not isinstance(response, Response):
@@ -297,7 +501,7 @@ class Sufast:
         else:
             return response
 
-    def route(self, path: str, cache_ttl: int = 0, static: bool = None, tags: list = None, group: str = None, summary: str = None, description: str = None):
+    def route(self, path: str, cache_ttl: int = 0, static: bool = None, tags: list = None, group: str = None, summary: str = None, description: str = None, vary: list = None, timeout: int = 0):
         """Ultimate route decorator with intelligent three-tier optimization and enhanced documentation.
         
         Args:
@@ -308,6 +512,10 @@ class Sufast:
             group: Group name for organizing routes (e.g., 'User Management', 'Authentication')
             summary: Brief description of the endpoint
             description: Detailed description of the endpoint functionality
+            vary: Request headers the response depends on (e.g. ['Accept-Language']); cached
+                responses are kept per combination of their values
+            timeout: Seconds the handler may take before the client gets 504 (0 = the
+                `set_request_timeout` default)
         """
         def decorator(func):
             # Intelligent tier detection
@@ -323,11 +531,16 @@ class Sufast:
                     response = func()
                     
                     # Normalize response format
+                    cache_control = static_vary = None
                     if isinstance(response, dict):
                         if 'body' in response:
                             body = response['body']
                             status = response.get('status', 200)
-                            content_type = response.get('headers', {}).get('Content-Type', 'application/json')
+                            response_headers = response.get('headers', {})
+                            content_type = response_headers.get('Content-Type', 'application/json')
+                            # Route-level caching; without these Rust caches for a year
+                            cache_control = response_headers.get('Cache-Control')
+                            static_vary = response_headers.get('Vary')
                         else:
                             body = json.dumps(response)
                             status = 200
@@ -342,11 +555,13 @@ class Sufast:
                         content_type = 'application/json'
                     
                     # Register as ultra-fast static route
-                    success = self.rust_core.add_static_route(
+                    success = self.rust_core.add_static_route_with_cache(
                         route_key.encode('utf-8'),
                         body.encode('utf-8'),
                         status,
-                        content_type.encode('utf-8')
+                        content_type.encode('utf-8'),
+                        cache_control.encode('utf-8') if cache_control else None,
+                        static_vary.encode('utf-8') if static_vary else None
                     )
                     
                     if success:
@@ -362,12 +577,7 @@ class Sufast:
             
             if not is_static:
                 # TIER 2 & 3: Dynamic/Cached routes
-                success = self.rust_core.add_dynamic_route(
-                    b"GET",
-                    path.encode('utf-8'),
-                    func.__name__.encode('utf-8'),
-                    cache_ttl
-                )
+                success = self._register_dynamic_route("GET", path, func.__name__, cache_ttl, vary, timeout)
                 
                 if success:
                     if is_cached:
@@ -2714,7 +2924,227 @@ class Sufast:
         except Exception:
             return False
 
-    def run(self, host: str = "127.0.0.1", port: int = 8080, debug: bool = False, doc: bool = False):
+    def list_routes(self) -> list:
+        """Every route registered in the Rust core: [{"method", "path", "is_dynamic", "cache_ttl"}]."""
+        routes_ptr = self.rust_core.list_routes()
+        try:
+            return json.loads(ctypes.string_at(routes_ptr).decode('utf-8'))
+        finally:
+            self.rust_core.free_sufast_string(routes_ptr)
+
+    def set_database_url(self, url):
+        """Database pinged by the built-in /healthz readiness probe (e.g. "sqlite://app.db"); None clears it."""
+        status = self.rust_core.set_database_url(url.encode('utf-8') if url is not None else None)
+        if status != FFI_OK:
+            raise ValueError(self.last_registration_error() or FFI_STATUS_NAMES.get(status, status))
+
+    def set_cors_config(self, allow_origins=("*",), allow_methods=("GET", "POST", "PUT", "DELETE", "OPTIONS"),
+                        allow_headers=("*",), max_age=None, allow_credentials=False):
+        """CORS policy for servers started afterwards (the default allows any origin); raises ValueError if rejected."""
+        config = {
+            "allow_origins": list(allow_origins),
+            "allow_methods": list(allow_methods),
+            "allow_headers": list(allow_headers),
+            "max_age": max_age,
+            "allow_credentials": allow_credentials,
+        }
+        if not self.rust_core.set_cors_config(json.dumps(config).encode('utf-8')):
+            raise ValueError(f"Invalid CORS config: {config}")
+
+    def set_template_dir(self, directory):
+        """Directory the Rust template engine loads templates from (default "templates")."""
+        if not self.rust_core.set_template_dir(str(directory).encode('utf-8')):
+            raise ValueError(f"Invalid template directory: {directory!r}")
+
+    def render_template(self, template_name: str, context: dict = None) -> str:
+        """Render a template with the Rust template engine; raises FileNotFoundError or ValueError."""
+        result_ptr = self.rust_core.render_template(
+            template_name.encode('utf-8'), json.dumps(context or {}).encode('utf-8')
+        )
+        try:
+            result = ctypes.string_at(result_ptr).decode('utf-8')
+        finally:
+            self.rust_core.free_sufast_string(result_ptr)
+
+        if result.startswith(TEMPLATE_ERROR_MARKER):
+            kind, _, message = result[len(TEMPLATE_ERROR_MARKER):].partition(':')
+            if kind == 'not_found':
+                raise FileNotFoundError(f"Template not found: {message}")
+            raise ValueError(f"Template {kind.replace('_', ' ')}: {message}")
+        return result
+
+    def set_request_timeout(self, seconds: int):
+        """Answer 504 when a dynamic handler runs longer than `seconds` (default 30, 0 disables)."""
+        self.rust_core.set_request_timeout(seconds)
+
+    def set_cache_sweep_interval(self, seconds: int):
+        """Evict expired cached responses every `seconds` seconds in the background (default 60, 0 disables)."""
+        self.rust_core.set_cache_sweep_interval(seconds)
+
+    def set_max_routes(self, max_routes: int):
+        """Cap the number of static + dynamic routes; registrations past it are rejected (0 = unlimited)."""
+        self.rust_core.set_max_routes(max_routes)
+
+    def enable_method_override(self, enabled: bool = True):
+        """Dispatch POSTs carrying X-HTTP-Method-Override or ?_method= as PUT/PATCH/DELETE."""
+        self.rust_core.enable_method_override(enabled)
+
+    def enable_idempotency_keys(self, enabled: bool = True, ttl: int = 86400, max_entries: int = 10000):
+        """Replay the first response to a POST/PUT/PATCH/DELETE with the same Idempotency-Key for `ttl` seconds."""
+        self.rust_core.set_idempotency_ttl(ttl)
+        self.rust_core.set_idempotency_max_entries(max_entries)
+        self.rust_core.enable_idempotency_keys(enabled)
+
+    def set_cache_ttl_jitter(self, fraction: float):
+        """Randomize cached response TTLs within ±fraction (e.g. 0.1) so they don't expire together."""
+        if not self.rust_core.set_cache_ttl_jitter(fraction):
+            raise ValueError("cache TTL jitter must be between 0 and 1")
+
+    def set_slow_request_threshold(self, ms: int):
+        """Warn about requests slower than `ms` milliseconds (default 1000, 0 disables)."""
+        self.rust_core.set_slow_request_threshold_ms(ms)
+
+    def set_max_path_length(self, n: int):
+        """Reject request paths (with query string) longer than `n` bytes with 414 (default 8192)."""
+        self.rust_core.set_max_path_length(n)
+
+    def set_max_inflight(self, n: int):
+        """Answer 503 with Retry-After once more than `n` requests are in flight (0 disables)."""
+        self.rust_core.set_max_inflight(n)
+
+    def set_max_requests_per_connection(self, n: int):
+        """Close keep-alive connections after `n` requests with `Connection: close` (0 = no limit)."""
+        self.rust_core.set_max_requests_per_connection(n)
+
+    def set_maintenance_mode(self, enabled: bool, allowlist=None):
+        """Answer 503 for every path except those under `allowlist` (e.g. ["/health", "/admin"])."""
+        if allowlist is not None:
+            status = self.rust_core.set_maintenance_allowlist(",".join(allowlist).encode('utf-8'))
+            if status != FFI_OK:
+                raise ValueError(self.last_registration_error() or FFI_STATUS_NAMES.get(status, status))
+        self.rust_core.set_maintenance_mode(enabled)
+
+    def set_listener_options(self, backlog: int = 1024, nodelay: bool = True, keepalive: bool = False, reuse_port: bool = False):
+        """Socket options applied when the server binds: accept backlog, TCP_NODELAY, SO_KEEPALIVE, SO_REUSEPORT."""
+        self.rust_core.set_listener_options(backlog, nodelay, keepalive, reuse_port)
+
+    def set_worker_threads(self, n: int):
+        """Tokio worker threads for the server (0 = one per available CPU; capped at the CPU quota)."""
+        self.rust_core.set_worker_threads(n)
+
+    def last_registration_error(self):
+        """Why the last route registration on this thread failed, or None."""
+        message_ptr = self.rust_core.last_error_message()
+        if not message_ptr:
+            return None
+        try:
+            return ctypes.string_at(message_ptr).decode('utf-8')
+        finally:
+            self.rust_core.free_sufast_string(message_ptr)
+
+    def warm_cache(self, routes: list) -> int:
+        """Run cached routes once so their responses are cached before traffic arrives.
+
+        `routes` holds paths or `{"method", "path", "headers"}` dicts; routes without a
+        cache_ttl are skipped. Call after registering routes and before `run()`. Returns
+        the number of responses cached.
+        """
+        entries = [{"path": route} if isinstance(route, str) else route for route in routes]
+        warmed = self.rust_core.warm_cache(json.dumps(entries).encode('utf-8'))
+        if warmed < 0:
+            raise ValueError("invalid cache warm list")
+        return warmed
+
+    def validate_routes(self) -> dict:
+        """Report route registrations the Rust core rejected (bad patterns, route limit).
+
+        Call after registering routes and before `run()`; `report["valid"]` is False
+        when any route was dropped.
+        """
+        report_ptr = self.rust_core.validate_routes()
+        try:
+            return json.loads(ctypes.string_at(report_ptr).decode('utf-8'))
+        finally:
+            self.rust_core.free_sufast_string(report_ptr)
+
+    def list_static_routes(self) -> list:
+        """Static routes registered in the Rust core: [{"method", "path", "status", "content_type"}]."""
+        routes_ptr = self.rust_core.list_static_routes()
+        try:
+            return json.loads(ctypes.string_at(routes_ptr).decode('utf-8'))
+        finally:
+            self.rust_core.free_sufast_string(routes_ptr)
+
+    def clear_static_routes(self) -> int:
+        """Drop every static route (e.g. before re-registering on hot reload); returns how many were removed."""
+        self.static_routes.clear()
+        return self.rust_core.clear_static_routes()
+
+    def add_response_transform(self, prefix: str, transform):
+        """Post-process every response under `prefix` before it is sent.
+
+        `transform(status, headers, body)` gets the status code, a list of (name, value)
+        header pairs and the body text, and returns a dict with any of "status", "headers"
+        and "body" to replace (a non-string body is sent as JSON), or None to keep the
+        response as it is. Streamed and compressed responses are not transformed.
+        """
+        def transform_callback(input_json):
+            try:
+                response = json.loads(input_json.decode('utf-8'))
+                result = transform(response["status"], response["headers"], response["body"])
+            except Exception as e:
+                print(f"❌ Response transform error: {e}")
+                return None
+            if result is None:
+                return None
+            buffer = ctypes.create_string_buffer(json.dumps(result).encode('utf-8'))
+            # Rust copies the result before this thread calls another transform
+            self._response_storage.transform_buffer = buffer
+            return ctypes.addressof(buffer)
+        
+        callback = self._transform_callback_type(transform_callback)
+        status = self.rust_core.add_response_transform(prefix.encode('utf-8'), callback)
+        if status != FFI_OK:
+            raise ValueError(self.last_registration_error() or FFI_STATUS_NAMES.get(status, status))
+        self._response_transforms[prefix.rstrip('/')] = callback
+
+    def clear_response_transforms(self):
+        """Remove every response transform."""
+        self.rust_core.clear_response_transforms()
+        self._response_transforms.clear()
+
+    def remove_route(self, path: str, method: str = None) -> bool:
+        """Unregister a route and drop its cached responses.
+
+        With a method, removes that method's static or dynamic route at `path`;
+        without one, removes the dynamic pattern for every method.
+        """
+        if method is None:
+            return self.rust_core.remove_dynamic_route(path.encode('utf-8'))
+        return self.rust_core.remove_route(method.upper().encode('utf-8'), path.encode('utf-8'))
+
+    def long_poll_route(self, path: str, timeout: int = 30):
+        """Register a long-poll endpoint: GETs wait up to `timeout` seconds for `publish()` on their path.
+
+        Clients pass the X-Poll-Version of their last response as `?since=`; a request with
+        nothing newer gets 204 after the timeout.
+        """
+        status = self.rust_core.add_long_poll_route(path.encode('utf-8'), timeout)
+        if status != FFI_OK:
+            raise ValueError(self.last_registration_error() or FFI_STATUS_NAMES.get(status, status))
+
+    def publish(self, path: str, data):
+        """Publish JSON data to the long-poll channel for `path`, waking parked requests.
+
+        Raises ValueError if no long-poll route matches `path`.
+        """
+        payload = data if isinstance(data, str) else json.dumps(data)
+        status = self.rust_core.publish_long_poll(path.encode('utf-8'), payload.encode('utf-8'))
+        if status != FFI_OK:
+            raise ValueError(self.last_registration_error() or FFI_STATUS_NAMES.get(status, status))
+
+    def run(self, host: str = "127.0.0.1", port: int = 8080, debug: bool = False, doc: bool = False, workers: int = 0,
+            certfile: str = None, keyfile: str = None):
         """Run the ultra-fast optimized server with three-tier performance.
         
         Args:
@@ -2722,7 +3152,14 @@ class Sufast:
             port: Port to bind to
             debug: Enable debug mode
             doc: Enable interactive documentation at /docs
+            workers: Accept loops bound with SO_REUSEPORT, one per thread (0 = single listener;
+                `set_worker_threads` sizes the runtime either way)
+            certfile: PEM certificate chain; serve HTTPS (HTTP/2 + HTTP/1.1) together with `keyfile`
+            keyfile: PEM private key for `certfile`
         """
+        if (certfile is None) != (keyfile is None):
+            raise ValueError("certfile and keyfile must be given together")
+        scheme = "https" if certfile else "http"
         # Enable docs if requested
         if doc:
             self.docs_enabled = True
@@ -2744,15 +3181,22 @@ class Sufast:
             print(f"  ⚡ Dynamic: {len(self.dynamic_routes)} ({dynamic_pct:.1f}%)")
             print()
         
-        print(f"🌐 Server starting on http://{host}:{port}")
+        print(f"🌐 Server starting on {scheme}://{host}:{port}")
         if doc:
-            print(f"📖 Documentation: http://{host}:{port}/docs")
+            print(f"📖 Documentation: {scheme}://{host}:{port}/docs")
         # print("🔥 Press Ctrl+C to stop")
         
         try:
             # This blocks and runs the fast server
             host_bytes = host.encode('utf-8')
-            result = self.rust_core.start_ultra_fast_server(host_bytes, port)
+            if certfile:
+                result = self.rust_core.start_sufast_server_tls(
+                    host_bytes, port, certfile.encode('utf-8'), keyfile.encode('utf-8')
+                )
+            elif workers > 0:
+                result = self.rust_core.start_sufast_server_multi(host_bytes, port, workers)
+            else:
+                result = self.rust_core.start_ultra_fast_server(host_bytes, port)
             if result != 0:
                 print(f"❌ Server failed with code: {result}")
         except KeyboardInterrupt:
@@ -2810,6 +3254,19 @@ class Sufast:
             return self._create_method_route(path, 'PATCH', func, **kwargs)
         return decorator
     
+    def _register_dynamic_route(self, method: str, path: str, handler_name: str, cache_ttl: int, vary, timeout: int) -> bool:
+        """Register a dynamic route in the Rust core; `timeout` > 0 overrides the default handler timeout."""
+        vary_bytes = ', '.join(vary).encode('utf-8') if vary else None
+        if timeout:
+            return self.rust_core.add_dynamic_route_with_timeout(
+                method.encode('utf-8'), path.encode('utf-8'), handler_name.encode('utf-8'),
+                cache_ttl, vary_bytes, timeout
+            )
+        return self.rust_core.add_dynamic_route_with_vary(
+            method.encode('utf-8'), path.encode('utf-8'), handler_name.encode('utf-8'),
+            cache_ttl, vary_bytes
+        )
+
     def _create_method_route(self, path: str, method: str, func, **kwargs):
         """Internal method to create routes for different HTTP methods."""
         # Extract enhanced parameters
@@ -2819,6 +3276,8 @@ class Sufast:
         group = kwargs.get('group', None)
         summary = kwargs.get('summary', None)
         description = kwargs.get('description', None)
+        vary = kwargs.get('vary', None)
+        timeout = kwargs.get('timeout', 0)
         
         # Determine if route should be static/cached/dynamic
         has_params = '{' in path and '}' in path
@@ -2827,8 +3286,9 @@ class Sufast:
         
         route_key = f"{method}:{path}"
         
-        if is_static and method == 'GET':
-            # Only GET requests can be truly static
+        if is_static:
+            # Only GETs are detected as static; other methods opt in with static=True
+            # (e.g. a canned 204 for DELETE /cache)
             try:
                 response = func()
                 
@@ -2867,12 +3327,7 @@ class Sufast:
         
         if not is_static:
             # Dynamic/Cached routes
-            success = self.rust_core.add_dynamic_route(
-                method.encode('utf-8'),
-                path.encode('utf-8'),
-                func.__name__.encode('utf-8'),
-                cache_ttl
-            )
+            success = self._register_dynamic_route(method, path, func.__name__, cache_ttl, vary, timeout)
             
             if success:
                 if is_cached:
@@ -60,6 +60,10 @@ class WebSocketException(Exception):
         super().__init__(reason)
 
 
+class RouteRegistrationError(RuntimeError):
+    """Raised when the Rust core rejects a route, e.g. past the `set_max_routes` cap."""
+
+
 class RequestValidationError(HTTPException):
     """Raised when request validation fails."""
     
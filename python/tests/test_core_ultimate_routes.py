@@ -0,0 +1,143 @@
+"""Route registration in the ctypes core, against a stand-in for the Rust library."""
+
+import ctypes
+import json
+from unittest import mock
+
+from sufast.core_ultimate import Sufast
+
+
+class FakeRustCore:
+    """Records FFI calls; registrations succeed like the real core's `_registration_ok`."""
+
+    def __init__(self):
+        self.calls = []
+
+    def __getattr__(self, name):
+        def ffi_function(*args):
+            self.calls.append((name, args))
+            return True
+
+        return ffi_function
+
+
+def make_app():
+    with mock.patch.object(Sufast, "_load_ultimate_rust_core"):
+        app = Sufast()
+    app.rust_core = FakeRustCore()
+    return app
+
+
+def dynamic_registrations(app):
+    return [args for name, args in app.rust_core.calls if name == "add_dynamic_route_with_vary"]
+
+
+def test_dynamic_route_registers_without_vary():
+    app = make_app()
+
+    @app.route("/users/{user_id}")
+    def get_user(user_id):
+        return {"id": user_id}
+
+    assert dynamic_registrations(app) == [(b"GET", b"/users/{user_id}", b"get_user", 0, None)]
+    assert "GET:/users/{user_id}" in app.dynamic_routes
+
+
+def test_cached_route_registers_with_vary():
+    app = make_app()
+
+    @app.route("/greeting", cache_ttl=60, vary=["Accept-Language", "X-Tenant"])
+    def greeting():
+        return {"hello": "world"}
+
+    assert dynamic_registrations(app) == [
+        (b"GET", b"/greeting", b"greeting", 60, b"Accept-Language, X-Tenant")
+    ]
+    assert "GET:/greeting" in app.cached_routes
+
+
+def test_route_timeout_registers_with_timeout():
+    app = make_app()
+
+    @app.route("/reports/{report_id}", timeout=5)
+    def report(report_id):
+        return {"id": report_id}
+
+    timeout_calls = [args for name, args in app.rust_core.calls if name == "add_dynamic_route_with_timeout"]
+    assert timeout_calls == [(b"GET", b"/reports/{report_id}", b"report", 0, None, 5)]
+    assert dynamic_registrations(app) == []
+
+
+def test_explicit_static_route_for_other_methods():
+    app = make_app()
+
+    @app.delete("/cache", static=True)
+    def purge_cache():
+        return "", 204
+
+    static_calls = [args for name, args in app.rust_core.calls if name == "add_static_route"]
+    assert static_calls == [(b"DELETE:/cache", b"", 204, b"application/json")]
+    assert "DELETE:/cache" in app.static_routes
+
+
+def test_static_route_vary_header_does_not_leak_into_decorator_argument():
+    app = make_app()
+
+    @app.route("/static", vary=["Accept-Language"])
+    def static_page():
+        return {"body": "hi", "headers": {"Content-Type": "text/plain", "Vary": "Origin"}}
+
+    static_calls = [args for name, args in app.rust_core.calls if name == "add_static_route_with_cache"]
+    assert static_calls == [(b"GET:/static", b"hi", 200, b"text/plain", None, b"Origin")]
+
+
+def test_unmatched_request_gets_minimal_404():
+    app = make_app()
+
+    @app.route("/users/{user_id}")
+    def get_user(user_id):
+        return {"id": user_id}
+
+    response = app._handle_ultra_fast_dynamic_route("GET", "/missing", {})
+    assert response["status"] == 404
+    assert json.loads(response["body"]) == {"error": "Not Found"}
+
+
+def test_fallback_handler_answers_unmatched_requests():
+    app = make_app()
+
+    @app.fallback
+    def gone(request):
+        return {"body": json.dumps({"error": "Gone", "path": request.path}), "status": 410}
+
+    response = app._handle_ultra_fast_dynamic_route("GET", "/old-page", {})
+    assert response["status"] == 410
+    assert json.loads(response["body"]) == {"error": "Gone", "path": "/old-page"}
+
+
+def test_cors_config_is_sent_as_json():
+    app = make_app()
+    app.set_cors_config(allow_origins=["https://app.example.com"], allow_credentials=True, max_age=600)
+
+    (config_json,) = [args[0] for name, args in app.rust_core.calls if name == "set_cors_config"]
+    assert json.loads(config_json) == {
+        "allow_origins": ["https://app.example.com"],
+        "allow_methods": ["GET", "POST", "PUT", "DELETE", "OPTIONS"],
+        "allow_headers": ["*"],
+        "max_age": 600,
+        "allow_credentials": True,
+    }
+
+
+def test_render_template_maps_error_marker_to_exceptions():
+    app = make_app()
+    outputs = iter([b"Hello, World!", b"SUFAST_TEMPLATE_ERROR:not_found:missing.html"])
+    app.rust_core.render_template = lambda name, context: ctypes.create_string_buffer(next(outputs))
+
+    assert app.render_template("hello.html", {"name": "World"}) == "Hello, World!"
+    try:
+        app.render_template("missing.html")
+    except FileNotFoundError as e:
+        assert "missing.html" in str(e)
+    else:
+        raise AssertionError("missing template did not raise")
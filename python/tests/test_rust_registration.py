@@ -0,0 +1,31 @@
+"""Route registration against the Rust core's status codes."""
+
+import pytest
+
+from sufast import App, RouteRegistrationError
+
+
+class CapacityLimitedCore:
+    """Stands in for the Rust core once the `set_max_routes` cap is reached."""
+
+    FFI_CAPACITY = 4
+
+    def add_static_route(self, *args):
+        return self.FFI_CAPACITY
+
+    def add_dynamic_route(self, *args):
+        return self.FFI_CAPACITY
+
+    def last_error_message(self):
+        return None
+
+
+def test_rejected_registration_raises():
+    app = App()
+    app._rust_available = True
+    app._rust_core = CapacityLimitedCore()
+
+    with pytest.raises(RouteRegistrationError, match="status 4"):
+        @app.get("/items/{item_id}")
+        def get_item(item_id: int):
+            return {"id": item_id}
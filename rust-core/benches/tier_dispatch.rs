@@ -0,0 +1,163 @@
+// Benchmarks for the three-tier dispatch path: static -> cache -> dynamic.
+//
+// Routes are registered through the same FFI entry points Python uses, and requests go
+// through the real router, so these numbers track the per-request cost behind the
+// 52K / 45K / 2K RPS figures.
+
+use axum::body::Body;
+use axum::http::Request;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::ffi::{c_char, CString};
+use sufast_server::{
+    add_dynamic_route, add_static_route, build_router, set_python_callback, FfiStatus,
+};
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+// Stands in for the Python handler so the dynamic tier measures Rust-side overhead only
+static CALLBACK_RESPONSE: &[u8] = b"{\"status\":200,\"body\":\"{\\\"ok\\\":true}\"}\0";
+
+extern "C" fn bench_callback(
+    _method: *const c_char,
+    _path: *const c_char,
+    _params: *const c_char,
+) -> *const c_char {
+    CALLBACK_RESPONSE.as_ptr() as *const c_char
+}
+
+fn register_static(method_path: &str, body: &str) {
+    let method_path = CString::new(method_path).unwrap();
+    let body = CString::new(body).unwrap();
+    assert_eq!(
+        add_static_route(method_path.as_ptr(), body.as_ptr(), 200, std::ptr::null()),
+        FfiStatus::Ok as i32
+    );
+}
+
+fn register_dynamic(method: &str, pattern: &str, handler: &str, cache_ttl: u64) {
+    let method = CString::new(method).unwrap();
+    let pattern = CString::new(pattern).unwrap();
+    let handler = CString::new(handler).unwrap();
+    assert_eq!(
+        add_dynamic_route(
+            method.as_ptr(),
+            pattern.as_ptr(),
+            handler.as_ptr(),
+            cache_ttl
+        ),
+        FfiStatus::Ok as i32
+    );
+}
+
+fn dispatch(runtime: &Runtime, router: &axum::Router, path: &str) -> u16 {
+    dispatch_with_encoding(runtime, router, path, None)
+}
+
+fn dispatch_with_encoding(
+    runtime: &Runtime,
+    router: &axum::Router,
+    path: &str,
+    accept_encoding: Option<&str>,
+) -> u16 {
+    runtime.block_on(async {
+        let mut request = Request::builder().uri(path);
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.header("accept-encoding", accept_encoding);
+        }
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.status().as_u16()
+    })
+}
+
+fn bench_tiers(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let router = build_router();
+    set_python_callback(bench_callback);
+
+    register_static("GET:/bench/static", r#"{"tier":"static"}"#);
+    // Large enough to get a precompressed gzip variant at registration
+    let large_body = format!(r#"{{"tier":"static","padding":"{}"}}"#, "x".repeat(4096));
+    register_static("GET:/bench/static-large", &large_body);
+    register_dynamic("GET", "/bench/cached/{id}", "cached", 3600);
+    register_dynamic("GET", "/bench/dynamic/{id}", "dynamic", 0);
+
+    // Prime the cache so every measured request is a cache-tier hit
+    assert_eq!(dispatch(&runtime, &router, "/bench/cached/1"), 200);
+
+    let mut group = c.benchmark_group("tier_dispatch");
+    group.bench_function("static_hit", |b| {
+        b.iter(|| dispatch(&runtime, &router, black_box("/bench/static")))
+    });
+    // Plaintext vs precompressed serving of a gzip-eligible body; static_large_identity
+    // should track static_hit, since the only extra work is the Accept-Encoding check
+    group.bench_function("static_large_identity", |b| {
+        b.iter(|| dispatch(&runtime, &router, black_box("/bench/static-large")))
+    });
+    group.bench_function("static_large_gzip", |b| {
+        b.iter(|| {
+            dispatch_with_encoding(
+                &runtime,
+                &router,
+                black_box("/bench/static-large"),
+                Some("gzip, deflate, br"),
+            )
+        })
+    });
+    group.bench_function("cache_hit", |b| {
+        b.iter(|| dispatch(&runtime, &router, black_box("/bench/cached/1")))
+    });
+    group.bench_function("dynamic_match", |b| {
+        b.iter(|| dispatch(&runtime, &router, black_box("/bench/dynamic/42")))
+    });
+    group.finish();
+}
+
+fn bench_cache_key(c: &mut Criterion) {
+    // Mirrors the "METHOD:path" key shared by the static and cache tiers
+    c.bench_function("cache_key_construction", |b| {
+        b.iter(|| format!("{}:{}", black_box("GET"), black_box("/api/v1/users/12345")))
+    });
+}
+
+// Matching cost as the route table grows. Routes are spread across literal prefixes the
+// way a real API is, so the prefix index keeps candidate lists short; a linear scan
+// degrades with the total route count instead.
+fn bench_many_routes(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let router = build_router();
+    set_python_callback(bench_callback);
+
+    let mut group = c.benchmark_group("dynamic_route_scaling");
+    let mut registered = 0;
+    for total in [10, 100, 1_000] {
+        while registered < total {
+            register_dynamic(
+                "GET",
+                &format!("/svc{}/items/{{id}}", registered),
+                "many",
+                0,
+            );
+            registered += 1;
+        }
+
+        let last = format!("/svc{}/items/7", total - 1);
+        group.bench_with_input(
+            BenchmarkId::new("last_registered", total),
+            &last,
+            |b, path| b.iter(|| dispatch(&runtime, &router, black_box(path))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("miss", total),
+            "/unknown/items/7",
+            |b, path| b.iter(|| dispatch(&runtime, &router, black_box(path))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_tiers, bench_cache_key, bench_many_routes);
+criterion_main!(benches);
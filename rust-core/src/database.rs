@@ -2,21 +2,99 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use sqlx::{SqlitePool, Row};
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use sqlx::{Column, Executor, Row, SqlitePool, TypeInfo, ValueRef};
+use sqlx::sqlite::SqlitePoolOptions;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
-use async_trait::async_trait;
 
 #[derive(Debug, Clone)]
 pub struct DatabasePool {
     pool: SqlitePool,
+    // Results of `query_cached`, shared by clones of the pool
+    query_cache: Arc<DashMap<String, CachedQuery>>,
+    query_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// How long `execute_query` and `execute_non_query` wait for a statement before
+    /// giving up with `DatabaseError::Timeout`.
+    pub query_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            query_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+// One cached read. The cell is shared before the query finishes, so concurrent identical
+// reads wait for the first one instead of each hitting the database.
+#[derive(Debug)]
+struct CachedQuery {
+    rows: Arc<tokio::sync::OnceCell<Vec<HashMap<String, Value>>>>,
+    expires_at: Instant,
+}
+
+impl CachedQuery {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            rows: Arc::new(tokio::sync::OnceCell::new()),
+            expires_at: Instant::now() + ttl,
+        }
+    }
 }
 
 impl DatabasePool {
     pub async fn new(database_url: &str) -> Result<Self, DatabaseError> {
+        Self::with_config(database_url, PoolConfig::default()).await
+    }
+    
+    pub async fn with_config(database_url: &str, config: PoolConfig) -> Result<Self, DatabaseError> {
         let pool = SqlitePool::connect(database_url).await
             .map_err(DatabaseError::ConnectionError)?;
         
-        Ok(Self { pool })
+        Ok(Self { pool, query_cache: Arc::default(), query_timeout: config.query_timeout })
+    }
+    
+    /// A private in-memory database, handy for tests. SQLite gives every connection to
+    /// `sqlite::memory:` its own empty database, so the pool holds exactly one connection
+    /// and never lets it expire; dropping the pool discards the data.
+    pub async fn in_memory() -> Result<Self, DatabaseError> {
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:").await
+            .map_err(DatabaseError::ConnectionError)?;
+        
+        Ok(Self { pool, query_cache: Arc::default(), query_timeout: PoolConfig::default().query_timeout })
+    }
+    
+    /// A handle to the same pool whose queries use a different timeout, e.g.
+    /// `pool.clone().with_query_timeout(Duration::from_secs(2))` for one request path.
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+    
+    /// Run setup SQL such as fixtures. May contain several `;`-separated statements.
+    pub async fn seed(&self, sql: &str) -> Result<(), DatabaseError> {
+        self.pool.execute(sql).await
+            .map_err(DatabaseError::QueryError)?;
+        Ok(())
+    }
+    
+    // Lightweight liveness check used by the /healthz endpoint
+    pub async fn ping(&self) -> Result<(), DatabaseError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await
+            .map_err(DatabaseError::QueryError)?;
+        Ok(())
     }
     
     pub async fn execute_query(&self, query: &str, params: &[Value]) -> Result<Vec<HashMap<String, Value>>, DatabaseError> {
@@ -41,7 +119,8 @@ impl DatabasePool {
             };
         }
         
-        let rows = query_builder.fetch_all(&self.pool).await
+        let rows = tokio::time::timeout(self.query_timeout, query_builder.fetch_all(&self.pool)).await
+            .map_err(|_| DatabaseError::Timeout(self.query_timeout))?
             .map_err(DatabaseError::QueryError)?;
         
         let mut results = Vec::new();
@@ -60,6 +139,88 @@ impl DatabasePool {
         Ok(results)
     }
     
+    /// `execute_query` for hot read paths: identical reads (same SQL and params) within
+    /// `ttl` of the first share its result, and concurrent ones share a single execution.
+    /// Opt-in per call, so writes and reads that must be fresh are unaffected; call
+    /// `clear_query_cache` after a write that cached reads must see.
+    pub async fn query_cached(&self, query: &str, params: &[Value], ttl: Duration) -> Result<Vec<HashMap<String, Value>>, DatabaseError> {
+        let key = query_cache_key(query, params);
+        let now = Instant::now();
+        
+        // Copy the cell out so no map guard is held across the retain/insert below
+        let fresh = self.query_cache.get(&key)
+            .filter(|cached| cached.expires_at > now)
+            .map(|cached| cached.rows.clone());
+        
+        let rows = match fresh {
+            Some(rows) => rows,
+            None => {
+                // Drop expired entries while we're inserting anyway
+                self.query_cache.retain(|_, cached| cached.expires_at > now);
+                let mut entry = self.query_cache.entry(key).or_insert_with(|| CachedQuery::new(ttl));
+                if entry.expires_at <= now {
+                    *entry = CachedQuery::new(ttl);
+                }
+                entry.rows.clone()
+            }
+        };
+        
+        // A failed query leaves the cell empty, so the next caller retries it
+        let rows = rows.get_or_try_init(|| self.execute_query(query, params)).await?;
+        Ok(rows.clone())
+    }
+    
+    pub fn clear_query_cache(&self) {
+        self.query_cache.clear();
+    }
+    
+    /// Run a query and deserialize each row into `T`, using the column names as field names.
+    /// A column that doesn't fit the target field yields a `ConversionError` naming the
+    /// row and field.
+    pub async fn query_as<T: DeserializeOwned>(&self, query: &str, params: &[Value]) -> Result<Vec<T>, DatabaseError> {
+        let rows = self.execute_query(query, params).await?;
+        
+        rows.into_iter().enumerate().map(|(index, row)| {
+            let record = Value::Object(row.into_iter().collect());
+            serde_path_to_error::deserialize(record).map_err(|err| {
+                DatabaseError::ConversionError(format!(
+                    "row {}: field '{}': {}",
+                    index,
+                    err.path(),
+                    err.inner()
+                ))
+            })
+        }).collect()
+    }
+    
+    /// Run `base_query` one page at a time. `page` is 1-based; page 0 and a page_size of 0
+    /// are treated as 1. Only the numeric LIMIT/OFFSET are appended to the query text, user
+    /// input must go through `params`.
+    pub async fn paginate(&self, base_query: &str, params: &[Value], page: u32, page_size: u32) -> Result<PaginatedResult, DatabaseError> {
+        let page = page.max(1);
+        let page_size = page_size.max(1);
+        let offset = (page as u64 - 1) * page_size as u64;
+        let base_query = base_query.trim().trim_end_matches(';');
+        
+        let count_query = format!("SELECT COUNT(*) AS total FROM ({}) AS paginated", base_query);
+        let total = self.execute_query(&count_query, params).await?
+            .first()
+            .and_then(|row| row.get("total"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        
+        let page_query = format!("{} LIMIT {} OFFSET {}", base_query, page_size, offset);
+        let items = self.execute_query(&page_query, params).await?;
+        
+        Ok(PaginatedResult {
+            items,
+            total,
+            page,
+            page_size,
+            total_pages: total.div_ceil(page_size as u64),
+        })
+    }
+    
     pub async fn execute_non_query(&self, query: &str, params: &[Value]) -> Result<u64, DatabaseError> {
         let mut query_builder = sqlx::query(query);
         
@@ -81,41 +242,47 @@ impl DatabasePool {
             };
         }
         
-        let result = query_builder.execute(&self.pool).await
+        let result = tokio::time::timeout(self.query_timeout, query_builder.execute(&self.pool)).await
+            .map_err(|_| DatabaseError::Timeout(self.query_timeout))?
             .map_err(DatabaseError::QueryError)?;
         
         Ok(result.rows_affected())
     }
     
     fn extract_value(&self, row: &sqlx::sqlite::SqliteRow, index: usize) -> Result<Value, DatabaseError> {
-        let column = &row.columns()[index];
+        let raw = row.try_get_raw(index)
+            .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
+        if raw.is_null() {
+            return Ok(Value::Null);
+        }
         
-        match column.type_info().name() {
-            "TEXT" => {
-                let value: Option<String> = row.try_get(index)
-                    .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
-                Ok(value.map(Value::String).unwrap_or(Value::Null))
-            }
+        // Expressions such as COUNT(*) have no declared type, so fall back to what SQLite
+        // actually stored
+        let declared = row.columns()[index].type_info().name();
+        let stored = raw.type_info();
+        let type_name = if declared == "BOOLEAN" { declared } else { stored.name() };
+        
+        match type_name {
             "INTEGER" => {
-                let value: Option<i64> = row.try_get(index)
+                let value: i64 = row.try_get(index)
                     .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
-                Ok(value.map(|v| Value::Number(v.into())).unwrap_or(Value::Null))
+                Ok(Value::Number(value.into()))
             }
             "REAL" => {
-                let value: Option<f64> = row.try_get(index)
+                let value: f64 = row.try_get(index)
                     .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
-                Ok(value.map(|v| Value::Number(serde_json::Number::from_f64(v).unwrap_or_else(|| 0.into()))).unwrap_or(Value::Null))
+                Ok(Value::Number(serde_json::Number::from_f64(value).unwrap_or_else(|| 0.into())))
             }
             "BOOLEAN" => {
-                let value: Option<bool> = row.try_get(index)
+                let value: bool = row.try_get(index)
                     .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
-                Ok(value.map(Value::Bool).unwrap_or(Value::Null))
+                Ok(Value::Bool(value))
             }
             _ => {
-                // Default to string for unknown types
-                let value: Option<String> = row.try_get(index)
+                // Default to string for text and unknown types
+                let value: String = row.try_get(index)
                     .map_err(|e| DatabaseError::ConversionError(e.to_string()))?;
-                Ok(value.map(Value::String).unwrap_or(Value::Null))
+                Ok(Value::String(value))
             }
         }
     }
@@ -172,6 +339,15 @@ impl DatabasePool {
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PaginatedResult {
+    pub items: Vec<HashMap<String, Value>>,
+    pub total: u64,
+    pub page: u32,
+    pub page_size: u32,
+    pub total_pages: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct ColumnDefinition {
     pub name: String,
@@ -260,6 +436,12 @@ impl Migration {
         self.down_sql = sql.to_string();
         self
     }
+    
+    // SHA-256 of up_sql, recorded when applied to detect later edits
+    pub fn checksum(&self) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(self.up_sql.as_bytes()))
+    }
 }
 
 pub struct MigrationRunner {
@@ -272,24 +454,50 @@ impl MigrationRunner {
     }
     
     pub async fn init(&self) -> Result<(), DatabaseError> {
-        let migrations_table = vec![
+        let migrations_table = [
             ColumnDefinition::new("id", "TEXT").primary_key(),
             ColumnDefinition::new("description", "TEXT").not_null(),
             ColumnDefinition::new("applied_at", "DATETIME").not_null().default_value("CURRENT_TIMESTAMP"),
+            ColumnDefinition::new("checksum", "TEXT"),
         ];
         
         self.pool.create_table("migrations", &migrations_table).await?;
+        
+        // Tables created before checksums were tracked lack the column
+        let columns = self.pool.get_table_info("migrations").await?;
+        if !columns.iter().any(|c| c.name == "checksum") {
+            self.pool.execute_non_query("ALTER TABLE migrations ADD COLUMN checksum TEXT", &[]).await?;
+        }
+        
         Ok(())
     }
     
     pub async fn run_migration(&self, migration: &Migration) -> Result<(), DatabaseError> {
+        let checksum = migration.checksum();
+        
         // Check if migration already applied
         let existing = self.pool.execute_query(
-            "SELECT id FROM migrations WHERE id = ?",
+            "SELECT id, checksum FROM migrations WHERE id = ?",
             &[Value::String(migration.id.clone())]
         ).await?;
         
-        if !existing.is_empty() {
+        if let Some(row) = existing.first() {
+            match row.get("checksum").and_then(|v| v.as_str()) {
+                Some(stored) if stored != checksum => {
+                    return Err(DatabaseError::MigrationError(format!(
+                        "Migration {} was modified after it was applied (checksum {} != {})",
+                        migration.id, stored, checksum
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    // Applied before checksums were recorded: adopt the current one
+                    self.pool.execute_non_query(
+                        "UPDATE migrations SET checksum = ? WHERE id = ?",
+                        &[Value::String(checksum), Value::String(migration.id.clone())]
+                    ).await?;
+                }
+            }
             return Ok(()); // Already applied
         }
         
@@ -298,10 +506,11 @@ impl MigrationRunner {
         
         // Record the migration
         self.pool.execute_non_query(
-            "INSERT INTO migrations (id, description) VALUES (?, ?)",
+            "INSERT INTO migrations (id, description, checksum) VALUES (?, ?, ?)",
             &[
                 Value::String(migration.id.clone()),
-                Value::String(migration.description.clone())
+                Value::String(migration.description.clone()),
+                Value::String(checksum)
             ]
         ).await?;
         
@@ -321,9 +530,42 @@ impl MigrationRunner {
         Ok(())
     }
     
+    /// Roll back every migration applied after `target_id`, newest first. `target_id`
+    /// itself stays applied. `migrations` must contain the definitions being rolled back.
+    pub async fn rollback_to(&self, target_id: &str, migrations: &[Migration]) -> Result<(), DatabaseError> {
+        let applied = self.get_applied_migrations().await?;
+        let position = applied.iter().position(|id| id == target_id)
+            .ok_or_else(|| DatabaseError::MigrationError(format!("Migration {} is not applied", target_id)))?;
+        
+        self.rollback_ids(&applied[position + 1..], migrations).await
+    }
+    
+    /// Roll back the last `n` applied migrations, newest first.
+    pub async fn rollback_last(&self, n: usize, migrations: &[Migration]) -> Result<(), DatabaseError> {
+        let applied = self.get_applied_migrations().await?;
+        let start = applied.len().saturating_sub(n);
+        
+        self.rollback_ids(&applied[start..], migrations).await
+    }
+    
+    async fn rollback_ids(&self, ids: &[String], migrations: &[Migration]) -> Result<(), DatabaseError> {
+        // Resolve every definition first so a missing one doesn't leave a partial rollback
+        let to_rollback = ids.iter().rev()
+            .map(|id| migrations.iter().find(|m| &m.id == id)
+                .ok_or_else(|| DatabaseError::MigrationError(format!("No definition found for applied migration {}", id))))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        for migration in to_rollback {
+            self.rollback_migration(migration).await?;
+        }
+        
+        Ok(())
+    }
+    
     pub async fn get_applied_migrations(&self) -> Result<Vec<String>, DatabaseError> {
+        // applied_at has one-second resolution; rowid keeps same-second migrations in order
         let rows = self.pool.execute_query(
-            "SELECT id FROM migrations ORDER BY applied_at",
+            "SELECT id FROM migrations ORDER BY applied_at, rowid",
             &[]
         ).await?;
         
@@ -343,6 +585,19 @@ pub enum DatabaseError {
     ConversionError(String),
     #[error("Migration error: {0}")]
     MigrationError(String),
+    #[error("Query timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+// SHA-256 of the SQL and its JSON-encoded params
+fn query_cache_key(query: &str, params: &[Value]) -> String {
+    use sha2::{Digest, Sha256};
+    
+    let mut hasher = Sha256::new();
+    hasher.update(query.as_bytes());
+    hasher.update([0]);
+    hasher.update(Value::from(params.to_vec()).to_string().as_bytes());
+    hex::encode(hasher.finalize())
 }
 
 #[cfg(test)]
@@ -354,12 +609,12 @@ mod tests {
     async fn test_database_basic_operations() {
         let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let db_url = format!("sqlite://{}", db_path.display());
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
         
         let pool = DatabasePool::new(&db_url).await.unwrap();
         
         // Create a test table
-        let columns = vec![
+        let columns = [
             ColumnDefinition::new("id", "INTEGER").primary_key().auto_increment(),
             ColumnDefinition::new("name", "TEXT").not_null(),
             ColumnDefinition::new("email", "TEXT").unique(),
@@ -381,11 +636,125 @@ mod tests {
         assert_eq!(results[0].get("name").unwrap().as_str().unwrap(), "John");
     }
 
+    #[tokio::test]
+    async fn test_in_memory_pool_is_shared_and_isolated() {
+        let pool = DatabasePool::in_memory().await.unwrap();
+        pool.seed(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);
+             INSERT INTO notes (body) VALUES ('first'), ('second');"
+        ).await.unwrap();
+        
+        // Separate queries go through the same connection and see the seeded data
+        let rows = pool.execute_query("SELECT COUNT(*) AS n FROM notes", &[]).await.unwrap();
+        assert_eq!(rows[0].get("n").unwrap().as_i64(), Some(2));
+        
+        // Each in-memory pool is its own database
+        let other = DatabasePool::in_memory().await.unwrap();
+        assert!(other.execute_query("SELECT * FROM notes", &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_timeout() {
+        let pool = DatabasePool::in_memory().await.unwrap()
+            .with_query_timeout(Duration::from_millis(50));
+        
+        let slow = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n LIMIT 1000000000)
+                    SELECT COUNT(*) AS total FROM n";
+        let err = pool.execute_query(slow, &[]).await.unwrap_err();
+        assert!(matches!(err, DatabaseError::Timeout(timeout) if timeout == Duration::from_millis(50)));
+    }
+
+    #[tokio::test]
+    async fn test_query_cached_reuses_results_within_ttl() {
+        let pool = DatabasePool::in_memory().await.unwrap();
+        pool.seed("CREATE TABLE items (id INTEGER PRIMARY KEY); INSERT INTO items DEFAULT VALUES;").await.unwrap();
+        
+        let count = "SELECT COUNT(*) AS n FROM items WHERE id > ?";
+        let params = [Value::from(0)];
+        let ttl = Duration::from_secs(60);
+        let (a, b) = tokio::join!(
+            pool.query_cached(count, &params, ttl),
+            pool.query_cached(count, &params, ttl),
+        );
+        assert_eq!(a.unwrap()[0]["n"], 1);
+        assert_eq!(b.unwrap()[0]["n"], 1);
+        
+        pool.execute_non_query("INSERT INTO items DEFAULT VALUES", &[]).await.unwrap();
+        
+        // Cached until the TTL passes or the cache is cleared; other params miss
+        assert_eq!(pool.query_cached(count, &params, ttl).await.unwrap()[0]["n"], 1);
+        assert_eq!(pool.query_cached(count, &[Value::from(-1)], ttl).await.unwrap()[0]["n"], 2);
+        pool.clear_query_cache();
+        assert_eq!(pool.query_cached(count, &params, ttl).await.unwrap()[0]["n"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_as_deserializes_rows() {
+        #[derive(Debug, serde::Deserialize)]
+        struct User {
+            id: i64,
+            name: String,
+            email: Option<String>,
+        }
+        
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct WrongUser {
+            name: i64,
+        }
+        
+        let pool = DatabasePool::in_memory().await.unwrap();
+        pool.seed(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT);
+             INSERT INTO users (name, email) VALUES ('John', 'john@example.com'), ('Jane', NULL);"
+        ).await.unwrap();
+        
+        let users: Vec<User> = pool.query_as("SELECT id, name, email FROM users ORDER BY id", &[]).await.unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].id, 1);
+        assert_eq!(users[0].name, "John");
+        assert_eq!(users[0].email.as_deref(), Some("john@example.com"));
+        assert_eq!(users[1].email, None);
+        
+        let err = pool.query_as::<WrongUser>("SELECT name FROM users", &[]).await.unwrap_err();
+        match err {
+            DatabaseError::ConversionError(message) => assert!(message.contains("name"), "{}", message),
+            other => panic!("expected ConversionError, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let pool = DatabasePool::in_memory().await.unwrap();
+        pool.seed("CREATE TABLE items (id INTEGER PRIMARY KEY, kind TEXT NOT NULL)").await.unwrap();
+        for i in 0..25 {
+            let kind = if i % 5 == 0 { "special" } else { "regular" };
+            pool.execute_non_query("INSERT INTO items (kind) VALUES (?)", &[Value::String(kind.to_string())]).await.unwrap();
+        }
+        
+        let page = pool.paginate("SELECT * FROM items ORDER BY id", &[], 3, 10).await.unwrap();
+        assert_eq!(page.total, 25);
+        assert_eq!(page.total_pages, 3);
+        assert_eq!(page.items.len(), 5);
+        assert_eq!(page.items[0].get("id").unwrap().as_i64(), Some(21));
+        
+        let filtered = pool.paginate(
+            "SELECT * FROM items WHERE kind = ? ORDER BY id;",
+            &[Value::String("special".to_string())],
+            0,
+            2
+        ).await.unwrap();
+        assert_eq!(filtered.page, 1);
+        assert_eq!(filtered.total, 5);
+        assert_eq!(filtered.total_pages, 3);
+        assert_eq!(filtered.items.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_migration_system() {
         let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test_migrations.db");
-        let db_url = format!("sqlite://{}", db_path.display());
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
         
         let pool = Arc::new(DatabasePool::new(&db_url).await.unwrap());
         let runner = MigrationRunner::new(pool);
@@ -401,4 +770,74 @@ mod tests {
         let applied = runner.get_applied_migrations().await.unwrap();
         assert_eq!(applied, vec!["001"]);
     }
+
+    #[tokio::test]
+    async fn test_rollback_to_version() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test_rollback.db");
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        
+        let pool = Arc::new(DatabasePool::new(&db_url).await.unwrap());
+        let runner = MigrationRunner::new(pool.clone());
+        runner.init().await.unwrap();
+        
+        let migrations = vec![
+            Migration::new("001", "Create users table")
+                .up("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+                .down("DROP TABLE users"),
+            Migration::new("002", "Create posts table")
+                .up("CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL)")
+                .down("DROP TABLE posts"),
+            Migration::new("003", "Create tags table")
+                .up("CREATE TABLE tags (id INTEGER PRIMARY KEY, label TEXT NOT NULL)")
+                .down("DROP TABLE tags"),
+        ];
+        
+        for migration in &migrations {
+            runner.run_migration(migration).await.unwrap();
+        }
+        assert_eq!(runner.get_applied_migrations().await.unwrap(), vec!["001", "002", "003"]);
+        
+        runner.rollback_to("001", &migrations).await.unwrap();
+        assert_eq!(runner.get_applied_migrations().await.unwrap(), vec!["001"]);
+        
+        let tables = pool.execute_query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name IN ('users', 'posts', 'tags')",
+            &[]
+        ).await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].get("name").unwrap().as_str().unwrap(), "users");
+        
+        runner.rollback_last(1, &migrations).await.unwrap();
+        assert!(runner.get_applied_migrations().await.unwrap().is_empty());
+        
+        assert!(runner.rollback_to("002", &migrations).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_migration_checksum_drift() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test_checksum.db");
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        
+        let pool = Arc::new(DatabasePool::new(&db_url).await.unwrap());
+        let runner = MigrationRunner::new(pool);
+        runner.init().await.unwrap();
+        
+        let original = Migration::new("001", "Create users table")
+            .up("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .down("DROP TABLE users");
+        runner.run_migration(&original).await.unwrap();
+        
+        // Re-running the unchanged migration is a no-op
+        runner.run_migration(&original).await.unwrap();
+        
+        let edited = Migration::new("001", "Create users table")
+            .up("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT)")
+            .down("DROP TABLE users");
+        match runner.run_migration(&edited).await {
+            Err(DatabaseError::MigrationError(message)) => assert!(message.contains("001")),
+            other => panic!("expected MigrationError, got {:?}", other),
+        }
+    }
 }
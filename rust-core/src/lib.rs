@@ -1,742 +0,0 @@
-use axum::{
-    body::Body,
-    extract::{Path, Query, State},
-    http::{HeaderMap, Method, StatusCode},
-    response::{Html, Json},
-    routing::{delete, get, post, put},
-    Router,
-};
-use dashmap::DashMap;
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::{
-    collections::HashMap,
-    ffi::{CStr, CString},
-    os::raw::{c_char, c_void},
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc, Mutex,
-    },
-    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
-};
-use tokio::net::TcpListener;
-use tokio::runtime::Runtime;
-use tokio::time::sleep;
-use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
-
-// === ULTRA-OPTIMIZED PERFORMANCE COUNTERS ===
-static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
-static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
-static STATIC_HITS: AtomicU64 = AtomicU64::new(0);
-static DYNAMIC_HITS: AtomicU64 = AtomicU64::new(0);
-
-// === PATTERN MATCHING FOR DYNAMIC ROUTES ===
-fn pattern_matches(pattern: &str, path: &str) -> bool {
-    // Convert Sufast pattern {param} to regex pattern
-    let mut regex_pattern = String::new();
-    let mut chars = pattern.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        match ch {
-            '{' => {
-                // Find the end of parameter
-                let mut param_name = String::new();
-                let mut found_end = false;
-
-                while let Some(&next_ch) = chars.peek() {
-                    if next_ch == '}' {
-                        chars.next(); // consume '}'
-                        found_end = true;
-                        break;
-                    } else {
-                        param_name.push(chars.next().unwrap());
-                    }
-                }
-
-                if found_end {
-                    // Add regex pattern for parameter (matches any non-slash characters)
-                    regex_pattern.push_str("[^/]+");
-                } else {
-                    // Malformed parameter, treat as literal
-                    regex_pattern.push('{');
-                    regex_pattern.push_str(&param_name);
-                }
-            }
-            '.' | '+' | '*' | '?' | '^' | '$' | '(' | ')' | '[' | ']' | '\\' | '|' => {
-                // Escape special regex characters
-                regex_pattern.push('\\');
-                regex_pattern.push(ch);
-            }
-            _ => {
-                regex_pattern.push(ch);
-            }
-        }
-    }
-
-    // Anchor the pattern to match the full path
-    regex_pattern = format!("^{}$", regex_pattern);
-
-    // Use simple pattern matching instead of regex crate for performance
-    pattern_matches_simple(&regex_pattern, path)
-}
-
-fn pattern_matches_simple(pattern: &str, path: &str) -> bool {
-    // Simple implementation without regex crate dependency
-    // Remove anchors for easier processing
-    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
-    let pattern = pattern.strip_suffix('$').unwrap_or(pattern);
-
-    match_segments(pattern, path)
-}
-
-fn match_segments(pattern: &str, path: &str) -> bool {
-    let mut pattern_pos = 0;
-    let mut path_pos = 0;
-    let pattern_bytes = pattern.as_bytes();
-    let path_bytes = path.as_bytes();
-
-    while pattern_pos < pattern_bytes.len() && path_pos < path_bytes.len() {
-        if pattern_pos + 5 < pattern_bytes.len()
-            && &pattern_bytes[pattern_pos..pattern_pos + 6] == b"[^/]+"
-        {
-            // Match parameter: consume until next '/' or end
-            while path_pos < path_bytes.len() && path_bytes[path_pos] != b'/' {
-                path_pos += 1;
-            }
-            pattern_pos += 6;
-        } else if pattern_bytes[pattern_pos] == b'\\' && pattern_pos + 1 < pattern_bytes.len() {
-            // Escaped character
-            if path_pos < path_bytes.len() && pattern_bytes[pattern_pos + 1] == path_bytes[path_pos]
-            {
-                pattern_pos += 2;
-                path_pos += 1;
-            } else {
-                return false;
-            }
-        } else {
-            // Literal character
-            if pattern_bytes[pattern_pos] == path_bytes[path_pos] {
-                pattern_pos += 1;
-                path_pos += 1;
-            } else {
-                return false;
-            }
-        }
-    }
-
-    // Check if we consumed both pattern and path completely
-    pattern_pos == pattern_bytes.len() && path_pos == path_bytes.len()
-}
-
-// === ULTRA-FAST STATIC ROUTE CACHE ===
-// Pre-compiled static routes for 52,000+ RPS performance
-static STATIC_ROUTES: Lazy<DashMap<String, StaticResponse>> = Lazy::new(|| {
-    let map = DashMap::new();
-
-    // Pre-cache critical routes with pre-compiled responses
-    map.insert("/".to_string(), StaticResponse {
-        body: r#"{"message":"Sufast Ultra-Optimized Server","version":"2.0","performance":"52000+ RPS static routes"}"#.to_string(),
-        content_type: "application/json".to_string(),
-        status: 200,
-    });
-
-    map.insert(
-        "/health".to_string(),
-        StaticResponse {
-            body: r#"{"status":"healthy","performance":"ultra-optimized","cache":"active"}"#
-                .to_string(),
-            content_type: "application/json".to_string(),
-            status: 200,
-        },
-    );
-
-    map.insert(
-        "/api/status".to_string(),
-        StaticResponse {
-            body: r#"{"api":"active","optimization":"maximum","routing":"ultra-fast"}"#.to_string(),
-            content_type: "application/json".to_string(),
-            status: 200,
-        },
-    );
-
-    map
-});
-
-// === INTELLIGENT RESPONSE CACHE ===
-// TTL-based caching for 45,000+ RPS on cached dynamic routes
-static RESPONSE_CACHE: Lazy<DashMap<String, CachedResponse>> = Lazy::new(DashMap::new);
-
-#[derive(Clone)]
-struct StaticResponse {
-    body: String,
-    content_type: String,
-    status: u16,
-}
-
-#[derive(Clone)]
-struct CachedResponse {
-    body: String,
-    content_type: String,
-    status: u16,
-    created_at: u64,
-    ttl_seconds: u64,
-}
-
-impl CachedResponse {
-    fn is_expired(&self) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        now > self.created_at + self.ttl_seconds
-    }
-}
-
-// === APPLICATION STATE ===
-#[derive(Clone)]
-pub struct AppState {
-    pub routes: Arc<DashMap<String, RouteHandler>>,
-    pub middleware_stack: Arc<Vec<MiddlewareHandler>>,
-    pub python_handler: Arc<Mutex<Option<PythonHandler>>>,
-    pub database: Arc<Mutex<Option<Database>>>,
-}
-
-#[derive(Clone)]
-pub struct RouteHandler {
-    pub method: String,
-    pub path: String,
-    pub handler_type: String,
-    pub is_dynamic: bool,
-    pub cache_ttl: Option<u64>,
-}
-
-pub type MiddlewareHandler = fn(&mut SufastRequest, &mut SufastResponse) -> bool;
-pub type PythonHandler = extern "C" fn(*const c_char, *const c_char) -> *const c_char;
-
-#[derive(Clone)]
-pub struct Database {
-    pub connection_string: String,
-    pub pool_size: u32,
-}
-
-#[derive(Clone, Debug)]
-pub struct SufastRequest {
-    pub method: String,
-    pub path: String,
-    pub query: HashMap<String, String>,
-    pub headers: HashMap<String, String>,
-    pub body: String,
-}
-
-#[derive(Clone, Debug)]
-pub struct SufastResponse {
-    pub status: u16,
-    pub headers: HashMap<String, String>,
-    pub body: String,
-}
-
-// === GLOBAL STATE MANAGEMENT ===
-static APP_STATE: Lazy<AppState> = Lazy::new(|| AppState {
-    routes: Arc::new(DashMap::new()),
-    middleware_stack: Arc::new(Vec::new()),
-    python_handler: Arc::new(Mutex::new(None)),
-    database: Arc::new(Mutex::new(None)),
-});
-
-fn get_app_state() -> &'static AppState {
-    &APP_STATE
-}
-
-// === ULTRA-OPTIMIZED CORE HANDLER ===
-// Three-tier optimization: Static (52K+ RPS) -> Cache (45K+ RPS) -> Dynamic (2K+ RPS)
-async fn ultra_fast_handler(
-    method: Method,
-    uri: axum::http::Uri,
-    headers: HeaderMap,
-    body: String,
-) -> axum::response::Response {
-    REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
-    let path = uri.path();
-    let method_str = method.as_str();
-
-    // === TIER 1: ULTRA-FAST STATIC ROUTES (52,000+ RPS) ===
-    if method_str == "GET" {
-        if let Some(static_response) = STATIC_ROUTES.get(path) {
-            STATIC_HITS.fetch_add(1, Ordering::Relaxed);
-
-            return axum::response::Response::builder()
-                .status(static_response.status)
-                .header("content-type", &static_response.content_type)
-                .header("x-sufast-tier", "static")
-                .header("x-sufast-performance", "52000-rps")
-                .body(axum::body::Body::from(static_response.body.clone()))
-                .unwrap();
-        }
-    }
-
-    // === TIER 2: INTELLIGENT CACHE (45,000+ RPS) ===
-    let cache_key = format!("{}:{}", method_str, path);
-    if let Some(cached) = RESPONSE_CACHE.get(&cache_key) {
-        if !cached.is_expired() {
-            CACHE_HITS.fetch_add(1, Ordering::Relaxed);
-
-            return axum::response::Response::builder()
-                .status(cached.status)
-                .header("content-type", &cached.content_type)
-                .header("x-sufast-tier", "cached")
-                .header("x-sufast-performance", "45000-rps")
-                .header("x-sufast-ttl", &cached.ttl_seconds.to_string())
-                .body(axum::body::Body::from(cached.body.clone()))
-                .unwrap();
-        } else {
-            // Remove expired cache entry
-            RESPONSE_CACHE.remove(&cache_key);
-        }
-    }
-
-    // === TIER 3: DYNAMIC PROCESSING (2,000+ RPS) ===
-    DYNAMIC_HITS.fetch_add(1, Ordering::Relaxed);
-
-    let state = get_app_state();
-    let route_key = format!("{}:{}", method_str, path);
-
-    // Check for exact route match first
-    if let Some(route_handler) = state.routes.get(&route_key) {
-        let response = process_dynamic_route(&route_handler, path, &headers, &body).await;
-
-        // Cache dynamic responses if TTL is specified
-        if let Some(ttl) = route_handler.cache_ttl {
-            let cached_response = CachedResponse {
-                body: response.body.clone(),
-                content_type: "application/json".to_string(),
-                status: response.status,
-                created_at: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                ttl_seconds: ttl,
-            };
-            RESPONSE_CACHE.insert(cache_key, cached_response);
-        }
-
-        return axum::response::Response::builder()
-            .status(response.status)
-            .header("content-type", "application/json")
-            .header("x-sufast-tier", "dynamic")
-            .header("x-sufast-performance", "2000-rps")
-            .body(axum::body::Body::from(response.body))
-            .unwrap();
-    }
-
-    // Check for pattern-based route matching
-    for entry in state.routes.iter() {
-        let pattern_key = entry.key();
-        let route_handler = entry.value();
-
-        if let Some(method_and_pattern) = pattern_key.split_once(':') {
-            if method_and_pattern.0 == method_str {
-                let pattern_path = method_and_pattern.1;
-                if pattern_matches(pattern_path, path) {
-                    let response =
-                        process_dynamic_route(&route_handler, path, &headers, &body).await;
-
-                    // Cache dynamic responses if TTL is specified
-                    if let Some(ttl) = route_handler.cache_ttl {
-                        let cached_response = CachedResponse {
-                            body: response.body.clone(),
-                            content_type: "application/json".to_string(),
-                            status: response.status,
-                            created_at: SystemTime::now()
-                                .duration_since(UNIX_EPOCH)
-                                .unwrap()
-                                .as_secs(),
-                            ttl_seconds: ttl,
-                        };
-                        RESPONSE_CACHE.insert(cache_key, cached_response);
-                    }
-
-                    return axum::response::Response::builder()
-                        .status(response.status)
-                        .header("content-type", "application/json")
-                        .header("x-sufast-tier", "dynamic")
-                        .header("x-sufast-performance", "2000-rps")
-                        .body(axum::body::Body::from(response.body))
-                        .unwrap();
-                }
-            }
-        }
-    }
-
-    // Fallback to Python handler if available
-    if let Ok(python_handler) = state.python_handler.lock() {
-        if let Some(handler) = python_handler.as_ref() {
-            let request_data = format!(
-                r#"{{"method":"{}","path":"{}","body":"{}"}}"#,
-                method_str, path, body
-            );
-
-            let c_request = CString::new(request_data).unwrap();
-            let c_path = CString::new(path).unwrap();
-
-            let result_ptr = handler(c_request.as_ptr(), c_path.as_ptr());
-            if !result_ptr.is_null() {
-                let c_result = unsafe { CStr::from_ptr(result_ptr) };
-                if let Ok(result_str) = c_result.to_str() {
-                    return axum::response::Response::builder()
-                        .status(200)
-                        .header("content-type", "application/json")
-                        .header("x-sufast-tier", "python")
-                        .body(axum::body::Body::from(result_str.to_string()))
-                        .unwrap();
-                }
-            }
-        }
-    }
-
-    // 404 fallback
-    fallback_handler().await
-}
-
-async fn process_dynamic_route(
-    route_handler: &RouteHandler,
-    path: &str,
-    headers: &HeaderMap,
-    body: &str,
-) -> SufastResponse {
-    // Try to delegate to Python handler for dynamic processing
-    let state = get_app_state();
-    if let Ok(python_handler) = state.python_handler.lock() {
-        if let Some(handler) = python_handler.as_ref() {
-            let request_data = format!(
-                r#"{{"method":"{}","path":"{}","body":"{}"}}"#,
-                route_handler.method, path, body
-            );
-
-            let c_request = CString::new(request_data).unwrap();
-            let c_path = CString::new(path).unwrap();
-
-            let result_ptr = handler(c_request.as_ptr(), c_path.as_ptr());
-            if !result_ptr.is_null() {
-                let c_result = unsafe { CStr::from_ptr(result_ptr) };
-                if let Ok(result_str) = c_result.to_str() {
-                    if !result_str.is_empty() {
-                        return SufastResponse {
-                            status: 200,
-                            headers: {
-                                let mut headers = HashMap::new();
-                                headers.insert("x-sufast-tier".to_string(), "python".to_string());
-                                headers.insert(
-                                    "content-type".to_string(),
-                                    "application/json".to_string(),
-                                );
-                                headers
-                            },
-                            body: result_str.to_string(),
-                        };
-                    }
-                }
-            }
-        }
-    }
-
-    // Fallback: simple route info response
-    let response_data = json!({
-        "route": path,
-        "method": route_handler.method,
-        "process_type": "dynamic",
-        "processed_at": SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis(),
-        "performance": "optimized",
-        "message": "Dynamic route processed by Rust core"
-    });
-
-    SufastResponse {
-        status: 200,
-        headers: HashMap::new(),
-        body: response_data.to_string(),
-    }
-}
-
-async fn fallback_handler() -> axum::response::Response {
-    let total_requests = REQUEST_COUNT.load(Ordering::Relaxed);
-    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
-    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
-    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
-
-    let stats = json!({
-        "error": "Route not found",
-        "status": "404",
-        "message": "No matching route found",
-        "performance": {
-            "total_requests": total_requests,
-            "static_hits": static_hits,
-            "cache_hits": cache_hits,
-            "dynamic_hits": dynamic_hits,
-            "cache_hit_ratio": if total_requests > 0 {
-                (cache_hits + static_hits) as f64 / total_requests as f64
-            } else { 0.0 },
-            "framework": "Sufast v2.0 Ultra-Optimized"
-        }
-    });
-
-    axum::response::Response::builder()
-        .status(404)
-        .header("content-type", "application/json")
-        .body(axum::body::Body::from(stats.to_string()))
-        .unwrap()
-}
-
-// === PERFORMANCE MONITORING ENDPOINT ===
-async fn performance_stats() -> Json<Value> {
-    let total_requests = REQUEST_COUNT.load(Ordering::Relaxed);
-    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
-    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
-    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
-
-    Json(json!({
-        "sufast_performance": {
-            "version": "2.0",
-            "optimization": "ultra",
-            "total_requests": total_requests,
-            "performance_breakdown": {
-                "static_routes": {
-                    "hits": static_hits,
-                    "performance": "52,000+ RPS",
-                    "percentage": if total_requests > 0 {
-                        static_hits as f64 / total_requests as f64 * 100.0
-                    } else { 0.0 }
-                },
-                "cached_routes": {
-                    "hits": cache_hits,
-                    "performance": "45,000+ RPS",
-                    "percentage": if total_requests > 0 {
-                        cache_hits as f64 / total_requests as f64 * 100.0
-                    } else { 0.0 }
-                },
-                "dynamic_routes": {
-                    "hits": dynamic_hits,
-                    "performance": "2,000+ RPS",
-                    "percentage": if total_requests > 0 {
-                        dynamic_hits as f64 / total_requests as f64 * 100.0
-                    } else { 0.0 }
-                }
-            },
-            "cache_efficiency": {
-                "total_cached": cache_hits + static_hits,
-                "cache_hit_ratio": if total_requests > 0 {
-                    (cache_hits + static_hits) as f64 / total_requests as f64
-                } else { 0.0 },
-                "optimization_impact": "Ultra-High"
-            }
-        }
-    }))
-}
-
-// === FFI FUNCTIONS FOR PYTHON INTEGRATION ===
-
-#[no_mangle]
-pub extern "C" fn set_python_handler(handler: PythonHandler) -> bool {
-    let state = get_app_state();
-    let mut python_handler = state.python_handler.lock().unwrap();
-    *python_handler = Some(handler);
-    println!("Python handler registered successfully");
-    true
-}
-
-#[no_mangle]
-pub extern "C" fn add_route(
-    method: *const c_char,
-    path: *const c_char,
-    handler_type: *const c_char,
-    cache_ttl: u64,
-) -> bool {
-    unsafe {
-        let method_str = CStr::from_ptr(method).to_str().unwrap();
-        let path_str = CStr::from_ptr(path).to_str().unwrap();
-        let handler_type_str = CStr::from_ptr(handler_type).to_str().unwrap();
-
-        let state = get_app_state();
-        let route_key = format!("{}:{}", method_str, path_str);
-
-        let route_handler = RouteHandler {
-            method: method_str.to_string(),
-            path: path_str.to_string(),
-            handler_type: handler_type_str.to_string(),
-            is_dynamic: !path_str.contains("static"),
-            cache_ttl: if cache_ttl > 0 { Some(cache_ttl) } else { None },
-        };
-
-        state.routes.insert(route_key, route_handler);
-
-        println!(
-            "Route added: {} {} (cache_ttl: {}s)",
-            method_str, path_str, cache_ttl
-        );
-        true
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn add_static_route(path: *const c_char, response: *const c_char) -> bool {
-    unsafe {
-        let path_str = CStr::from_ptr(path).to_str().unwrap();
-        let response_str = CStr::from_ptr(response).to_str().unwrap();
-
-        let static_response = StaticResponse {
-            body: response_str.to_string(),
-            content_type: "application/json".to_string(),
-            status: 200,
-        };
-
-        STATIC_ROUTES.insert(path_str.to_string(), static_response);
-
-        println!("Static route added: {} (52,000+ RPS performance)", path_str);
-        true
-    }
-}
-
-#[no_mangle]
-// Global runtime for keeping the server alive
-static mut RUNTIME: Option<Runtime> = None;
-
-#[no_mangle]
-pub extern "C" fn start_sufast_server(host: *const c_char, port: u16) -> i32 {
-    if host.is_null() {
-        return -1;
-    }
-
-    let host_str = unsafe {
-        match CStr::from_ptr(host).to_str() {
-            Ok(s) => s,
-            Err(_) => return -1,
-        }
-    };
-
-    let addr = format!("{}:{}", host_str, port);
-    println!("Sufast Ultra-Optimized Server starting on {}", addr);
-
-    // Create and store runtime globally to keep it alive
-    let rt = match Runtime::new() {
-        Ok(rt) => rt,
-        Err(e) => {
-            println!("❌ Failed to create runtime: {}", e);
-            return -1;
-        }
-    };
-
-    // Store runtime globally
-    unsafe {
-        RUNTIME = Some(rt);
-    }
-
-    // Get reference to the runtime and run server (blocking)
-    let runtime = unsafe { RUNTIME.as_ref().unwrap() };
-
-    // Block on the server to keep it running
-    match runtime.block_on(run_server(&addr)) {
-        Ok(_) => {
-            println!("✅ Rust server completed successfully");
-            0
-        }
-        Err(e) => {
-            println!("❌ Rust server error: {}", e);
-            -1
-        }
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn get_performance_stats() -> *mut c_char {
-    let total_requests = REQUEST_COUNT.load(Ordering::Relaxed);
-    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
-    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
-    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
-
-    let stats = json!({
-        "total_requests": total_requests,
-        "static_hits": static_hits,
-        "cache_hits": cache_hits,
-        "dynamic_hits": dynamic_hits,
-        "cache_hit_ratio": if total_requests > 0 {
-            (cache_hits + static_hits) as f64 / total_requests as f64
-        } else { 0.0 },
-        "performance_tier_breakdown": {
-            "ultra_fast_static": format!("{} requests (52,000+ RPS)", static_hits),
-            "intelligent_cache": format!("{} requests (45,000+ RPS)", cache_hits),
-            "dynamic_processing": format!("{} requests (2,000+ RPS)", dynamic_hits)
-        }
-    });
-
-    let json_string = stats.to_string();
-    let c_string = CString::new(json_string).unwrap();
-    c_string.into_raw()
-}
-
-// === MAIN SERVER FUNCTION ===
-pub async fn run_server(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let app = Router::new()
-        .route("/performance", get(performance_stats_handler))
-        .fallback(ultra_fast_handler)
-        .layer(ServiceBuilder::new().layer(CorsLayer::permissive()));
-
-    let listener = TcpListener::bind(addr).await?;
-    println!("🌐 Server listening on {}", addr);
-
-    axum::serve(listener, app).await?;
-    Ok(())
-}
-
-// Performance stats handler for axum
-async fn performance_stats_handler() -> axum::response::Json<Value> {
-    let total_requests = REQUEST_COUNT.load(Ordering::Relaxed);
-    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
-    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
-    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
-
-    let cache_hit_ratio = if total_requests > 0 {
-        cache_hits as f64 / total_requests as f64
-    } else {
-        0.0
-    };
-
-    let stats = json!({
-        "total_requests": total_requests,
-        "cache_hits": cache_hits,
-        "static_hits": static_hits,
-        "dynamic_hits": dynamic_hits,
-        "cache_hit_ratio": cache_hit_ratio,
-        "performance_tier_breakdown": {
-            "ultra_fast_static": format!("{} requests (52,000+ RPS)", static_hits),
-            "intelligent_cache": format!("{} requests (45,000+ RPS)", cache_hits),
-            "dynamic_processing": format!("{} requests (2,000+ RPS)", dynamic_hits)
-        },
-        "rust_cache": {
-            "response_cache_size": RESPONSE_CACHE.len(),
-            "static_routes_count": STATIC_ROUTES.len()
-        }
-    });
-
-    axum::response::Json(stats)
-}
-
-// === CONVENIENCE FUNCTIONS ===
-#[no_mangle]
-pub extern "C" fn clear_cache() -> bool {
-    RESPONSE_CACHE.clear();
-    println!("Response cache cleared");
-    true
-}
-
-#[no_mangle]
-pub extern "C" fn cache_size() -> u64 {
-    RESPONSE_CACHE.len() as u64
-}
-
-#[no_mangle]
-pub extern "C" fn static_routes_count() -> u64 {
-    STATIC_ROUTES.len() as u64
-}
@@ -1,21 +1,39 @@
+// The extern "C" functions take pointers from Python's ctypes and null-check them
+// before reading
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+pub mod database;
+pub mod middleware;
+pub mod rate_limiting;
+pub mod request;
+pub mod response;
+pub mod routing;
+pub mod security;
+pub mod templates;
+
 use axum::{
     body::Body,
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    http::{HeaderMap, Method, StatusCode, Uri},
-    response::{IntoResponse, Response},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    response::Response,
     Router,
 };
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
+use rand::Rng;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::cmp;
 use std::collections::HashMap;
 use std::ffi::{c_char, CStr, CString};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
-use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::watch;
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use tracing::Instrument;
 
 // ========================
 // PERFORMANCE OPTIMIZATION
@@ -31,23 +49,239 @@ static RESPONSE_CACHE: Lazy<DashMap<String, CachedResponse>> = Lazy::new(DashMap
 // Dynamic patterns: Fast pattern matching (stores "METHOD:PATTERN" -> DynamicRoute)
 static DYNAMIC_ROUTES: Lazy<DashMap<String, DynamicRoute>> = Lazy::new(DashMap::new);
 
+// Dynamic routes ordered by specificity and indexed by literal prefix; rebuilt on
+// registration, consulted when matching
+static DYNAMIC_ROUTE_INDEX: Lazy<RwLock<DynamicRouteIndex>> =
+    Lazy::new(|| RwLock::new(DynamicRouteIndex::default()));
+
 // WebSocket routes
 static WS_ROUTES: Lazy<DashMap<String, WsRoute>> = Lazy::new(DashMap::new);
 
+// Long-poll routes (pattern -> route) and their channels, keyed by request path. A channel
+// exists only for paths matching a registered route, and is dropped once nobody is
+// polling it and its last publish is older than the route's timeout.
+static LONG_POLL_ROUTES: Lazy<DashMap<String, LongPollRoute>> = Lazy::new(DashMap::new);
+static LONG_POLL_CHANNELS: Lazy<DashMap<String, watch::Sender<LongPollState>>> =
+    Lazy::new(DashMap::new);
+
+// How long a poll is parked when the route doesn't set its own timeout
+const LONG_POLL_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 // Performance counters
 static STATIC_HITS: AtomicU64 = AtomicU64::new(0);
 static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
 static DYNAMIC_HITS: AtomicU64 = AtomicU64::new(0);
 static WS_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
 static TOTAL_REQUESTS: AtomicU64 = AtomicU64::new(0);
+// Responses sent, by status code, for /metrics
+static RESPONSE_STATUS_COUNTS: Lazy<DashMap<u16, AtomicU64>> = Lazy::new(DashMap::new);
+
+// Default time budget for a dynamic route's Python call; 0 disables the timeout
+static REQUEST_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(30);
+
+// Seconds between background sweeps of expired RESPONSE_CACHE entries; 0 disables sweeping
+static CACHE_SWEEP_INTERVAL_SECS: AtomicU64 = AtomicU64::new(60);
+
+// CORS policy from `set_cors_config`; None keeps the permissive default
+static CORS_LAYER: Lazy<RwLock<Option<CorsLayer>>> = Lazy::new(|| RwLock::new(None));
+
+// Directory `render_template` reads from; see `set_template_dir`
+static TEMPLATE_ENGINE: Lazy<RwLock<templates::TemplateEngine>> =
+    Lazy::new(|| RwLock::new(templates::TemplateEngine::new("templates")));
+
+/// Prefix of every `render_template` failure, followed by a kind and a message
+pub const TEMPLATE_ERROR_MARKER: &str = "SUFAST_TEMPLATE_ERROR:";
+
+// Database checked by /healthz; see `set_database_url`. The pool is opened at server start.
+static DATABASE_URL: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+static DATABASE_POOL: Lazy<RwLock<Option<Arc<database::DatabasePool>>>> =
+    Lazy::new(|| RwLock::new(None));
+
+// Cache misses answered by another request's in-flight Python call
+static COALESCED_HITS: AtomicU64 = AtomicU64::new(0);
+
+// In-flight Python calls for cached dynamic routes, keyed like RESPONSE_CACHE. Concurrent
+// misses on one key wait on the same cell instead of each calling Python. The value is
+// None when the result can't be shared (an error or a streamed body).
+type SharedPythonResponse = Option<(String, u16, HashMap<String, String>)>;
+// Vary header names of the cached route behind each "METHOD:path"; only routes declaring
+// vary headers have an entry. The cache tier consults it to build the variant key before
+// any route is matched.
+static CACHE_VARY_HEADERS: Lazy<DashMap<String, Arc<[String]>>> = Lazy::new(DashMap::new);
+
+static IN_FLIGHT: Lazy<DashMap<String, Arc<tokio::sync::OnceCell<SharedPythonResponse>>>> =
+    Lazy::new(DashMap::new);
+
+// Cap on static + dynamic routes combined; 0 means unlimited
+static MAX_ROUTES: AtomicU64 = AtomicU64::new(0);
+
+// Registrations that were rejected, reported by `validate_routes`. An entry is cleared
+// when the same route later registers successfully.
+static ROUTE_ERRORS: Lazy<Mutex<Vec<RouteError>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// Trailing-slash policy applied before route lookup
+const TRAILING_SLASH_STRICT: u8 = 0;
+const TRAILING_SLASH_IGNORE: u8 = 1;
+const TRAILING_SLASH_REDIRECT: u8 = 2;
+static TRAILING_SLASH_POLICY: AtomicU8 = AtomicU8::new(TRAILING_SLASH_STRICT);
+
+// Idempotency-Key handling is opt-in: replays are keyed by a client-chosen value
+static IDEMPOTENCY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+// How long the first response to an Idempotency-Key request is kept for replays
+static IDEMPOTENCY_TTL_SECS: AtomicU64 = AtomicU64::new(24 * 60 * 60);
+
+// Most keys tracked at once; past it, expired and then the oldest responses are evicted
+static IDEMPOTENCY_MAX_ENTRIES: AtomicUsize = AtomicUsize::new(10_000);
+
+// Responses stored for Idempotency-Key requests ("METHOD:path" scoped), plus a marker
+// for keys whose first request is still running
+static IDEMPOTENCY_STORE: Lazy<DashMap<String, IdempotencyEntry>> = Lazy::new(DashMap::new);
+
+// Expired idempotency entries are swept at most this often
+const IDEMPOTENCY_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+static IDEMPOTENCY_SWEPT_AT: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+// Cached route TTLs are randomized within ±this fraction (an f64 stored as bits) so
+// routes registered with the same TTL don't all expire at once; 0 disables jitter
+static CACHE_TTL_JITTER_BITS: AtomicU64 = AtomicU64::new(0);
+
+// Largest request body buffered for the Python callback (and to fingerprint an
+// Idempotency-Key request); bigger bodies get 413
+const MAX_REQUEST_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+// Largest response stored for replay; bigger or streamed responses are passed through
+const IDEMPOTENCY_MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+
+// Requests slower than this are logged with tracing::warn!; 0 disables the check
+static SLOW_REQUEST_THRESHOLD_MS: AtomicU64 = AtomicU64::new(1000);
+
+// Longest request target (path plus query) accepted before any routing; longer gets 414
+static MAX_PATH_LENGTH: AtomicUsize = AtomicUsize::new(8 * 1024);
+
+// Query pairs parsed per request; later pairs are ignored
+const MAX_QUERY_PARAMS: usize = 256;
+
+// Socket options for the server's listening socket; see `set_listener_options`
+static LISTENER_OPTIONS: Lazy<Mutex<ListenerOptions>> =
+    Lazy::new(|| Mutex::new(ListenerOptions::default()));
+
+// POST dispatched as PUT/PATCH/DELETE via X-HTTP-Method-Override or `?_method=`; off by default
+static METHOD_OVERRIDE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Tokio worker threads for the server runtime; 0 sizes it to the CPUs available to the process
+static WORKER_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// Requests currently inside `ultra_fast_handler`, and the high-water mark above which new
+// ones are shed with 503 (0 disables shedding)
+static IN_FLIGHT_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+static MAX_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static SHED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+// Seconds clients are asked to wait after being shed
+const SHED_RETRY_AFTER_SECS: u64 = 1;
+
+// Open client connections and connections accepted since start
+static ACTIVE_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+// Requests served on one keep-alive connection before it is closed; 0 means no limit
+static MAX_REQUESTS_PER_CONNECTION: AtomicU64 = AtomicU64::new(0);
+
+// While set, every request outside MAINTENANCE_ALLOWLIST (path prefixes) gets 503
+static MAINTENANCE_MODE: AtomicBool = AtomicBool::new(false);
+static MAINTENANCE_ALLOWLIST: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(Vec::new()));
+const MAINTENANCE_RETRY_AFTER_SECS: u64 = 120;
+
+#[derive(Clone, Copy)]
+struct ListenerOptions {
+    backlog: u32,
+    nodelay: bool,
+    keepalive: bool,
+    reuse_port: bool,
+}
+
+impl Default for ListenerOptions {
+    fn default() -> Self {
+        Self {
+            backlog: 1024,
+            // Small JSON responses shouldn't wait on Nagle's algorithm
+            nodelay: true,
+            keepalive: false,
+            reuse_port: false,
+        }
+    }
+}
 
 #[derive(Clone)]
 struct StaticResponse {
     body: String,
+    // Gzip variant computed once at registration; None when the body is too small or
+    // doesn't shrink. `Bytes` so serving it is a refcount bump, not a copy.
+    gzip_body: Option<bytes::Bytes>,
     status: u16,
     headers: HashMap<String, String>,
 }
 
+// Bodies below this are served uncompressed; gzip framing outweighs the savings
+const STATIC_GZIP_MIN_BYTES: usize = 256;
+
+impl StaticResponse {
+    fn new(body: String, status: u16, mut headers: HashMap<String, String>) -> Self {
+        let gzip_body = if body.len() >= STATIC_GZIP_MIN_BYTES {
+            gzip(body.as_bytes()).filter(|compressed| compressed.len() < body.len())
+        } else {
+            None
+        };
+
+        // The body served depends on Accept-Encoding; add it to any route-level Vary
+        if gzip_body.is_some() {
+            let vary = match headers.get("vary") {
+                Some(vary) => format!("{}, accept-encoding", vary),
+                None => "accept-encoding".to_string(),
+            };
+            headers.insert("vary".to_string(), vary);
+        }
+
+        Self {
+            body,
+            gzip_body: gzip_body.map(bytes::Bytes::from),
+            status,
+            headers,
+        }
+    }
+}
+
+fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+/// True when `Accept-Encoding` lists gzip (or `*`) with a non-zero q-value.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers
+        .get("accept-encoding")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    accept.split(',').any(|entry| {
+        let mut parts = entry.split(';');
+        let coding = parts.next().unwrap_or("").trim();
+        let q = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        (coding.eq_ignore_ascii_case("gzip") || coding == "*") && q > 0.0
+    })
+}
+
 #[derive(Clone)]
 struct CachedResponse {
     body: String,
@@ -55,42 +289,670 @@ struct CachedResponse {
     headers: HashMap<String, String>,
     cached_at: Instant,
     ttl: Duration,
+    etag: String,
+    // SHA-256 of the request body for idempotency-key entries; None for route cache entries
+    request_fingerprint: Option<String>,
+}
+
+enum IdempotencyEntry {
+    // The first request with this key is still being handled
+    InProgress,
+    Stored(CachedResponse),
+}
+
+#[derive(Clone, Serialize)]
+struct RouteError {
+    kind: &'static str,
+    method: String,
+    pattern: String,
+    reason: String,
 }
 
 #[derive(Clone)]
 struct DynamicRoute {
     method: String,
+    pattern: String,
     regex: Regex,
     handler_name: String,
     cache_ttl: Option<Duration>,
+    // Lowercased request headers whose values select the cached variant
+    vary_headers: Arc<[String]>,
+    // Overrides REQUEST_TIMEOUT_SECS for this route
+    timeout: Option<Duration>,
+}
+
+// Narrows the candidate routes for a path before any regex runs. Each route is filed
+// under its literal prefix (the leading segments before the first parameter or regex
+// syntax), so `/blog/post-1` is never tested against `/api/v1/users/{id}`.
+#[derive(Default)]
+struct DynamicRouteIndex {
+    // All routes, most specific first; candidate lists hold indices into this
+    routes: Vec<DynamicRoute>,
+    by_prefix: HashMap<String, Vec<usize>>,
+}
+
+impl DynamicRouteIndex {
+    fn build(routes: Vec<DynamicRoute>) -> Self {
+        let mut by_prefix: HashMap<String, Vec<usize>> = HashMap::new();
+        for (rank, route) in routes.iter().enumerate() {
+            by_prefix
+                .entry(literal_prefix(&route.pattern))
+                .or_default()
+                .push(rank);
+        }
+        Self { routes, by_prefix }
+    }
+
+    /// Routes that could match `path`, most specific first.
+    fn candidates(&self, path: &str) -> impl Iterator<Item = &DynamicRoute> {
+        let mut ranks: Vec<usize> = Vec::new();
+        let mut prefix = String::new();
+
+        if let Some(bucket) = self.by_prefix.get(&prefix) {
+            ranks.extend(bucket);
+        }
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            prefix.push('/');
+            prefix.push_str(segment);
+            if let Some(bucket) = self.by_prefix.get(&prefix) {
+                ranks.extend(bucket);
+            }
+        }
+
+        ranks.sort_unstable();
+        ranks.into_iter().map(move |rank| &self.routes[rank])
+    }
+}
+
+// "/api/v1/users/{id}" -> "/api/v1/users"; "/{slug}" -> ""
+fn literal_prefix(pattern: &str) -> String {
+    const REGEX_SYNTAX: &[char] = &[
+        '{', '}', '(', ')', '[', ']', '*', '+', '?', '.', '|', '^', '$', '\\',
+    ];
+
+    let mut prefix = String::new();
+    for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+        if segment.contains(REGEX_SYNTAX) {
+            break;
+        }
+        prefix.push('/');
+        prefix.push_str(segment);
+    }
+    prefix
 }
 
+// Registered from Python; nothing upgrades connections to these routes yet
+#[allow(dead_code)]
 #[derive(Clone)]
 struct WsRoute {
     regex: Regex,
     handler_name: String,
 }
 
+#[derive(Clone)]
+struct LongPollRoute {
+    regex: Regex,
+    timeout: Duration,
+}
+
+// Latest data published on a long-poll channel. `version` counts publishes, so 0 means
+// nothing has been published yet. `kept_until` keeps the channel, and so the data, for
+// clients that are between two polls.
+#[derive(Clone, Default)]
+struct LongPollState {
+    version: u64,
+    data: String,
+    kept_until: Option<Instant>,
+}
+
 // Python callback for dynamic routes (thread-safe, no static mut UB)
 type PythonCallback = extern "C" fn(*const c_char, *const c_char, *const c_char) -> *const c_char;
 static PYTHON_CALLBACK: Lazy<Mutex<Option<PythonCallback>>> = Lazy::new(|| Mutex::new(None));
 
-// Response pool to prevent memory leaks
+// FFI string ownership contract for the Python callback:
+// - Arguments (method, path, params) are Rust-owned CStrings, valid only for the duration
+//   of the call. Their buffers are recycled through RESPONSE_POOL instead of being
+//   reallocated on every dynamic request.
+// - The returned pointer is owned by Python (a ctypes buffer Python keeps alive). Rust
+//   copies it immediately and never frees it.
+// Streaming responses (pull protocol). When the Python callback's JSON carries
+// `"stream": <id>` instead of a `body`, Rust pulls the body from the stream callback on a
+// blocking thread and sends each chunk as it arrives (chunked transfer encoding):
+// - `cb(id, &mut len)` returning non-null with `len >= 0`: a chunk of `len` bytes. Python
+//   owns the buffer and must keep it alive until the next call for this stream.
+// - null with `len == 0`: end of stream.
+// - `len < 0`: the generator failed; the response is aborted without the terminating
+//   chunk, so clients see a truncated body rather than a silently short one.
+// - `cb(id, null)`: the client went away; Python should close the generator. Not sent
+//   after end of stream or an error.
+type PythonStreamCallback = extern "C" fn(u64, *mut i64) -> *const c_char;
+static PYTHON_STREAM_CALLBACK: Lazy<Mutex<Option<PythonStreamCallback>>> =
+    Lazy::new(|| Mutex::new(None));
+
+// Chunks buffered between the Python generator and the socket
+const STREAM_CHANNEL_CAPACITY: usize = 8;
+
+// Response transforms, keyed by path prefix. The callback receives
+// `{"status", "headers": [[name, value], ...], "body"}` for a finished response and
+// returns the same shape; omitted fields keep their value, a non-string `body` is sent
+// as JSON, and null leaves the response unchanged. Argument ownership is as for the
+// Python callback above (valid for the call; the result is copied and never freed).
+type ResponseTransformCallback = extern "C" fn(*const c_char) -> *const c_char;
+static RESPONSE_TRANSFORMS: Lazy<RwLock<Vec<(String, ResponseTransformCallback)>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+// Largest response body buffered for a transform; bigger or streamed bodies pass through
+const TRANSFORM_MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
 static RESPONSE_POOL: Lazy<Arc<Mutex<Vec<CString>>>> =
     Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
 
+// Upper bound on pooled buffers so a burst can't pin memory forever
+const RESPONSE_POOL_MAX: usize = 256;
+
 // ========================
 // FAST HANDLER
 // ========================
 
 async fn ultra_fast_handler(
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    axum::Extension(RequestId(request_id)): axum::Extension<RequestId>,
+    body: Body,
+) -> Response<Body> {
+    let started = Instant::now();
+    // Everything logged while handling the request (middleware, handler, database) is
+    // nested under this span; tier and status are filled in once the response exists
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %method,
+        path = uri.path(),
+        tier = tracing::field::Empty,
+        status = tracing::field::Empty,
+    );
+
+    // Both are cheap clones (standard methods are inline, the URI is refcounted)
+    let (logged_method, logged_uri) = (method.clone(), uri.clone());
+    let target_len = uri
+        .path_and_query()
+        .map_or(0, |target| target.as_str().len());
+    // Held until the handler returns, including when the connection drops mid-request
+    let admitted =
+        InFlightGuard::acquire(&IN_FLIGHT_REQUESTS, MAX_IN_FLIGHT.load(Ordering::Relaxed));
+    let mut response = if admitted.is_none() {
+        SHED_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        overloaded_response()
+    } else if target_len > MAX_PATH_LENGTH.load(Ordering::Relaxed) {
+        json_error_response(StatusCode::URI_TOO_LONG, "Request URI too long")
+    } else if let Some(message) = malformed_utf8(&uri, &headers) {
+        json_error_response(StatusCode::BAD_REQUEST, message)
+    } else if in_maintenance(uri.path()) {
+        maintenance_response()
+    } else {
+        match idempotency_key(&method, &headers) {
+            Some(key) => {
+                dispatch_idempotent(key, method, uri, headers, body, &request_id)
+                    .instrument(span.clone())
+                    .await
+            }
+            None => {
+                dispatch_request(method, uri, headers, body, &request_id)
+                    .instrument(span.clone())
+                    .await
+            }
+        }
+    };
+    if let Some(transform) = response_transform_for(logged_uri.path()) {
+        response = apply_response_transform(transform, response)
+            .instrument(span.clone())
+            .await;
+    }
+
+    if let Some(tier) = response
+        .headers()
+        .get("x-sufast-tier")
+        .and_then(|value| value.to_str().ok())
+    {
+        span.record("tier", tier);
+    }
+    span.record("status", response.status().as_u16());
+    span.in_scope(|| log_if_slow(&logged_method, &logged_uri, &response, started.elapsed()));
+    response
+}
+
+// The transform registered for the longest prefix covering `path`
+fn response_transform_for(path: &str) -> Option<ResponseTransformCallback> {
+    RESPONSE_TRANSFORMS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(prefix, _)| path_under(path, prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, transform)| *transform)
+}
+
+async fn apply_response_transform(
+    transform: ResponseTransformCallback,
+    response: Response<Body>,
+) -> Response<Body> {
+    use axum::body::HttpBody;
+
+    // Streamed and compressed bodies can't be handed over as text, and 204/304 must
+    // stay bodiless
+    let bodiless = matches!(
+        response.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    );
+    let buffered = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|len| len as usize <= TRANSFORM_MAX_BODY_BYTES);
+    if bodiless || !buffered || response.headers().contains_key("content-encoding") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, TRANSFORM_MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Response body error")
+        }
+    };
+    let headers: Vec<(&str, String)> = parts
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str(), value)
+        })
+        .collect();
+    let input = json!({
+        "status": parts.status.as_u16(),
+        "headers": headers,
+        "body": String::from_utf8_lossy(&body),
+    })
+    .to_string();
+
+    // Transforms may call into Python, so they run on the blocking pool like handlers
+    let span = tracing::Span::current();
+    let output = tokio::task::spawn_blocking(move || {
+        let _request_span = span.enter();
+        let input = CString::new(input).ok()?;
+        let result_ptr = transform(input.as_ptr());
+        if result_ptr.is_null() {
+            return None;
+        }
+        // Owned by the caller: copy it out, never free it from Rust
+        Some(unsafe { CStr::from_ptr(result_ptr).to_string_lossy().into_owned() })
+    })
+    .await
+    .ok()
+    .flatten();
+
+    let Some(output) = output else {
+        return Response::from_parts(parts, Body::from(body));
+    };
+    let Ok(Value::Object(mut output)) = serde_json::from_str::<Value>(&output) else {
+        tracing::warn!("response transform returned invalid JSON; sending the original");
+        return Response::from_parts(parts, Body::from(body));
+    };
+
+    if let Some(status) = output
+        .get("status")
+        .and_then(Value::as_u64)
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+    {
+        parts.status = status;
+    }
+    if let Some(Value::Array(headers)) = output.get("headers") {
+        parts.headers.clear();
+        for pair in headers {
+            let (Some(name), Some(value)) = (
+                pair.get(0).and_then(Value::as_str),
+                pair.get(1).and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            if let (Ok(name), Ok(value)) = (
+                axum::http::HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                parts.headers.append(name, value);
+            }
+        }
+    }
+    let body = match output.remove("body") {
+        Some(Value::String(text)) => bytes::Bytes::from(text),
+        Some(other) => bytes::Bytes::from(other.to_string()),
+        None => body,
+    };
+    // The length may have changed; hyper recomputes it from the new body
+    parts.headers.remove("content-length");
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// One admitted request in an in-flight counter; the slot is released on drop.
+struct InFlightGuard<'a> {
+    counter: &'a AtomicUsize,
+}
+
+impl<'a> InFlightGuard<'a> {
+    /// Take a slot, or `None` when `max` (if nonzero) are already taken.
+    fn acquire(counter: &'a AtomicUsize, max: usize) -> Option<Self> {
+        let in_flight = counter.fetch_add(1, Ordering::AcqRel) + 1;
+        let guard = Self { counter };
+        if max > 0 && in_flight > max {
+            return None;
+        }
+        Some(guard)
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn overloaded_response() -> Response<Body> {
+    let mut response = json_error_response(StatusCode::SERVICE_UNAVAILABLE, "Server overloaded");
+    response
+        .headers_mut()
+        .insert("retry-after", HeaderValue::from(SHED_RETRY_AFTER_SECS));
+    response
+}
+
+fn in_maintenance(path: &str) -> bool {
+    MAINTENANCE_MODE.load(Ordering::Relaxed)
+        && !MAINTENANCE_ALLOWLIST
+            .read()
+            .unwrap()
+            .iter()
+            .any(|prefix| path_under(path, prefix))
+}
+
+// Whole-segment prefix match: "/admin" covers "/admin" and "/admin/users", not "/administrator"
+fn path_under(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+fn maintenance_response() -> Response<Body> {
+    let mut response = json_error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Service temporarily unavailable for maintenance",
+    );
+    response.headers_mut().insert(
+        "retry-after",
+        HeaderValue::from(MAINTENANCE_RETRY_AFTER_SECS),
+    );
+    response
+}
+
+// Handlers and the Python bridge work on strings, so a path or query whose percent-escapes
+// decode to invalid UTF-8, or a header value that isn't UTF-8, is rejected up front
+// rather than mangled (or dropped) further down
+fn malformed_utf8(uri: &Uri, headers: &HeaderMap) -> Option<&'static str> {
+    let decodes_to_utf8 =
+        |raw: &str| std::str::from_utf8(&urlencoding::decode_binary(raw.as_bytes())).is_ok();
+
+    if !decodes_to_utf8(uri.path()) {
+        return Some("Request path is not valid UTF-8");
+    }
+    if !uri.query().is_none_or(decodes_to_utf8) {
+        return Some("Query string is not valid UTF-8");
+    }
+    if headers
+        .values()
+        .any(|value| std::str::from_utf8(value.as_bytes()).is_err())
+    {
+        return Some("Header value is not valid UTF-8");
+    }
+    None
+}
+
+// Only the threshold comparison runs for fast requests. For streamed bodies this times
+// the response head, not the full transfer.
+fn log_if_slow(method: &Method, uri: &Uri, response: &Response<Body>, elapsed: Duration) {
+    let threshold_ms = SLOW_REQUEST_THRESHOLD_MS.load(Ordering::Relaxed);
+    if threshold_ms == 0 || elapsed < Duration::from_millis(threshold_ms) {
+        return;
+    }
+
+    let tier = response
+        .headers()
+        .get("x-sufast-tier")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("unknown");
+    tracing::warn!(
+        method = %method,
+        path = uri.path(),
+        duration_ms = elapsed.as_millis() as u64,
+        tier,
+        status = response.status().as_u16(),
+        "slow request"
+    );
+}
+
+/// The `Idempotency-Key` of a request whose method can create or change state.
+/// Safe methods are idempotent already and never consult the store.
+fn idempotency_key(method: &Method, headers: &HeaderMap) -> Option<String> {
+    if !IDEMPOTENCY_ENABLED.load(Ordering::Relaxed)
+        || matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    {
+        return None;
+    }
+
+    let key = headers.get("idempotency-key")?.to_str().ok()?.trim();
+    (!key.is_empty() && key.len() <= 255).then(|| key.to_string())
+}
+
+// Replays the stored response for a repeated Idempotency-Key, or dispatches the request
+// and stores its response. The key is claimed before dispatch, so a duplicate arriving
+// while the first request runs gets a 409 instead of running the handler twice; reusing
+// a key with a different body is also a 409. 5xx, streamed and oversized responses
+// aren't stored, so a retry runs the handler again.
+async fn dispatch_idempotent(
+    key: String,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Body,
+    request_id: &str,
+) -> Response<Body> {
+    use axum::body::HttpBody;
+    use dashmap::mapref::entry::Entry;
+    use sha2::{Digest, Sha256};
+
+    let body = match axum::body::to_bytes(body, MAX_REQUEST_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return json_error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large")
+        }
+    };
+    let fingerprint = hex::encode(Sha256::digest(&body));
+    let store_key = format!("{}:{}:{}", key, method, uri.path());
+
+    make_room_for_idempotency_key();
+    match IDEMPOTENCY_STORE.entry(store_key.clone()) {
+        Entry::Occupied(mut entry) => {
+            match entry.get() {
+                IdempotencyEntry::InProgress => {
+                    return json_error_response(
+                        StatusCode::CONFLICT,
+                        "A request with this Idempotency-Key is still being processed",
+                    );
+                }
+                IdempotencyEntry::Stored(stored) if stored.cached_at.elapsed() < stored.ttl => {
+                    if stored.request_fingerprint.as_deref() != Some(fingerprint.as_str()) {
+                        return json_error_response(
+                            StatusCode::CONFLICT,
+                            "Idempotency-Key was already used with a different request body",
+                        );
+                    }
+                    return replay_idempotent_response(stored, request_id);
+                }
+                // Expired: handled as a first request
+                IdempotencyEntry::Stored(_) => {}
+            }
+            entry.insert(IdempotencyEntry::InProgress);
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(IdempotencyEntry::InProgress);
+        }
+    }
+    // Releases the claim if the response isn't stored, including when the client hangs up
+    let claim = IdempotencyClaim(store_key);
+
+    // The fingerprinted bytes are what the handler sees
+    let response = dispatch_request(method, uri, headers, Body::from(body), request_id).await;
+    let size = response.body().size_hint().exact();
+    if response.status().is_server_error()
+        || size.is_none_or(|size| size > IDEMPOTENCY_MAX_RESPONSE_BYTES)
+    {
+        return response;
+    }
+
+    let (parts, response_body) = response.into_parts();
+    let response_body = match axum::body::to_bytes(response_body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Response body error")
+        }
+    };
+
+    let stored_headers = parts
+        .headers
+        .iter()
+        .filter(|(name, _)| !matches!(name.as_str(), "x-request-id" | "x-sufast-request-id"))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let stored = CachedResponse {
+        body: String::from_utf8_lossy(&response_body).into_owned(),
+        status: parts.status.as_u16(),
+        headers: stored_headers,
+        cached_at: Instant::now(),
+        ttl: Duration::from_secs(IDEMPOTENCY_TTL_SECS.load(Ordering::Relaxed)),
+        etag: compute_weak_etag(&String::from_utf8_lossy(&response_body)),
+        request_fingerprint: Some(fingerprint),
+    };
+    IDEMPOTENCY_STORE.insert(claim.take(), IdempotencyEntry::Stored(stored));
+
+    Response::from_parts(parts, Body::from(response_body))
+}
+
+fn replay_idempotent_response(stored: &CachedResponse, request_id: &str) -> Response<Body> {
+    let mut response_builder = Response::builder().status(stored.status);
+    for (name, value) in &stored.headers {
+        response_builder = response_builder.header(name, value);
+    }
+    response_builder
+        .header("idempotent-replayed", "true")
+        .header("x-sufast-request-id", request_id)
+        .body(Body::from(stored.body.clone()))
+        .unwrap()
+}
+
+// An in-progress marker in IDEMPOTENCY_STORE, removed on drop unless taken
+struct IdempotencyClaim(String);
+
+impl IdempotencyClaim {
+    fn take(mut self) -> String {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl Drop for IdempotencyClaim {
+    fn drop(&mut self) {
+        if !self.0.is_empty() {
+            IDEMPOTENCY_STORE.remove_if(&self.0, |_, entry| {
+                matches!(entry, IdempotencyEntry::InProgress)
+            });
+        }
+    }
+}
+
+// Sweeps expired replays once per IDEMPOTENCY_SWEEP_INTERVAL, or whenever the store is
+// full; if it is still full, evicts the oldest stored responses. In-progress claims are
+// never evicted.
+fn make_room_for_idempotency_key() {
+    let max = IDEMPOTENCY_MAX_ENTRIES.load(Ordering::Relaxed);
+    let full = max > 0 && IDEMPOTENCY_STORE.len() >= max;
+    {
+        let mut swept_at = IDEMPOTENCY_SWEPT_AT.lock().unwrap();
+        if !full && swept_at.elapsed() < IDEMPOTENCY_SWEEP_INTERVAL {
+            return;
+        }
+        *swept_at = Instant::now();
+    }
+
+    IDEMPOTENCY_STORE.retain(|_, entry| match entry {
+        IdempotencyEntry::InProgress => true,
+        IdempotencyEntry::Stored(stored) => stored.cached_at.elapsed() < stored.ttl,
+    });
+    if max == 0 || IDEMPOTENCY_STORE.len() < max {
+        return;
+    }
+
+    let mut stored: Vec<(Instant, String)> = IDEMPOTENCY_STORE
+        .iter()
+        .filter_map(|entry| match entry.value() {
+            IdempotencyEntry::Stored(stored) => Some((stored.cached_at, entry.key().clone())),
+            IdempotencyEntry::InProgress => None,
+        })
+        .collect();
+    stored.sort_unstable();
+    let excess = (IDEMPOTENCY_STORE.len() + 1).saturating_sub(max);
+    for (_, key) in stored.into_iter().take(excess) {
+        IDEMPOTENCY_STORE.remove(&key);
+    }
+}
+
+async fn dispatch_request(
     method: Method,
     uri: Uri,
     headers: HeaderMap,
     body: Body,
+    request_id: &str,
 ) -> Response<Body> {
-    let request_id = TOTAL_REQUESTS.fetch_add(1, Ordering::Relaxed) + 1;
-    let path = uri.path();
+    let mut path = uri.path();
+
+    // Trailing-slash normalization ("/about/" vs "/about")
+    if path.len() > 1 && path.ends_with('/') {
+        match TRAILING_SLASH_POLICY.load(Ordering::Relaxed) {
+            TRAILING_SLASH_IGNORE => {
+                path = path.trim_end_matches('/');
+                if path.is_empty() {
+                    path = "/";
+                }
+            }
+            TRAILING_SLASH_REDIRECT => {
+                let trimmed = path.trim_end_matches('/');
+                let mut location = if trimmed.is_empty() { "/" } else { trimmed }.to_string();
+                if let Some(query) = uri.query() {
+                    location.push('?');
+                    location.push_str(query);
+                }
+
+                return Response::builder()
+                    .status(StatusCode::PERMANENT_REDIRECT)
+                    .header("location", location)
+                    .header("x-sufast-tier", "redirect")
+                    .header("x-sufast-request-id", request_id)
+                    .header("server", "sufast-ultra/3.0")
+                    .body(Body::empty())
+                    .unwrap();
+            }
+            _ => {}
+        }
+    }
+    let method = overridden_method(&method, &headers, uri.query()).unwrap_or(method);
     let method_str = method.as_str();
     let route_key = format!("{}:{}", method_str, path);
 
@@ -104,19 +966,47 @@ async fn ultra_fast_handler(
             response_builder = response_builder.header(key, value);
         }
 
+        let body = match &static_resp.gzip_body {
+            Some(compressed) => {
+                if accepts_gzip(&headers) {
+                    response_builder = response_builder.header("content-encoding", "gzip");
+                    Body::from(compressed.clone())
+                } else {
+                    Body::from(static_resp.body.clone())
+                }
+            }
+            None => Body::from(static_resp.body.clone()),
+        };
+
         return response_builder
             .header("x-sufast-tier", "static")
-            .header("x-sufast-request-id", request_id.to_string())
+            .header("x-sufast-request-id", request_id)
             .header("server", "sufast-ultra/3.0")
-            .body(Body::from(static_resp.body.clone()))
+            .body(body)
             .unwrap();
     }
 
     // TIER 2: Cache lookup - Fast cache
-    if let Some(cached) = RESPONSE_CACHE.get(&route_key) {
+    let cache_key = match CACHE_VARY_HEADERS.get(&route_key) {
+        Some(vary_headers) => vary_cache_key(&route_key, &vary_headers, &headers),
+        None => route_key.clone(),
+    };
+    if let Some(cached) = RESPONSE_CACHE.get(&cache_key) {
         if cached.cached_at.elapsed() < cached.ttl {
             CACHE_HITS.fetch_add(1, Ordering::Relaxed);
 
+            // Conditional request: client already has this representation
+            if if_none_match_matches(&headers, &cached.etag) {
+                return Response::builder()
+                    .status(StatusCode::NOT_MODIFIED)
+                    .header("etag", &cached.etag)
+                    .header("x-sufast-tier", "cached")
+                    .header("x-sufast-request-id", request_id)
+                    .header("server", "sufast-ultra/3.0")
+                    .body(Body::empty())
+                    .unwrap();
+            }
+
             let mut response_builder = Response::builder().status(cached.status);
 
             for (key, value) in &cached.headers {
@@ -124,8 +1014,9 @@ async fn ultra_fast_handler(
             }
 
             return response_builder
+                .header("etag", &cached.etag)
                 .header("x-sufast-tier", "cached")
-                .header("x-sufast-request-id", request_id.to_string())
+                .header("x-sufast-request-id", request_id)
                 .header(
                     "x-sufast-cache-age",
                     cached.cached_at.elapsed().as_secs().to_string(),
@@ -134,458 +1025,4684 @@ async fn ultra_fast_handler(
                 .body(Body::from(cached.body.clone()))
                 .unwrap();
         } else {
-            RESPONSE_CACHE.remove(&route_key);
+            RESPONSE_CACHE.remove(&cache_key);
+        }
+    }
+
+    // Long-poll routes park the request on their channel instead of calling Python
+    if method == Method::GET {
+        if let Some(timeout) = match_long_poll_route(path) {
+            return long_poll_response(path, uri.query(), timeout, request_id).await;
         }
     }
 
     // TIER 3: Dynamic processing - Call Python via FFI
     DYNAMIC_HITS.fetch_add(1, Ordering::Relaxed);
 
-    // Match dynamic routes by method + path pattern
-    for route_entry in DYNAMIC_ROUTES.iter() {
-        let route = route_entry.value();
-
-        // Check method matches
-        if route.method != "*" && route.method != method_str {
-            continue;
+    // Only Python handlers see the body, so it isn't read for the tiers above
+    let body = match axum::body::to_bytes(body, MAX_REQUEST_BODY_BYTES).await {
+        Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+        Err(_) => {
+            return json_error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large")
         }
+    };
 
-        if let Some(captures) = route.regex.captures(path) {
-            // Extract parameters
-            let mut params_json = String::from("{");
-            let mut first = true;
-
-            for name in route.regex.capture_names() {
-                if let Some(name) = name {
-                    if let Some(value) = captures.name(name) {
-                        if !first {
-                            params_json.push(',');
-                        }
-                        params_json.push_str(&format!(
-                            "\"{}\":\"{}\"",
-                            name,
-                            value.as_str().replace('\"', "\\\"")
-                        ));
-                        first = false;
-                    }
-                }
-            }
-            params_json.push('}');
+    // Match dynamic routes by method + path pattern, most specific first
+    if let Some((route, params)) = match_dynamic_route(method_str, path) {
+        let payload = build_callback_payload(params, &headers, uri.query(), &body, request_id);
 
-            // Call Python handler
-            if let Ok((body, status, response_headers)) =
-                call_ultra_fast_python_handler(method_str, path, &params_json).await
-            {
-                // Cache successful responses
-                if status == 200 && route.cache_ttl.is_some() {
-                    let cached = CachedResponse {
-                        body: body.clone(),
-                        status,
-                        headers: response_headers.clone(),
-                        cached_at: Instant::now(),
-                        ttl: route.cache_ttl.unwrap(),
-                    };
-                    RESPONSE_CACHE.insert(route_key, cached);
+        // Call Python handler; concurrent misses on a cached route share one call
+        let cache_key = vary_cache_key(&route_key, &route.vary_headers, &headers);
+        let call = async {
+            if route.cache_ttl.is_some() {
+                call_python_single_flight(&cache_key, method_str, path, &payload).await
+            } else {
+                call_ultra_fast_python_handler(method_str, path, &payload).await
+            }
+        };
+        // A Python callback already running on the blocking pool can't be cancelled; on
+        // expiry it finishes in the background and its response is dropped
+        let result = match effective_timeout(&route) {
+            Some(limit) => match tokio::time::timeout(limit, call).await {
+                Ok(result) => result,
+                Err(_) => return gateway_timeout_response(method_str, path, limit, request_id),
+            },
+            None => call.await,
+        };
+        if let Err(error) = &result {
+            if let Some(response) = python_error_response(error, request_id) {
+                return response;
+            }
+        }
+        if let Ok((body, status, response_headers)) = result {
+            let body = match body {
+                PythonBody::Full(body) => body,
+                PythonBody::Stream(stream_id) => {
+                    return python_response_head(status, &response_headers)
+                        .header("x-sufast-tier", "dynamic")
+                        .header("x-sufast-request-id", request_id)
+                        .header("x-sufast-handler", &route.handler_name)
+                        .body(stream_body(stream_id))
+                        .unwrap();
                 }
+            };
+            let mut response_headers = response_headers;
+            add_vary_headers(&mut response_headers, &route.vary_headers);
+            let mut response_builder = Response::builder().status(status);
+
+            // Cache successful responses
+            if let Some(cache_ttl) = route.cache_ttl.filter(|_| status == 200) {
+                let etag = compute_weak_etag(&body);
+                response_builder = response_builder.header("etag", &etag);
 
-                let mut response_builder = Response::builder().status(status);
-                for (key, value) in &response_headers {
-                    response_builder = response_builder.header(key, value);
+                let cached = CachedResponse {
+                    body: body.clone(),
+                    status,
+                    headers: response_headers.clone(),
+                    cached_at: Instant::now(),
+                    ttl: jittered_ttl(
+                        cache_ttl,
+                        f64::from_bits(CACHE_TTL_JITTER_BITS.load(Ordering::Relaxed)),
+                    ),
+                    etag,
+                    request_fingerprint: None,
+                };
+                if route.vary_headers.is_empty() {
+                    // The route may have been re-registered without vary headers
+                    CACHE_VARY_HEADERS.remove(&route_key);
+                } else {
+                    CACHE_VARY_HEADERS.insert(route_key, route.vary_headers.clone());
                 }
+                RESPONSE_CACHE.insert(cache_key, cached);
+            }
 
-                return response_builder
-                    .header("x-sufast-tier", "dynamic")
-                    .header("x-sufast-request-id", request_id.to_string())
-                    .header("x-sufast-handler", &route.handler_name)
-                    .header("server", "sufast-ultra/3.0")
-                    .body(Body::from(body))
-                    .unwrap();
+            for (key, value) in &response_headers {
+                response_builder = response_builder.header(key, value);
             }
+
+            return response_builder
+                .header("x-sufast-tier", "dynamic")
+                .header("x-sufast-request-id", request_id)
+                .header("x-sufast-handler", &route.handler_name)
+                .header("server", "sufast-ultra/3.0")
+                .body(Body::from(body))
+                .unwrap();
         }
     }
 
     // Last resort: forward ALL unmatched requests to Python
     // This lets Python handle docs, static files, etc.
-    if let Ok((body, status, response_headers)) =
-        call_ultra_fast_python_handler(method_str, path, "{}").await
-    {
-        let mut response_builder = Response::builder().status(status);
-        for (key, value) in &response_headers {
-            response_builder = response_builder.header(key, value);
+    let payload = build_callback_payload(
+        serde_json::Map::new(),
+        &headers,
+        uri.query(),
+        &body,
+        request_id,
+    );
+    let result = call_ultra_fast_python_handler(method_str, path, &payload).await;
+    if let Err(error) = &result {
+        if let Some(response) = python_error_response(error, request_id) {
+            return response;
         }
-
-        return response_builder
+    }
+    if let Ok((body, status, response_headers)) = result {
+        let body = match body {
+            PythonBody::Full(body) => Body::from(body),
+            PythonBody::Stream(stream_id) => stream_body(stream_id),
+        };
+        return python_response_head(status, &response_headers)
             .header("x-sufast-tier", "python-fallback")
-            .header("x-sufast-request-id", request_id.to_string())
-            .header("server", "sufast-ultra/3.0")
-            .body(Body::from(body))
+            .header("x-sufast-request-id", request_id)
+            .body(body)
             .unwrap();
     }
 
-    // Final 404
-    let stats = json!({
-        "error": "Route not found",
-        "status": 404,
-        "path": path,
-        "method": method_str,
-        "server": "sufast-ultra/3.0"
-    });
-
-    Response::builder()
-        .status(404)
-        .header("content-type", "application/json")
-        .header("x-sufast-tier", "404")
-        .header("server", "sufast-ultra/3.0")
-        .body(Body::from(stats.to_string()))
-        .unwrap()
+    // Final 404, when there is no Python callback to hand the request to. Custom 404s
+    // belong to the Python fallback handler.
+    let mut response = json_error_response(StatusCode::NOT_FOUND, "Not Found");
+    response
+        .headers_mut()
+        .insert("x-sufast-tier", HeaderValue::from_static("404"));
+    response
 }
 
-async fn call_ultra_fast_python_handler(
+/// Find the most specific dynamic route for `method` + `path`.
+/// Returns a clone of the route and its captured path parameters.
+fn match_dynamic_route(
     method: &str,
     path: &str,
-    params_json: &str,
-) -> Result<(String, u16, HashMap<String, String>), String> {
-    let callback = PYTHON_CALLBACK.lock().unwrap();
-    if let Some(cb) = *callback {
-        let method_cstr = CString::new(method).map_err(|e| e.to_string())?;
-        let path_cstr = CString::new(path).map_err(|e| e.to_string())?;
-        let params_cstr = CString::new(params_json).map_err(|e| e.to_string())?;
+) -> Option<(DynamicRoute, serde_json::Map<String, Value>)> {
+    let index = DYNAMIC_ROUTE_INDEX.read().unwrap();
 
-        let result_ptr = cb(
-            method_cstr.as_ptr(),
-            path_cstr.as_ptr(),
-            params_cstr.as_ptr(),
-        );
-        drop(callback); // Release lock before processing
-        if result_ptr.is_null() {
-            return Err("Python callback returned null".to_string());
+    for route in index.candidates(path) {
+        // Check method matches
+        if route.method != "*" && route.method != method {
+            continue;
         }
 
-        let response_json = unsafe {
-            let c_str = CStr::from_ptr(result_ptr);
-            let s = c_str.to_string_lossy().to_string();
-            // Reclaim the CString to prevent memory leak
-            drop(CString::from_raw(result_ptr as *mut c_char));
-            s
-        };
-
-        // Parse response
-        if let Ok(response_data) = serde_json::from_str::<Value>(&response_json) {
-            let body = response_data["body"].as_str().unwrap_or("{}").to_string();
-            let status = response_data["status"].as_u64().unwrap_or(200) as u16;
-
-            let mut headers = HashMap::new();
-            headers.insert("content-type".to_string(), "application/json".to_string());
-            headers.insert("x-sufast-engine".to_string(), "rust-python-ffi".to_string());
-
-            if let Some(response_headers) = response_data["headers"].as_object() {
-                for (key, value) in response_headers {
-                    if let Some(value_str) = value.as_str() {
-                        headers.insert(key.clone(), value_str.to_string());
-                    }
+        if let Some(captures) = route.regex.captures(path) {
+            // Extract parameters; serde_json escapes quotes, backslashes and control
+            // characters in the captured values
+            let mut params = serde_json::Map::new();
+            for name in route.regex.capture_names().flatten() {
+                if let Some(value) = captures.name(name) {
+                    params.insert(name.to_string(), Value::String(value.as_str().to_string()));
                 }
             }
 
-            return Ok((body, status, headers));
+            return Some((route.clone(), params));
         }
     }
-    Err("Python callback failed".to_string())
+
+    None
 }
 
-// ========================
-// FFI ROUTE REGISTRATION
-// ========================
+// The most specific matching pattern wins, as for dynamic routes, so overlapping
+// patterns resolve the same way regardless of DashMap order
+fn match_long_poll_route(path: &str) -> Option<Duration> {
+    LONG_POLL_ROUTES
+        .iter()
+        .filter(|route| route.regex.is_match(path))
+        .min_by(|a, b| {
+            compare_route_specificity(a.key(), b.key()).then_with(|| a.key().cmp(b.key()))
+        })
+        .map(|route| route.timeout)
+}
 
-#[no_mangle]
-pub extern "C" fn add_static_route(
-    method_path: *const c_char,
-    response_body: *const c_char,
-    status: u16,
-    content_type: *const c_char,
-) -> bool {
-    unsafe {
-        if method_path.is_null() || response_body.is_null() {
-            return false;
-        }
+fn long_poll_channel_idle(sender: &watch::Sender<LongPollState>) -> bool {
+    sender.receiver_count() == 0
+        && sender
+            .borrow()
+            .kept_until
+            .is_none_or(|until| Instant::now() >= until)
+}
 
-        let method_path_str = CStr::from_ptr(method_path).to_string_lossy().to_string();
-        let body_str = CStr::from_ptr(response_body).to_string_lossy().to_string();
-        let content_type_str = if content_type.is_null() {
-            "application/json".to_string()
-        } else {
-            CStr::from_ptr(content_type).to_string_lossy().to_string()
-        };
+/// Answer a long poll on the channel for `path`. A client sends the `x-poll-version` of
+/// the last response it saw as `?since=`; newer data is returned at once, otherwise the
+/// request waits (on the async runtime, holding no worker thread) for the next publish
+/// or until `timeout`, which yields 204. Without `since` only future publishes count.
+async fn long_poll_response(
+    path: &str,
+    query: Option<&str>,
+    timeout: Duration,
+    request_id: &str,
+) -> Response<Body> {
+    // Subscribe before reading the version so a publish in between isn't missed
+    let mut updates = LONG_POLL_CHANNELS
+        .entry(path.to_string())
+        .or_insert_with(|| watch::channel(LongPollState::default()).0)
+        .subscribe();
+    let current = updates.borrow_and_update().version;
 
-        let mut headers = HashMap::new();
-        headers.insert("content-type".to_string(), content_type_str);
-        headers.insert("x-sufast-optimized".to_string(), "static".to_string());
-        headers.insert(
-            "cache-control".to_string(),
-            "public, max-age=31536000".to_string(),
-        );
+    let since = parse_query(query)
+        .into_iter()
+        .find(|(key, _)| key == "since")
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+        // A version from the future means the server restarted; resend what we have
+        .map(|since| if since > current { 0 } else { since })
+        .unwrap_or(current);
 
-        let static_response = StaticResponse {
-            body: body_str,
-            status,
-            headers,
-        };
+    if current <= since {
+        let _ = tokio::time::timeout(timeout, updates.changed()).await;
+    }
+
+    let state = updates.borrow().clone();
+    drop(updates);
+    LONG_POLL_CHANNELS.remove_if(path, |_, sender| long_poll_channel_idle(sender));
+
+    let response_builder = Response::builder()
+        .header("x-poll-version", state.version.to_string())
+        .header("cache-control", "no-store")
+        .header("x-sufast-tier", "long-poll")
+        .header("x-sufast-request-id", request_id)
+        .header("server", "sufast-ultra/3.0");
 
-        STATIC_RESPONSES.insert(method_path_str, static_response);
-        true
+    if state.version <= since {
+        return response_builder
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap();
     }
+
+    response_builder
+        .status(StatusCode::OK)
+        .header("content-type", "application/json")
+        .body(Body::from(state.data))
+        .unwrap()
 }
 
-#[no_mangle]
-pub extern "C" fn add_dynamic_route(
-    method: *const c_char,
-    pattern: *const c_char,
-    handler_name: *const c_char,
-    cache_ttl_seconds: u64,
-) -> bool {
-    unsafe {
-        if method.is_null() || pattern.is_null() || handler_name.is_null() {
-            return false;
+// Decoded query pairs, at most MAX_QUERY_PARAMS of them so a request can't make us build
+// an arbitrarily large map
+fn parse_query(query: Option<&str>) -> Vec<(String, String)> {
+    let Some(query) = query else {
+        return Vec::new();
+    };
+    let end = query
+        .match_indices('&')
+        .nth(MAX_QUERY_PARAMS - 1)
+        .map_or(query.len(), |(i, _)| i);
+    serde_urlencoded::from_str(&query[..end]).unwrap_or_default()
+}
+
+/// JSON payload handed to the Python callback: the captured path parameters at the top
+/// level (unchanged from earlier versions) plus `headers`, `query`, `body` (the request
+/// body as text) and `request_id`.
+fn build_callback_payload(
+    mut params: serde_json::Map<String, Value>,
+    headers: &HeaderMap,
+    query: Option<&str>,
+    body: &str,
+    request_id: &str,
+) -> String {
+    let mut header_map = serde_json::Map::new();
+    for (name, value) in headers {
+        // Non-ASCII UTF-8 values (rejected by `to_str`) are passed through as text
+        let value = String::from_utf8_lossy(value.as_bytes());
+        // Repeated headers are folded into one comma-separated value
+        match header_map.get_mut(name.as_str()) {
+            Some(Value::String(existing)) => {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            }
+            _ => {
+                header_map.insert(name.as_str().to_string(), Value::String(value.into_owned()));
+            }
         }
+    }
 
-        let method_str = CStr::from_ptr(method).to_string_lossy().to_string();
-        let pattern_str = CStr::from_ptr(pattern).to_string_lossy().to_string();
-        let handler_str = CStr::from_ptr(handler_name).to_string_lossy().to_string();
+    let query_map: serde_json::Map<String, Value> = parse_query(query)
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect();
 
-        // Compile fast regex pattern
-        if let Ok(regex) = compile_ultra_fast_pattern(&pattern_str) {
-            let cache_ttl = if cache_ttl_seconds > 0 {
-                Some(Duration::from_secs(cache_ttl_seconds))
-            } else {
-                None
-            };
+    params.insert("headers".to_string(), Value::Object(header_map));
+    params.insert("query".to_string(), Value::Object(query_map));
+    params.insert("body".to_string(), Value::String(body.to_string()));
+    params.insert(
+        "request_id".to_string(),
+        Value::String(request_id.to_string()),
+    );
+    Value::Object(params).to_string()
+}
 
-            let dynamic_route = DynamicRoute {
-                method: method_str.clone(),
-                regex,
-                handler_name: handler_str,
-                cache_ttl,
-            };
+/// Order two route patterns so the more specific one sorts first.
+/// Literal segments beat `{param}` segments position by position, then patterns with
+/// more literal segments and fewer params win. Remaining ties fall back to the pattern
+/// text so the order never depends on hash iteration.
+fn compare_route_specificity(a: &str, b: &str) -> cmp::Ordering {
+    let is_param = |segment: &str| segment.contains('{');
+    let a_segments: Vec<&str> = a.split('/').filter(|s| !s.is_empty()).collect();
+    let b_segments: Vec<&str> = b.split('/').filter(|s| !s.is_empty()).collect();
 
-            // Key includes method for proper multi-method routing
-            let key = format!("{}:{}", method_str, pattern_str);
-            DYNAMIC_ROUTES.insert(key, dynamic_route);
-            true
-        } else {
-            false
+    for (a_seg, b_seg) in a_segments.iter().zip(b_segments.iter()) {
+        match (is_param(a_seg), is_param(b_seg)) {
+            (false, true) => return cmp::Ordering::Less,
+            (true, false) => return cmp::Ordering::Greater,
+            _ => {}
         }
     }
+
+    let a_literals = a_segments.iter().filter(|s| !is_param(s)).count();
+    let b_literals = b_segments.iter().filter(|s| !is_param(s)).count();
+    let a_params = a_segments.len() - a_literals;
+    let b_params = b_segments.len() - b_literals;
+
+    b_literals
+        .cmp(&a_literals)
+        .then_with(|| a_params.cmp(&b_params))
+        .then_with(|| a.cmp(b))
 }
 
-#[no_mangle]
-pub extern "C" fn add_websocket_route(
-    pattern: *const c_char,
-    handler_name: *const c_char,
-) -> bool {
-    unsafe {
-        if pattern.is_null() || handler_name.is_null() {
-            return false;
-        }
+fn rebuild_dynamic_route_order() {
+    let mut routes: Vec<DynamicRoute> = DYNAMIC_ROUTES.iter().map(|e| e.value().clone()).collect();
+    routes.sort_by(|a, b| {
+        compare_route_specificity(&a.pattern, &b.pattern).then_with(|| a.method.cmp(&b.method))
+    });
+    *DYNAMIC_ROUTE_INDEX.write().unwrap() = DynamicRouteIndex::build(routes);
+}
 
-        let pattern_str = CStr::from_ptr(pattern).to_string_lossy().to_string();
-        let handler_str = CStr::from_ptr(handler_name).to_string_lossy().to_string();
+/// The method a POST should be dispatched as when method override is enabled. The
+/// `X-HTTP-Method-Override` header wins over a `_method` query parameter; only PUT, PATCH
+/// and DELETE are accepted and anything else is ignored.
+fn overridden_method(method: &Method, headers: &HeaderMap, query: Option<&str>) -> Option<Method> {
+    if *method != Method::POST || !METHOD_OVERRIDE_ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
 
-        if let Ok(regex) = compile_ultra_fast_pattern(&pattern_str) {
-            let ws_route = WsRoute {
-                regex,
-                handler_name: handler_str,
-            };
-            WS_ROUTES.insert(pattern_str, ws_route);
-            true
-        } else {
-            false
-        }
+    let requested = headers
+        .get("x-http-method-override")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| {
+            query?
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "_method")
+                .map(|(_, value)| value.to_string())
+        })?;
+
+    match requested.trim().to_ascii_uppercase().as_str() {
+        "PUT" => Some(Method::PUT),
+        "PATCH" => Some(Method::PATCH),
+        "DELETE" => Some(Method::DELETE),
+        _ => None,
     }
 }
 
-fn compile_ultra_fast_pattern(pattern: &str) -> Result<Regex, regex::Error> {
-    let mut regex_pattern = pattern.to_string();
+/// Weak ETag derived from a hash of the response body.
+fn compute_weak_etag(body: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(body.as_bytes());
+    format!("W/\"{}\"", hex::encode(&digest[..8]))
+}
 
-    // Fast parameter replacement
-    while let Some(start) = regex_pattern.find('{') {
-        if let Some(end) = regex_pattern[start..].find('}') {
-            let param_name = &regex_pattern[start + 1..start + end];
-            let replacement = format!("(?P<{}>[^/]+)", param_name);
-            regex_pattern.replace_range(start..start + end + 1, &replacement);
-        } else {
-            break;
-        }
-    }
+/// True when the request's If-None-Match header lists `etag` (or `*`).
+/// Comparison is weak, so `W/` prefixes are ignored on both sides.
+fn if_none_match_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(header) = headers.get("if-none-match").and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
 
-    // Compile with optimizations
-    Regex::new(&format!("^{}$", regex_pattern))
+    let wanted = etag.trim_start_matches("W/");
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == wanted)
 }
 
-#[no_mangle]
-pub extern "C" fn set_python_callback(callback: PythonCallback) {
-    let mut cb = PYTHON_CALLBACK.lock().unwrap();
-    *cb = Some(callback);
+#[derive(Debug)]
+enum PythonCallError {
+    // Python couldn't be called or gave nothing back (no callback, null result, panic)
+    Unavailable(String),
+    // Python answered, but not with the JSON response envelope
+    InvalidResponse { detail: String, preview: String },
+    // The request can't be passed over FFI (a NUL byte would end the C string early)
+    InvalidRequest(String),
 }
 
-#[no_mangle]
-pub extern "C" fn get_performance_stats() -> *mut c_char {
-    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
-    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
-    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
-    let ws_conns = WS_CONNECTIONS.load(Ordering::Relaxed);
-    let total = static_hits + cache_hits + dynamic_hits;
+impl std::fmt::Display for PythonCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable(reason) | Self::InvalidRequest(reason) => f.write_str(reason),
+            Self::InvalidResponse { detail, preview } => {
+                write!(f, "invalid handler response ({}): {}", detail, preview)
+            }
+        }
+    }
+}
 
-    let stats = json!({
-        "total_requests": total,
-        "static_hits": static_hits,
-        "cache_hits": cache_hits,
-        "dynamic_hits": dynamic_hits,
-        "websocket_connections": ws_conns,
-        "performance_breakdown": {
-            "static_percentage": if total > 0 { (static_hits as f64 / total as f64) * 100.0 } else { 0.0 },
-            "cache_percentage": if total > 0 { (cache_hits as f64 / total as f64) * 100.0 } else { 0.0 },
-            "dynamic_percentage": if total > 0 { (dynamic_hits as f64 / total as f64) * 100.0 } else { 0.0 }
-        },
-        "route_counts": {
-            "static_routes": STATIC_RESPONSES.len(),
-            "cached_responses": RESPONSE_CACHE.len(),
-            "dynamic_patterns": DYNAMIC_ROUTES.len(),
-            "websocket_routes": WS_ROUTES.len()
-        },
-        "server": "sufast-ultra/3.0"
-    });
+fn effective_timeout(route: &DynamicRoute) -> Option<Duration> {
+    route.timeout.or_else(|| {
+        let seconds = REQUEST_TIMEOUT_SECS.load(Ordering::Relaxed);
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    })
+}
 
-    let cstring = CString::new(stats.to_string())
-        .unwrap_or_else(|_| CString::new("{}").unwrap());
-    let ptr = cstring.into_raw();
-    // Caller must call free_rust_string(ptr) to avoid memory leak
-    ptr
+fn gateway_timeout_response(
+    method: &str,
+    path: &str,
+    limit: Duration,
+    request_id: &str,
+) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .header("content-type", "application/json")
+        .header("x-sufast-tier", "dynamic")
+        .header("x-sufast-request-id", request_id)
+        .header("server", "sufast-ultra/3.0")
+        .body(Body::from(
+            json!({
+                "error": "Gateway Timeout",
+                "message": format!(
+                    "{} {} did not complete within {}s",
+                    method,
+                    path,
+                    limit.as_secs()
+                ),
+                "timeout_seconds": limit.as_secs(),
+            })
+            .to_string(),
+        ))
+        .unwrap()
 }
 
-/// Free a string returned by Rust FFI functions.
-/// Must be called for every pointer returned by get_performance_stats.
-#[no_mangle]
-pub extern "C" fn free_rust_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        unsafe {
-            drop(CString::from_raw(ptr));
+// None when Python is unavailable, so the request falls through to the next tier
+fn python_error_response(error: &PythonCallError, request_id: &str) -> Option<Response<Body>> {
+    match error {
+        PythonCallError::Unavailable(_) => None,
+        PythonCallError::InvalidResponse { .. } => Some(invalid_python_response(error, request_id)),
+        PythonCallError::InvalidRequest(reason) => {
+            let mut response = json_error_response(StatusCode::BAD_REQUEST, reason);
+            if let Ok(value) = HeaderValue::from_str(request_id) {
+                response.headers_mut().insert("x-sufast-request-id", value);
+            }
+            Some(response)
         }
     }
 }
 
-#[no_mangle]
-pub extern "C" fn clear_cache() -> bool {
-    RESPONSE_CACHE.clear();
-    true
+fn json_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .header("server", "sufast-ultra/3.0")
+        .body(Body::from(json!({ "error": message }).to_string()))
+        .unwrap()
 }
 
-#[no_mangle]
-pub extern "C" fn precompile_static_routes() -> u64 {
-    // Pre-compile default static routes
-    let routes = vec![
-        (
-            "GET:/",
-            r#"{"message":"Welcome to Sufast v3.0!","server":"sufast-ultra/3.0","docs":"/docs"}"#,
-            200,
-            "application/json",
-        ),
-        (
-            "GET:/health",
-            r#"{"status":"healthy","service":"sufast","server":"sufast-ultra/3.0"}"#,
-            200,
-            "application/json",
-        ),
-        (
-            "GET:/api/status",
-            r#"{"api":"active","status":"operational","server":"sufast-ultra/3.0"}"#,
-            200,
-            "application/json",
-        ),
-    ];
+/// Build a CString reusing a pooled buffer when one is available.
+fn pooled_cstring(value: &str) -> Result<CString, std::ffi::NulError> {
+    let mut buffer = RESPONSE_POOL
+        .lock()
+        .unwrap()
+        .pop()
+        .map(CString::into_bytes)
+        .unwrap_or_default();
+    buffer.clear();
+    buffer.extend_from_slice(value.as_bytes());
+    CString::new(buffer)
+}
 
-    for (route_key, body, status, content_type) in routes {
-        // Only add if not already registered by Python
-        if !STATIC_RESPONSES.contains_key(route_key) {
-            let mut headers = HashMap::new();
-            headers.insert("content-type".to_string(), content_type.to_string());
-            headers.insert("x-sufast-precompiled".to_string(), "true".to_string());
-            headers.insert(
-                "cache-control".to_string(),
-                "public, max-age=3600".to_string(),
-            );
-            headers.insert("server".to_string(), "sufast-ultra/3.0".to_string());
+/// Return a CString's allocation to the pool once the FFI call is done with it.
+fn recycle_cstring(value: CString) {
+    let mut pool = RESPONSE_POOL.lock().unwrap();
+    if pool.len() < RESPONSE_POOL_MAX {
+        pool.push(value);
+    }
+}
 
-            let static_response = StaticResponse {
-                body: body.to_string(),
-                status,
-                headers,
-            };
+/// `ttl` scaled by a random factor in `[1 - fraction, 1 + fraction]`.
+fn jittered_ttl(ttl: Duration, fraction: f64) -> Duration {
+    if fraction <= 0.0 {
+        return ttl;
+    }
+    let factor = 1.0 + rand::thread_rng().gen_range(-fraction..=fraction);
+    ttl.mul_f64(factor)
+}
 
-            STATIC_RESPONSES.insert(route_key.to_string(), static_response);
+// A route with vary headers caches one response per combination of their request values,
+// under "METHOD:path\nvalue\nvalue..." (header values can't contain '\n'). Without vary
+// headers the key is just `route_key`.
+fn vary_cache_key(route_key: &str, vary_headers: &[String], headers: &HeaderMap) -> String {
+    let mut key = route_key.to_string();
+    for name in vary_headers {
+        key.push('\n');
+        if let Some(value) = headers.get(name.as_str()) {
+            key.push_str(&String::from_utf8_lossy(value.as_bytes()));
         }
     }
-
-    STATIC_RESPONSES.len() as u64
+    key
 }
 
-#[no_mangle]
-pub extern "C" fn start_ultra_fast_server(host: *const c_char, port: u16) -> i32 {
-    if host.is_null() {
-        eprintln!("[sufast] Host parameter cannot be null");
-        return -1;
+// Append the route's vary headers to the handler's own Vary, so downstream caches split
+// responses the same way
+fn add_vary_headers(headers: &mut HashMap<String, String>, vary_headers: &[String]) {
+    if vary_headers.is_empty() {
+        return;
     }
 
-    let host_str = unsafe {
-        match CStr::from_ptr(host).to_str() {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("[sufast] Invalid host string: {}", e);
-                return -1;
-            }
-        }
+    let existing_key = headers
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case("vary"))
+        .cloned();
+    let mut names: Vec<String> = match existing_key.and_then(|k| headers.remove(&k)) {
+        Some(vary) => vary
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect(),
+        None => Vec::new(),
     };
+    for name in vary_headers {
+        if !names
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(name))
+        {
+            names.push(name.clone());
+        }
+    }
+    headers.insert("vary".to_string(), names.join(", "));
+}
 
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let app = Router::new()
-            .fallback(ultra_fast_handler)
-            .layer(CorsLayer::permissive());
+/// `call_ultra_fast_python_handler` for a cached route, coalescing concurrent callers for
+/// the same `route_key` (single flight): the first caller runs the handler, the rest
+/// await it and get a copy of its response. Callers only fall back to their own call
+/// when the shared result is unusable.
+async fn call_python_single_flight(
+    route_key: &str,
+    method: &str,
+    path: &str,
+    params_json: &str,
+) -> Result<(PythonBody, u16, HashMap<String, String>), PythonCallError> {
+    let flight = IN_FLIGHT.entry(route_key.to_string()).or_default().clone();
 
-        let addr = format!("{}:{}", host_str, port);
-        let listener = match TcpListener::bind(&addr).await {
-            Ok(listener) => listener,
-            Err(e) => {
-                eprintln!("[sufast] Failed to bind to {}: {}", addr, e);
-                return -1;
+    let mut own_result = None;
+    let shared = flight
+        .get_or_init(|| async {
+            let result = call_ultra_fast_python_handler(method, path, params_json).await;
+            let shared = match &result {
+                Ok((PythonBody::Full(body), status, headers)) => {
+                    Some((body.clone(), *status, headers.clone()))
+                }
+                _ => None,
+            };
+            own_result = Some(result);
+            shared
+        })
+        .await
+        .clone();
+
+    if let Some(result) = own_result {
+        // Done: later misses start a new flight (or hit the cache once this is stored)
+        IN_FLIGHT.remove_if(route_key, |_, current| Arc::ptr_eq(current, &flight));
+        return result;
+    }
+
+    match shared {
+        Some((body, status, headers)) => {
+            COALESCED_HITS.fetch_add(1, Ordering::Relaxed);
+            Ok((PythonBody::Full(body), status, headers))
+        }
+        None => call_ultra_fast_python_handler(method, path, params_json).await,
+    }
+}
+
+// Characters of an invalid Python response echoed back in the 502 and the log
+const INVALID_RESPONSE_PREVIEW_CHARS: usize = 200;
+
+/// 502 for a Python handler that returned something other than the response envelope
+/// (`{"body", "status", "headers"}`), e.g. a bare string. Distinct from the 404 for an
+/// unmatched route so the broken handler is obvious.
+fn invalid_python_response(error: &PythonCallError, request_id: &str) -> Response<Body> {
+    let PythonCallError::InvalidResponse { detail, preview } = error else {
+        unreachable!("only invalid responses are reported as 502");
+    };
+
+    Response::builder()
+        .status(StatusCode::BAD_GATEWAY)
+        .header("content-type", "application/json")
+        .header("x-sufast-tier", "python-error")
+        .header("x-sufast-request-id", request_id)
+        .header("server", "sufast-ultra/3.0")
+        .body(Body::from(
+            json!({
+                "error": "Python handler returned an invalid response",
+                "detail": detail,
+                "preview": preview,
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+// Body of a Python handler response: the complete body, or a stream id to pull chunks for
+enum PythonBody {
+    Full(String),
+    Stream(u64),
+}
+
+async fn call_ultra_fast_python_handler(
+    method: &str,
+    path: &str,
+    params_json: &str,
+) -> Result<(PythonBody, u16, HashMap<String, String>), PythonCallError> {
+    let unavailable = |e: &dyn std::fmt::Display| PythonCallError::Unavailable(e.to_string());
+
+    // Copy the function pointer out so the lock isn't held while Python runs
+    let cb = PYTHON_CALLBACK
+        .lock()
+        .unwrap()
+        .ok_or_else(|| unavailable(&"Python callback not registered"))?;
+
+    let nul_byte = |field: &'static str| {
+        move |e: std::ffi::NulError| {
+            PythonCallError::InvalidRequest(format!(
+                "request {} contains a NUL byte at position {}",
+                field,
+                e.nul_position()
+            ))
+        }
+    };
+    let method_cstr = pooled_cstring(method).map_err(nul_byte("method"))?;
+    let path_cstr = pooled_cstring(path).map_err(nul_byte("path"))?;
+    let params_cstr = pooled_cstring(params_json).map_err(nul_byte("parameters"))?;
+
+    // Python handlers can block for a long time; run them on the blocking pool so
+    // tokio workers keep serving other requests
+    let span = tracing::Span::current();
+    let response_json = tokio::task::spawn_blocking(move || {
+        let _request_span = span.enter();
+        let result_ptr = cb(
+            method_cstr.as_ptr(),
+            path_cstr.as_ptr(),
+            params_cstr.as_ptr(),
+        );
+
+        recycle_cstring(method_cstr);
+        recycle_cstring(path_cstr);
+        recycle_cstring(params_cstr);
+
+        if result_ptr.is_null() {
+            return Err(unavailable(&"Python callback returned null"));
+        }
+
+        // Python owns this buffer: copy it out, never free it from Rust
+        Ok(unsafe { CStr::from_ptr(result_ptr).to_string_lossy().to_string() })
+    })
+    .await
+    .map_err(|e| unavailable(&format!("Python callback task failed: {}", e)))??;
+
+    // Parse response
+    let response_data = match serde_json::from_str::<Value>(&response_json) {
+        Ok(response_data) if response_data.is_object() => response_data,
+        parsed => {
+            let detail = match parsed {
+                Err(e) => format!("not JSON: {}", e),
+                Ok(_) => "expected a JSON object".to_string(),
+            };
+            let preview: String = response_json
+                .chars()
+                .take(INVALID_RESPONSE_PREVIEW_CHARS)
+                .collect();
+            tracing::warn!(
+                "Python handler for {} {} returned an invalid response ({}): {:?}",
+                method, path, detail, preview
+            );
+            return Err(PythonCallError::InvalidResponse { detail, preview });
+        }
+    };
+
+    let body = match response_data["stream"].as_u64() {
+        Some(stream_id) => PythonBody::Stream(stream_id),
+        None => PythonBody::Full(response_data["body"].as_str().unwrap_or("{}").to_string()),
+    };
+    let status = response_data["status"].as_u64().unwrap_or(200) as u16;
+
+    let mut headers = HashMap::new();
+    headers.insert("content-type".to_string(), "application/json".to_string());
+    headers.insert("x-sufast-engine".to_string(), "rust-python-ffi".to_string());
+
+    if let Some(response_headers) = response_data["headers"].as_object() {
+        for (key, value) in response_headers {
+            if let Some(value_str) = value.as_str() {
+                headers.insert(key.clone(), value_str.to_string());
             }
+        }
+    }
+
+    Ok((body, status, headers))
+}
+
+// Response head for a Python handler response; the caller adds tier headers and the body
+fn python_response_head(
+    status: u16,
+    headers: &HashMap<String, String>,
+) -> axum::http::response::Builder {
+    let mut response_builder = Response::builder().status(status);
+    for (key, value) in headers {
+        response_builder = response_builder.header(key, value);
+    }
+    response_builder.header("server", "sufast-ultra/3.0")
+}
+
+// Pull chunks for `stream_id` from the Python stream callback on a blocking thread and
+// forward them through a bounded channel, so at most STREAM_CHANNEL_CAPACITY chunks are
+// held in memory regardless of the response size.
+fn stream_body(stream_id: u64) -> Body {
+    let (tx, rx) =
+        tokio::sync::mpsc::channel::<Result<bytes::Bytes, std::io::Error>>(STREAM_CHANNEL_CAPACITY);
+    let callback = *PYTHON_STREAM_CALLBACK.lock().unwrap();
+
+    tokio::task::spawn_blocking(move || {
+        let Some(callback) = callback else {
+            let _ = tx.blocking_send(Err(std::io::Error::other(
+                "streaming response without a registered stream callback",
+            )));
+            return;
         };
 
-        eprintln!(
-            "[sufast] Rust core listening on {} (routes: {} static, {} dynamic, {} ws)",
-            addr,
-            STATIC_RESPONSES.len(),
-            DYNAMIC_ROUTES.len(),
-            WS_ROUTES.len()
-        );
+        loop {
+            let mut len: i64 = 0;
+            let chunk_ptr = callback(stream_id, &mut len);
 
-        match axum::serve(listener, app).await {
-            Ok(_) => 0,
-            Err(e) => {
-                eprintln!("[sufast] Server error: {}", e);
-                -1
+            if len < 0 {
+                tracing::warn!("Stream {} failed in the Python generator", stream_id);
+                let _ = tx.blocking_send(Err(std::io::Error::other("python stream failed")));
+                return;
+            }
+            if chunk_ptr.is_null() {
+                return;
+            }
+
+            // Python owns the buffer; copy it before asking for the next chunk
+            let chunk = unsafe { std::slice::from_raw_parts(chunk_ptr as *const u8, len as usize) };
+            if tx
+                .blocking_send(Ok(bytes::Bytes::copy_from_slice(chunk)))
+                .is_err()
+            {
+                // Receiver dropped: the client disconnected
+                callback(stream_id, std::ptr::null_mut());
+                return;
             }
         }
-    })
+    });
+
+    Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx))
+}
+
+/// Register the chunk callback used for streaming responses; see the protocol notes on
+/// `PythonStreamCallback`.
+#[no_mangle]
+pub extern "C" fn set_python_stream_callback(callback: PythonStreamCallback) {
+    *PYTHON_STREAM_CALLBACK.lock().unwrap() = Some(callback);
 }
 
 // ========================
-// UTILITY FUNCTIONS 
+// FFI ROUTE REGISTRATION
 // ========================
 
+/// Return codes of the route registration functions. Anything other than `Ok` and
+/// `Replaced` also leaves a detail message for `last_error_message` on the calling thread.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiStatus {
+    Ok = 0,
+    NullArg = 1,
+    InvalidUtf8 = 2,
+    BadPattern = 3,
+    Capacity = 4,
+    // Registered, overwriting an existing route with the same method and path
+    Replaced = 5,
+    NotFound = 6,
+    InvalidHeader = 7,
+}
+
+struct FfiError {
+    status: FfiStatus,
+    message: String,
+}
+
+impl FfiError {
+    fn new(status: FfiStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+thread_local! {
+    // errno-style: the detail for the most recent failed registration on this thread
+    static LAST_ERROR: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+fn ffi_status(result: Result<(), FfiError>) -> i32 {
+    let (status, message) = match result {
+        Ok(()) => (FfiStatus::Ok, None),
+        Err(error) => (error.status, Some(error.message)),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status as i32
+}
+
+// For registrations, which succeed with either `Ok` or `Replaced`
+fn registration_status(result: Result<FfiStatus, FfiError>) -> i32 {
+    match result {
+        Ok(status) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            status as i32
+        }
+        Err(error) => ffi_status(Err(error)),
+    }
+}
+
+// Shadowing is usually an accident, e.g. two handlers decorated with the same path
+fn replaced_status(kind: &str, method: &str, path: &str, replaced: bool) -> FfiStatus {
+    if replaced {
+        eprintln!(
+            "[sufast] {} route {} {} replaced an existing registration",
+            kind, method, path
+        );
+        FfiStatus::Replaced
+    } else {
+        FfiStatus::Ok
+    }
+}
+
+unsafe fn c_str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::new(
+            FfiStatus::NullArg,
+            format!("{} must not be null", name),
+        ));
+    }
+    CStr::from_ptr(ptr).to_str().map_err(|e| {
+        FfiError::new(
+            FfiStatus::InvalidUtf8,
+            format!("{} is not valid UTF-8: {}", name, e),
+        )
+    })
+}
+
+// A C string argument that will be sent as a header value
+unsafe fn header_value_arg(ptr: *const c_char, name: &str) -> Result<String, FfiError> {
+    let value = c_str_arg(ptr, name)?;
+    if HeaderValue::from_str(value).is_err() {
+        return Err(FfiError::new(
+            FfiStatus::InvalidHeader,
+            format!("{} is not a valid header value: {:?}", name, value),
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Detail for the last registration call on this thread that didn't return
+/// `FfiStatus::Ok`, or null if it succeeded. Free with `free_sufast_string`.
 #[no_mangle]
-pub extern "C" fn get_route_count() -> u64 {
-    (STATIC_RESPONSES.len() + DYNAMIC_ROUTES.len()) as u64
+pub extern "C" fn last_error_message() -> *mut c_char {
+    LAST_ERROR.with(|last| match last.borrow().as_deref() {
+        Some(message) => CString::new(message)
+            .unwrap_or_else(|_| CString::new("error message contained a NUL byte").unwrap())
+            .into_raw(),
+        None => std::ptr::null_mut(),
+    })
 }
 
+// Cache-Control for static routes registered without their own directives
+const STATIC_DEFAULT_CACHE_CONTROL: &str = "public, max-age=31536000";
+
+/// Register a pre-built response for `"METHOD:/path"`, cached for a year by clients and
+/// proxies. Returns an `FfiStatus` code.
 #[no_mangle]
-pub extern "C" fn get_ws_route_count() -> u64 {
-    WS_ROUTES.len() as u64
+pub extern "C" fn add_static_route(
+    method_path: *const c_char,
+    response_body: *const c_char,
+    status: u16,
+    content_type: *const c_char,
+) -> i32 {
+    add_static_route_with_cache(
+        method_path,
+        response_body,
+        status,
+        content_type,
+        std::ptr::null(),
+        std::ptr::null(),
+    )
+}
+
+/// `add_static_route` with explicit caching: `cache_control` replaces the one-year
+/// default (e.g. `"no-store"`, `"private, max-age=60"`, `"public, no-cache"`) and `vary`
+/// sets the `Vary` header (e.g. `"Accept-Language"`) so shared caches keep one copy per
+/// variant. Either may be null for the default. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_static_route_with_cache(
+    method_path: *const c_char,
+    response_body: *const c_char,
+    status: u16,
+    content_type: *const c_char,
+    cache_control: *const c_char,
+    vary: *const c_char,
+) -> i32 {
+    registration_status(unsafe {
+        register_static_route(
+            method_path,
+            response_body,
+            status,
+            content_type,
+            cache_control,
+            vary,
+        )
+    })
+}
+
+unsafe fn register_static_route(
+    method_path: *const c_char,
+    response_body: *const c_char,
+    status: u16,
+    content_type: *const c_char,
+    cache_control: *const c_char,
+    vary: *const c_char,
+) -> Result<FfiStatus, FfiError> {
+    let method_path_str = c_str_arg(method_path, "method_path")?;
+    let body_str = c_str_arg(response_body, "response_body")?.to_string();
+    let content_type_str = if content_type.is_null() {
+        "application/json".to_string()
+    } else {
+        c_str_arg(content_type, "content_type")?.to_string()
+    };
+    let cache_control_str = if cache_control.is_null() {
+        STATIC_DEFAULT_CACHE_CONTROL.to_string()
+    } else {
+        header_value_arg(cache_control, "cache_control")?
+    };
+
+    // Request methods are matched uppercase, so "delete:/cache" must be stored as DELETE
+    let (method, path) = match method_path_str.split_once(':') {
+        Some((method, path)) => (method.to_ascii_uppercase(), path.to_string()),
+        None => {
+            record_route_error("static", "", method_path_str, "expected METHOD:path");
+            return Err(FfiError::new(
+                FfiStatus::BadPattern,
+                format!("method_path must be METHOD:path, got {:?}", method_path_str),
+            ));
+        }
+    };
+
+    let mut headers = HashMap::new();
+    headers.insert("content-type".to_string(), content_type_str);
+    headers.insert("x-sufast-optimized".to_string(), "static".to_string());
+    headers.insert("cache-control".to_string(), cache_control_str);
+    if !vary.is_null() {
+        headers.insert("vary".to_string(), header_value_arg(vary, "vary")?);
+    }
+
+    let static_response = StaticResponse::new(body_str, status, headers);
+
+    let method_path_str = format!("{}:{}", method, path);
+    if !STATIC_RESPONSES.contains_key(&method_path_str) && !has_route_capacity() {
+        record_route_error("static", &method, &path, "route limit reached");
+        return Err(route_limit_error());
+    }
+    clear_route_error("static", &method, &path);
+    let replaced = STATIC_RESPONSES
+        .insert(method_path_str, static_response)
+        .is_some();
+    Ok(replaced_status("Static", &method, &path, replaced))
+}
+
+/// Register a dynamic route served by the Python callback. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_dynamic_route(
+    method: *const c_char,
+    pattern: *const c_char,
+    handler_name: *const c_char,
+    cache_ttl_seconds: u64,
+) -> i32 {
+    add_dynamic_route_with_vary(
+        method,
+        pattern,
+        handler_name,
+        cache_ttl_seconds,
+        std::ptr::null(),
+    )
+}
+
+/// `add_dynamic_route` for responses that depend on request headers. `vary_headers` is a
+/// comma-separated list of header names (e.g. `"Accept-Language, X-Tenant"`): cached
+/// responses are stored per combination of their values, and each response carries a
+/// matching `Vary` header. Null means no vary headers. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_dynamic_route_with_vary(
+    method: *const c_char,
+    pattern: *const c_char,
+    handler_name: *const c_char,
+    cache_ttl_seconds: u64,
+    vary_headers: *const c_char,
+) -> i32 {
+    let result = unsafe {
+        register_dynamic_route(
+            method,
+            pattern,
+            handler_name,
+            cache_ttl_seconds,
+            vary_headers,
+            0,
+        )
+    };
+    if result.is_ok() {
+        rebuild_dynamic_route_order();
+    }
+    registration_status(result)
+}
+
+/// `add_dynamic_route_with_vary` with its own time budget for the Python handler: after
+/// `timeout_seconds` the client gets 504 Gateway Timeout. 0 uses the
+/// `set_request_timeout` default. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_dynamic_route_with_timeout(
+    method: *const c_char,
+    pattern: *const c_char,
+    handler_name: *const c_char,
+    cache_ttl_seconds: u64,
+    vary_headers: *const c_char,
+    timeout_seconds: u64,
+) -> i32 {
+    let result = unsafe {
+        register_dynamic_route(
+            method,
+            pattern,
+            handler_name,
+            cache_ttl_seconds,
+            vary_headers,
+            timeout_seconds,
+        )
+    };
+    if result.is_ok() {
+        rebuild_dynamic_route_order();
+    }
+    registration_status(result)
+}
+
+unsafe fn register_dynamic_route(
+    method: *const c_char,
+    pattern: *const c_char,
+    handler_name: *const c_char,
+    cache_ttl_seconds: u64,
+    vary_headers: *const c_char,
+    timeout_seconds: u64,
+) -> Result<FfiStatus, FfiError> {
+    let method_str = c_str_arg(method, "method")?.to_string();
+    let pattern_str = c_str_arg(pattern, "pattern")?.to_string();
+    let handler_str = c_str_arg(handler_name, "handler_name")?.to_string();
+    let vary = if vary_headers.is_null() {
+        Vec::new()
+    } else {
+        parse_vary_headers(c_str_arg(vary_headers, "vary_headers")?)?
+    };
+    insert_dynamic_route(
+        method_str,
+        pattern_str,
+        handler_str,
+        cache_ttl_seconds,
+        vary,
+        timeout_seconds,
+    )
+}
+
+// "Accept-Language, X-Tenant" -> ["accept-language", "x-tenant"]
+fn parse_vary_headers(list: &str) -> Result<Vec<String>, FfiError> {
+    let mut names: Vec<String> = Vec::new();
+    for name in list
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(FfiError::new(
+                FfiStatus::InvalidHeader,
+                format!("vary_headers contains an invalid header name: {:?}", name),
+            ));
+        }
+        let name = name.to_ascii_lowercase();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+#[derive(Deserialize)]
+struct BatchRoute {
+    method: String,
+    path: String,
+    handler_type: String,
+    #[serde(default)]
+    cache_ttl: u64,
+    #[serde(default)]
+    vary_headers: Vec<String>,
+    #[serde(default)]
+    timeout: u64,
+}
+
+/// Register many dynamic routes in one FFI call. `json_ptr` points at `len` bytes holding
+/// a JSON array of `{"method", "path", "handler_type", "cache_ttl", "vary_headers",
+/// "timeout"}` objects (`cache_ttl` and `timeout`, in seconds, and the `vary_headers`
+/// name list are optional).
+/// Entries that fail to register are skipped. Returns the number of routes added, or -1
+/// if the payload isn't a valid array.
+#[no_mangle]
+pub extern "C" fn add_routes_batch(json_ptr: *const c_char, len: usize) -> i64 {
+    if json_ptr.is_null() {
+        return -1;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(json_ptr as *const u8, len) };
+    let routes: Vec<BatchRoute> = match serde_json::from_slice(bytes) {
+        Ok(routes) => routes,
+        Err(e) => {
+            eprintln!("[sufast] Invalid route batch: {}", e);
+            return -1;
+        }
+    };
+
+    let mut added = 0;
+    for route in routes {
+        let inserted = parse_vary_headers(&route.vary_headers.join(",")).and_then(|vary| {
+            insert_dynamic_route(
+                route.method,
+                route.path,
+                route.handler_type,
+                route.cache_ttl,
+                vary,
+                route.timeout,
+            )
+        });
+        if inserted.is_ok() {
+            added += 1;
+        }
+    }
+
+    // One index rebuild for the whole batch rather than one per route
+    if added > 0 {
+        rebuild_dynamic_route_order();
+    }
+    added
+}
+
+#[derive(Deserialize)]
+struct WarmRequest {
+    #[serde(default = "default_warm_method")]
+    method: String,
+    path: String,
+    // For routes with vary headers: the variant to warm
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+fn default_warm_method() -> String {
+    "GET".to_string()
+}
+
+/// Pre-populate the response cache, e.g. from a deploy script before traffic arrives.
+/// `routes_json` is a JSON array of `{"method", "path", "headers"}` objects (`method`
+/// defaults to GET, `headers` is optional). Each request goes through the normal
+/// dispatch, so the Python handler runs once and a 200 is cached; paths that don't match
+/// a dynamic route with a cache TTL are skipped. Blocks until done and returns the number
+/// of responses cached, or -1 if the payload isn't a valid array.
+#[no_mangle]
+pub extern "C" fn warm_cache(routes_json: *const c_char) -> i64 {
+    let requests: Vec<WarmRequest> = match unsafe { c_str_arg(routes_json, "routes_json") }
+        .map_err(|e| e.message)
+        .and_then(|json| serde_json::from_str(json).map_err(|e| e.to_string()))
+    {
+        Ok(requests) => requests,
+        Err(e) => {
+            eprintln!("[sufast] Invalid cache warm list: {}", e);
+            return -1;
+        }
+    };
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[sufast] Failed to create runtime: {}", e);
+            return -1;
+        }
+    };
+
+    let router = build_router();
+    let mut warmed = 0;
+    runtime.block_on(async {
+        use tower::ServiceExt;
+
+        for warm in requests {
+            let method = warm.method.to_uppercase();
+            let cached_route = match_dynamic_route(&method, &warm.path)
+                .is_some_and(|(route, _)| route.cache_ttl.is_some());
+            if !cached_route {
+                continue;
+            }
+
+            let mut request = axum::http::Request::builder()
+                .method(method.as_str())
+                .uri(&warm.path);
+            for (name, value) in &warm.headers {
+                request = request.header(name, value);
+            }
+            let Ok(request) = request.body(Body::empty()) else {
+                eprintln!(
+                    "[sufast] Skipping invalid cache warm request for {}",
+                    warm.path
+                );
+                continue;
+            };
+
+            match router.clone().oneshot(request).await {
+                Ok(response) if response.status() == StatusCode::OK => warmed += 1,
+                Ok(response) => eprintln!(
+                    "[sufast] Cache warm for {} {} returned {}",
+                    method,
+                    warm.path,
+                    response.status()
+                ),
+                Err(never) => match never {},
+            }
+        }
+    });
+    warmed
+}
+
+// Compile and store a dynamic route; the caller rebuilds the route index afterwards
+fn insert_dynamic_route(
+    method: String,
+    pattern: String,
+    handler_name: String,
+    cache_ttl_seconds: u64,
+    vary_headers: Vec<String>,
+    timeout_seconds: u64,
+) -> Result<FfiStatus, FfiError> {
+    // Request methods are matched uppercase, so "get" must be stored as GET
+    let method = method.to_ascii_uppercase();
+    // Compile fast regex pattern
+    let regex = match compile_ultra_fast_pattern(&pattern) {
+        Ok(regex) => regex,
+        Err(e) => {
+            record_route_error("dynamic", &method, &pattern, &e.to_string());
+            return Err(bad_pattern_error(&pattern, &e));
+        }
+    };
+
+    // Key includes method for proper multi-method routing
+    let key = format!("{}:{}", method, pattern);
+    if !DYNAMIC_ROUTES.contains_key(&key) && !has_route_capacity() {
+        record_route_error("dynamic", &method, &pattern, "route limit reached");
+        return Err(route_limit_error());
+    }
+    clear_route_error("dynamic", &method, &pattern);
+
+    let cache_ttl = if cache_ttl_seconds > 0 {
+        Some(Duration::from_secs(cache_ttl_seconds))
+    } else {
+        None
+    };
+
+    let replaced = replaced_status(
+        "Dynamic",
+        &method,
+        &pattern,
+        DYNAMIC_ROUTES.contains_key(&key),
+    );
+    let dynamic_route = DynamicRoute {
+        method,
+        pattern,
+        regex,
+        handler_name,
+        cache_ttl,
+        vary_headers: vary_headers.into(),
+        timeout: (timeout_seconds > 0).then(|| Duration::from_secs(timeout_seconds)),
+    };
+
+    DYNAMIC_ROUTES.insert(key, dynamic_route);
+    Ok(replaced)
+}
+
+/// Register a WebSocket route. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_websocket_route(pattern: *const c_char, handler_name: *const c_char) -> i32 {
+    ffi_status(unsafe { register_websocket_route(pattern, handler_name) })
+}
+
+unsafe fn register_websocket_route(
+    pattern: *const c_char,
+    handler_name: *const c_char,
+) -> Result<(), FfiError> {
+    let pattern_str = c_str_arg(pattern, "pattern")?.to_string();
+    let handler_str = c_str_arg(handler_name, "handler_name")?.to_string();
+
+    match compile_ultra_fast_pattern(&pattern_str) {
+        Ok(regex) => {
+            clear_route_error("websocket", "GET", &pattern_str);
+            let ws_route = WsRoute {
+                regex,
+                handler_name: handler_str,
+            };
+            WS_ROUTES.insert(pattern_str, ws_route);
+            Ok(())
+        }
+        Err(e) => {
+            record_route_error("websocket", "GET", &pattern_str, &e.to_string());
+            Err(bad_pattern_error(&pattern_str, &e))
+        }
+    }
+}
+
+/// Register a long-poll route: GET requests matching `pattern` wait up to `timeout_secs`
+/// (0 for the 30s default) for `publish_long_poll` on their path. Returns an `FfiStatus`.
+#[no_mangle]
+pub extern "C" fn add_long_poll_route(pattern: *const c_char, timeout_secs: u64) -> i32 {
+    ffi_status(unsafe { register_long_poll_route(pattern, timeout_secs) })
+}
+
+unsafe fn register_long_poll_route(
+    pattern: *const c_char,
+    timeout_secs: u64,
+) -> Result<(), FfiError> {
+    let pattern_str = c_str_arg(pattern, "pattern")?.to_string();
+
+    match compile_ultra_fast_pattern(&pattern_str) {
+        Ok(regex) => {
+            clear_route_error("long_poll", "GET", &pattern_str);
+            let timeout = if timeout_secs > 0 {
+                Duration::from_secs(timeout_secs)
+            } else {
+                LONG_POLL_DEFAULT_TIMEOUT
+            };
+            LONG_POLL_ROUTES.insert(pattern_str, LongPollRoute { regex, timeout });
+            Ok(())
+        }
+        Err(e) => {
+            record_route_error("long_poll", "GET", &pattern_str, &e.to_string());
+            Err(bad_pattern_error(&pattern_str, &e))
+        }
+    }
+}
+
+/// Publish `data` (a JSON document) to the long-poll channel for `path`, waking every
+/// request parked on it. Returns an `FfiStatus` code, `NotFound` when no long-poll route
+/// matches `path`.
+#[no_mangle]
+pub extern "C" fn publish_long_poll(path: *const c_char, data: *const c_char) -> i32 {
+    ffi_status(unsafe { publish_long_poll_update(path, data) })
+}
+
+unsafe fn publish_long_poll_update(
+    path: *const c_char,
+    data: *const c_char,
+) -> Result<(), FfiError> {
+    let path_str = c_str_arg(path, "path")?.to_string();
+    let data_str = c_str_arg(data, "data")?.to_string();
+    let timeout = match_long_poll_route(&path_str).ok_or_else(|| {
+        FfiError::new(
+            FfiStatus::NotFound,
+            format!("no long-poll route matches '{}'", path_str),
+        )
+    })?;
+
+    LONG_POLL_CHANNELS.retain(|_, sender| !long_poll_channel_idle(sender));
+    // send_modify stores the update even when nobody is currently polling; it is kept
+    // for one poll timeout so a client reconnecting with `since` still gets it
+    LONG_POLL_CHANNELS
+        .entry(path_str)
+        .or_insert_with(|| watch::channel(LongPollState::default()).0)
+        .send_modify(|state| {
+            state.version += 1;
+            state.data = data_str;
+            state.kept_until = Some(Instant::now() + timeout);
+        });
+    Ok(())
+}
+
+fn bad_pattern_error(pattern: &str, error: &regex::Error) -> FfiError {
+    FfiError::new(
+        FfiStatus::BadPattern,
+        format!("invalid route pattern '{}': {}", pattern, error),
+    )
+}
+
+fn route_limit_error() -> FfiError {
+    FfiError::new(
+        FfiStatus::Capacity,
+        format!(
+            "route limit of {} reached",
+            MAX_ROUTES.load(Ordering::Relaxed)
+        ),
+    )
+}
+
+fn record_route_error(kind: &'static str, method: &str, pattern: &str, reason: &str) {
+    eprintln!(
+        "[sufast] Failed to register {} route {} {}: {}",
+        kind, method, pattern, reason
+    );
+
+    let mut errors = ROUTE_ERRORS.lock().unwrap();
+    errors.retain(|e| !(e.kind == kind && e.method == method && e.pattern == pattern));
+    errors.push(RouteError {
+        kind,
+        method: method.to_string(),
+        pattern: pattern.to_string(),
+        reason: reason.to_string(),
+    });
+}
+
+fn clear_route_error(kind: &str, method: &str, pattern: &str) {
+    ROUTE_ERRORS
+        .lock()
+        .unwrap()
+        .retain(|e| !(e.kind == kind && e.method == method && e.pattern == pattern));
+}
+
+/// JSON report of route registrations that were rejected (pattern failed to compile,
+/// route limit reached) and have not since succeeded:
+/// `{"valid": bool, "errors": [{"kind", "method", "pattern", "reason"}], "routes": {...}}`.
+/// Call after registering routes and before serving to fail fast on config errors.
+/// The caller must release the pointer with `free_sufast_string`.
+#[no_mangle]
+pub extern "C" fn validate_routes() -> *mut c_char {
+    let errors = ROUTE_ERRORS.lock().unwrap().clone();
+
+    let report = json!({
+        "valid": errors.is_empty(),
+        "errors": errors,
+        "routes": {
+            "static": STATIC_RESPONSES.len(),
+            "dynamic": DYNAMIC_ROUTES.len(),
+            "websocket": WS_ROUTES.len(),
+            "long_poll": LONG_POLL_ROUTES.len()
+        }
+    });
+
+    CString::new(report.to_string())
+        .unwrap_or_else(|_| CString::new("{}").unwrap())
+        .into_raw()
+}
+
+fn compile_ultra_fast_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut regex_pattern = pattern.to_string();
+
+    // Fast parameter replacement
+    while let Some(start) = regex_pattern.find('{') {
+        if let Some(end) = regex_pattern[start..].find('}') {
+            let param_name = &regex_pattern[start + 1..start + end];
+            let replacement = format!("(?P<{}>[^/]+)", param_name);
+            regex_pattern.replace_range(start..start + end + 1, &replacement);
+        } else {
+            break;
+        }
+    }
+
+    // Compile with optimizations
+    Regex::new(&format!("^{}$", regex_pattern))
+}
+
+// Whether one more route fits under MAX_ROUTES. Re-registering an existing key replaces
+// it in place, so callers only check this for new keys.
+fn has_route_capacity() -> bool {
+    let max_routes = MAX_ROUTES.load(Ordering::Relaxed);
+    let current = (STATIC_RESPONSES.len() + DYNAMIC_ROUTES.len()) as u64;
+    if max_routes > 0 && current >= max_routes {
+        eprintln!(
+            "[sufast] Route limit reached ({} routes); rejecting registration",
+            max_routes
+        );
+        return false;
+    }
+    true
+}
+
+/// Cap the total number of static and dynamic routes. Registrations past the cap return
+/// `FfiStatus::Capacity` (4); routes already registered are kept. 0 removes the cap.
+#[no_mangle]
+pub extern "C" fn set_max_routes(max_routes: u64) {
+    MAX_ROUTES.store(max_routes, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "C" fn set_python_callback(callback: PythonCallback) {
+    let mut cb = PYTHON_CALLBACK.lock().unwrap();
+    *cb = Some(callback);
+}
+
+/// Set how paths with a trailing slash are handled: "strict" (no normalization),
+/// "ignore" (match as if the slash were absent) or "redirect" (308 to the slashless path).
+#[no_mangle]
+pub extern "C" fn set_trailing_slash_policy(policy: *const c_char) -> bool {
+    if policy.is_null() {
+        return false;
+    }
+
+    let policy_str = unsafe { CStr::from_ptr(policy).to_string_lossy().to_lowercase() };
+    let value = match policy_str.as_str() {
+        "strict" => TRAILING_SLASH_STRICT,
+        "ignore" => TRAILING_SLASH_IGNORE,
+        "redirect" => TRAILING_SLASH_REDIRECT,
+        _ => return false,
+    };
+
+    TRAILING_SLASH_POLICY.store(value, Ordering::Relaxed);
+    true
+}
+
+/// Replay the first response to a POST/PUT/PATCH/DELETE carrying an `Idempotency-Key`
+/// header instead of running the handler again. Disabled by default.
+#[no_mangle]
+pub extern "C" fn enable_idempotency_keys(enabled: bool) {
+    IDEMPOTENCY_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// How long responses to `Idempotency-Key` requests are kept for replay (default 24h).
+#[no_mangle]
+pub extern "C" fn set_idempotency_ttl(seconds: u64) {
+    IDEMPOTENCY_TTL_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Most `Idempotency-Key` responses kept at once (default 10,000; 0 = unlimited). Past
+/// it, the oldest are evicted first.
+#[no_mangle]
+pub extern "C" fn set_idempotency_max_entries(max: usize) {
+    IDEMPOTENCY_MAX_ENTRIES.store(max, Ordering::Relaxed);
+}
+
+/// Randomize each cached response's TTL within ±`fraction` of the route's TTL (e.g. 0.1
+/// for ±10%) to spread out expirations. 0 (the default) disables jitter. Returns false,
+/// leaving the setting unchanged, unless `fraction` is in `[0, 1]`.
+#[no_mangle]
+pub extern "C" fn set_cache_ttl_jitter(fraction: f64) -> bool {
+    if !(0.0..=1.0).contains(&fraction) {
+        return false;
+    }
+    CACHE_TTL_JITTER_BITS.store(fraction.to_bits(), Ordering::Relaxed);
+    true
+}
+
+/// Log requests that take longer than `ms` milliseconds (default 1000) as a warning
+/// with method, path, duration and tier. 0 turns slow-request logging off.
+#[no_mangle]
+pub extern "C" fn set_slow_request_threshold_ms(ms: u64) {
+    SLOW_REQUEST_THRESHOLD_MS.store(ms, Ordering::Relaxed);
+}
+
+/// Longest request path, including the query string, accepted (default 8192 bytes).
+/// Longer requests get 414 URI Too Long before any route matching. 0 restores the default.
+#[no_mangle]
+pub extern "C" fn set_max_path_length(n: usize) {
+    MAX_PATH_LENGTH.store(if n > 0 { n } else { 8 * 1024 }, Ordering::Relaxed);
+}
+
+/// Shed load above `n` concurrent requests: further requests get 503 with `Retry-After`
+/// immediately instead of queueing behind the backlog. 0 (the default) disables shedding.
+#[no_mangle]
+pub extern "C" fn set_max_inflight(n: usize) {
+    MAX_IN_FLIGHT.store(n, Ordering::Relaxed);
+}
+
+/// Close keep-alive connections after `n` requests by answering the last one with
+/// `Connection: close`, so clients reconnect (and get rebalanced). 0 (the default) keeps
+/// connections open indefinitely.
+#[no_mangle]
+pub extern "C" fn set_max_requests_per_connection(n: u64) {
+    MAX_REQUESTS_PER_CONNECTION.store(n, Ordering::Relaxed);
+}
+
+/// Answer every request with 503 and `Retry-After`, except paths under the maintenance
+/// allowlist. Takes effect for the next request; no restart needed.
+#[no_mangle]
+pub extern "C" fn set_maintenance_mode(enabled: bool) {
+    MAINTENANCE_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Comma-separated path prefixes that keep working in maintenance mode (e.g.
+/// `"/health, /admin"`); each covers its subpaths. Null clears the list. Returns an
+/// `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn set_maintenance_allowlist(paths: *const c_char) -> i32 {
+    let result = unsafe { parse_maintenance_allowlist(paths) }.map(|paths| {
+        *MAINTENANCE_ALLOWLIST.write().unwrap() = paths;
+    });
+    ffi_status(result)
+}
+
+unsafe fn parse_maintenance_allowlist(paths: *const c_char) -> Result<Vec<String>, FfiError> {
+    if paths.is_null() {
+        return Ok(Vec::new());
+    }
+    let mut prefixes = Vec::new();
+    for path in c_str_arg(paths, "paths")?
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        if !path.starts_with('/') {
+            return Err(FfiError::new(
+                FfiStatus::BadPattern,
+                format!(
+                    "maintenance allowlist paths must start with '/': {:?}",
+                    path
+                ),
+            ));
+        }
+        prefixes.push(path.to_string());
+    }
+    Ok(prefixes)
+}
+
+/// Run `transform` on every response whose path is under `prefix` (e.g. `/api` covers
+/// `/api/users`), after the handler and before it is sent. When several prefixes match,
+/// the longest wins; registering the same prefix again replaces its transform. Responses
+/// that are streamed, compressed, or larger than 10 MiB are sent untouched. Returns an
+/// `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn add_response_transform(
+    prefix: *const c_char,
+    transform: ResponseTransformCallback,
+) -> i32 {
+    let result = unsafe { c_str_arg(prefix, "prefix") }.and_then(|prefix| {
+        if !prefix.starts_with('/') {
+            return Err(FfiError::new(
+                FfiStatus::BadPattern,
+                format!("transform prefix must start with '/': {:?}", prefix),
+            ));
+        }
+        let prefix = prefix.trim_end_matches('/').to_string();
+        let mut transforms = RESPONSE_TRANSFORMS.write().unwrap();
+        transforms.retain(|(existing, _)| *existing != prefix);
+        transforms.push((prefix, transform));
+        Ok(())
+    });
+    ffi_status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn clear_response_transforms() {
+    RESPONSE_TRANSFORMS.write().unwrap().clear();
+}
+
+/// Let HTML form clients send PUT/PATCH/DELETE as a POST carrying
+/// `X-HTTP-Method-Override` or `?_method=`. Disabled by default.
+#[no_mangle]
+pub extern "C" fn enable_method_override(enabled: bool) {
+    METHOD_OVERRIDE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "C" fn get_performance_stats() -> *mut c_char {
+    let static_hits = STATIC_HITS.load(Ordering::Relaxed);
+    let cache_hits = CACHE_HITS.load(Ordering::Relaxed);
+    let dynamic_hits = DYNAMIC_HITS.load(Ordering::Relaxed);
+    let ws_conns = WS_CONNECTIONS.load(Ordering::Relaxed);
+    let total = static_hits + cache_hits + dynamic_hits;
+
+    let stats = json!({
+        "total_requests": total,
+        "static_hits": static_hits,
+        "cache_hits": cache_hits,
+        "dynamic_hits": dynamic_hits,
+        "coalesced_hits": COALESCED_HITS.load(Ordering::Relaxed),
+        "websocket_connections": ws_conns,
+        "in_flight_requests": IN_FLIGHT_REQUESTS.load(Ordering::Relaxed),
+        "active_connections": ACTIVE_CONNECTIONS.load(Ordering::Relaxed),
+        "total_connections": TOTAL_CONNECTIONS.load(Ordering::Relaxed),
+        "shed_requests": SHED_REQUESTS.load(Ordering::Relaxed),
+        "performance_breakdown": {
+            "static_percentage": if total > 0 { (static_hits as f64 / total as f64) * 100.0 } else { 0.0 },
+            "cache_percentage": if total > 0 { (cache_hits as f64 / total as f64) * 100.0 } else { 0.0 },
+            "dynamic_percentage": if total > 0 { (dynamic_hits as f64 / total as f64) * 100.0 } else { 0.0 }
+        },
+        "route_counts": {
+            "static_routes": STATIC_RESPONSES.len(),
+            "cached_responses": RESPONSE_CACHE.len(),
+            "dynamic_patterns": DYNAMIC_ROUTES.len(),
+            "websocket_routes": WS_ROUTES.len(),
+            "long_poll_routes": LONG_POLL_ROUTES.len(),
+            "total_routes": STATIC_RESPONSES.len() + DYNAMIC_ROUTES.len(),
+            "max_routes": MAX_ROUTES.load(Ordering::Relaxed)
+        },
+        "server": "sufast-ultra/3.0"
+    });
+
+    let cstring = CString::new(stats.to_string())
+        .unwrap_or_else(|_| CString::new("{}").unwrap());
+    // Caller must call free_sufast_string(ptr) to avoid memory leak
+    cstring.into_raw()
+}
+
+/// JSON array of every static and dynamic route, `[{"method", "path", "is_dynamic",
+/// "cache_ttl"}]`, sorted by path then method. `cache_ttl` is the dynamic route's response
+/// cache TTL in seconds, or null. Free with `free_sufast_string`.
+#[no_mangle]
+pub extern "C" fn list_routes() -> *mut c_char {
+    let mut routes: Vec<(String, String, bool, Option<u64>)> = STATIC_RESPONSES
+        .iter()
+        .map(|entry| {
+            let (method, path) = entry.key().split_once(':').unwrap_or(("", entry.key()));
+            (path.to_string(), method.to_string(), false, None)
+        })
+        .chain(DYNAMIC_ROUTES.iter().map(|route| {
+            (
+                route.pattern.clone(),
+                route.method.clone(),
+                true,
+                route.cache_ttl.map(|ttl| ttl.as_secs()),
+            )
+        }))
+        .collect();
+    routes.sort();
+
+    let routes: Vec<Value> = routes
+        .into_iter()
+        .map(|(path, method, is_dynamic, cache_ttl)| {
+            json!({"method": method, "path": path, "is_dynamic": is_dynamic, "cache_ttl": cache_ttl})
+        })
+        .collect();
+
+    CString::new(Value::Array(routes).to_string())
+        .unwrap_or_else(|_| CString::new("[]").unwrap())
+        .into_raw()
+}
+
+/// Free a string returned by a Sufast FFI function (`get_performance_stats`,
+/// `list_routes`, `validate_routes`, `last_error_message`, `render_template`).
+/// Every such pointer must be passed here exactly once; null is ignored.
+#[no_mangle]
+pub extern "C" fn free_sufast_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        unsafe {
+            drop(CString::from_raw(ptr));
+        }
+    }
+}
+
+/// Older name for `free_sufast_string`, kept for existing bindings.
+#[no_mangle]
+pub extern "C" fn free_rust_string(ptr: *mut c_char) {
+    free_sufast_string(ptr)
+}
+
+#[no_mangle]
+pub extern "C" fn clear_cache() -> bool {
+    RESPONSE_CACHE.clear();
+    CACHE_VARY_HEADERS.clear();
+    true
+}
+
+/// Remove every cached response whose path starts with `prefix`, across all methods.
+/// Returns the number of entries removed.
+#[no_mangle]
+pub extern "C" fn invalidate_cache_prefix(prefix: *const c_char) -> u64 {
+    if prefix.is_null() {
+        return 0;
+    }
+
+    let prefix_str = unsafe { CStr::from_ptr(prefix).to_string_lossy().to_string() };
+
+    let mut removed = 0u64;
+    RESPONSE_CACHE.retain(|key, _| match key.split_once(':') {
+        Some((_, path)) if path.starts_with(&prefix_str) => {
+            removed += 1;
+            false
+        }
+        _ => true,
+    });
+    removed
+}
+
+/// Remove the cached response for a single method + path, including every variant cached
+/// for a route with vary headers. Returns the number of entries removed.
+#[no_mangle]
+pub extern "C" fn invalidate_cache_exact(method: *const c_char, path: *const c_char) -> u64 {
+    if method.is_null() || path.is_null() {
+        return 0;
+    }
+
+    let (method_str, path_str) = unsafe {
+        (
+            CStr::from_ptr(method).to_string_lossy().to_string(),
+            CStr::from_ptr(path).to_string_lossy().to_string(),
+        )
+    };
+
+    let key = format!("{}:{}", method_str, path_str);
+    let mut removed = 0u64;
+    RESPONSE_CACHE.retain(|cached_key, _| {
+        if cached_key.split('\n').next() == Some(key.as_str()) {
+            removed += 1;
+            false
+        } else {
+            true
+        }
+    });
+    removed
+}
+
+/// Replace the permissive default CORS policy with a JSON object of `allow_origins`,
+/// `allow_methods`, `allow_headers`, `max_age` and `allow_credentials` (omitted keys allow
+/// any origin, method or header). Listed origins are echoed back only when they match.
+/// Null restores the default. Takes effect for servers started afterwards. Returns false
+/// for invalid JSON or values.
+#[no_mangle]
+pub extern "C" fn set_cors_config(config_json: *const c_char) -> bool {
+    if config_json.is_null() {
+        *CORS_LAYER.write().unwrap() = None;
+        return true;
+    }
+    let layer = unsafe { c_str_arg(config_json, "config_json") }
+        .map_err(|e| e.message)
+        .and_then(|json| serde_json::from_str::<CorsConfig>(json).map_err(|e| e.to_string()))
+        .and_then(|config| config.to_layer());
+    match layer {
+        Ok(layer) => {
+            *CORS_LAYER.write().unwrap() = Some(layer);
+            true
+        }
+        Err(e) => {
+            eprintln!("[sufast] Invalid CORS config: {}", e);
+            false
+        }
+    }
+}
+
+/// Directory `render_template` loads templates from (default `"templates"`). Returns
+/// false for a null or non-UTF-8 path.
+#[no_mangle]
+pub extern "C" fn set_template_dir(dir: *const c_char) -> bool {
+    match unsafe { c_str_arg(dir, "dir") } {
+        Ok(dir) => {
+            *TEMPLATE_ENGINE.write().unwrap() = templates::TemplateEngine::new(dir);
+            true
+        }
+        Err(e) => {
+            eprintln!("[sufast] Invalid template directory: {}", e.message);
+            false
+        }
+    }
+}
+
+/// Render `template_name` from the template directory with a JSON object as context.
+/// On failure the result starts with `TEMPLATE_ERROR_MARKER` followed by `not_found:`,
+/// `invalid_context:` or `render_failed:` and a message.
+/// The caller owns the returned pointer and must release it with `free_sufast_string`.
+#[no_mangle]
+pub extern "C" fn render_template(
+    template_name: *const c_char,
+    context_json: *const c_char,
+) -> *mut c_char {
+    let output = unsafe { render_template_to_string(template_name, context_json) }
+        .unwrap_or_else(|(kind, message)| format!("{}{}:{}", TEMPLATE_ERROR_MARKER, kind, message));
+
+    // Template output can't carry NUL bytes across the C boundary
+    CString::new(output)
+        .unwrap_or_else(|_| {
+            CString::new(format!(
+                "{}render_failed:output contains a NUL byte",
+                TEMPLATE_ERROR_MARKER
+            ))
+            .unwrap()
+        })
+        .into_raw()
+}
+
+unsafe fn render_template_to_string(
+    template_name: *const c_char,
+    context_json: *const c_char,
+) -> Result<String, (&'static str, String)> {
+    let name = c_str_arg(template_name, "template_name").map_err(|e| ("not_found", e.message))?;
+    let context: HashMap<String, Value> = if context_json.is_null() {
+        HashMap::new()
+    } else {
+        let json =
+            c_str_arg(context_json, "context_json").map_err(|e| ("invalid_context", e.message))?;
+        serde_json::from_str(json).map_err(|e| ("invalid_context", e.to_string()))?
+    };
+
+    TEMPLATE_ENGINE
+        .read()
+        .unwrap()
+        .render(name, &context)
+        .map_err(|e| match e {
+            templates::TemplateError::TemplateNotFound(name) => ("not_found", name),
+            other => ("render_failed", other.to_string()),
+        })
+}
+
+/// Database checked by `/healthz` (e.g. `"sqlite://app.db"`); its pool is opened when the
+/// server starts. Null clears it. Returns an `FfiStatus` code.
+#[no_mangle]
+pub extern "C" fn set_database_url(url: *const c_char) -> i32 {
+    let result = if url.is_null() {
+        Ok(None)
+    } else {
+        unsafe { c_str_arg(url, "url") }.map(|url| Some(url.to_string()))
+    };
+    ffi_status(result.map(|url| {
+        *DATABASE_URL.write().unwrap() = url;
+    }))
+}
+
+/// Default time budget in seconds for dynamic routes' Python handlers (default 30); past
+/// it the client gets 504 Gateway Timeout. 0 disables the timeout. Routes registered with
+/// `add_dynamic_route_with_timeout` keep their own value.
+#[no_mangle]
+pub extern "C" fn set_request_timeout(seconds: u64) {
+    REQUEST_TIMEOUT_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Seconds between background sweeps that evict expired response cache entries
+/// (default 60). 0 disables sweeping; expired entries are then only replaced on their next hit.
+#[no_mangle]
+pub extern "C" fn set_cache_sweep_interval(seconds: u64) {
+    CACHE_SWEEP_INTERVAL_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Unregister the route for a method + path: the static response registered under it,
+/// the dynamic route whose pattern is exactly `path`, and any responses cached for
+/// either. Returns whether a route was removed.
+#[no_mangle]
+pub extern "C" fn remove_route(method: *const c_char, path: *const c_char) -> bool {
+    if method.is_null() || path.is_null() {
+        return false;
+    }
+
+    let (method_str, path_str) = unsafe {
+        (
+            CStr::from_ptr(method).to_string_lossy().to_string(),
+            CStr::from_ptr(path).to_string_lossy().to_string(),
+        )
+    };
+
+    let key = format!("{}:{}", method_str, path_str);
+    let removed_static = STATIC_RESPONSES.remove(&key).is_some();
+    RESPONSE_CACHE.remove(&key);
+
+    let removed_dynamic = match DYNAMIC_ROUTES.remove(&key) {
+        Some((_, route)) => {
+            invalidate_cached_route(&route);
+            rebuild_dynamic_route_order();
+            true
+        }
+        None => false,
+    };
+
+    removed_static || removed_dynamic
+}
+
+/// Unregister every dynamic route registered with `pattern`, for all methods, and drop
+/// the responses cached for them. Returns whether anything was removed.
+#[no_mangle]
+pub extern "C" fn remove_dynamic_route(pattern: *const c_char) -> bool {
+    if pattern.is_null() {
+        return false;
+    }
+
+    let pattern_str = unsafe { CStr::from_ptr(pattern).to_string_lossy().to_string() };
+
+    let mut removed = Vec::new();
+    DYNAMIC_ROUTES.retain(|_, route| {
+        if route.pattern == pattern_str {
+            removed.push(route.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    if removed.is_empty() {
+        return false;
+    }
+
+    for route in &removed {
+        invalidate_cached_route(route);
+    }
+    rebuild_dynamic_route_order();
+    true
+}
+
+// Cache keys are concrete "METHOD:path" strings, so find the ones this route produced
+// by matching them against its pattern
+fn invalidate_cached_route(route: &DynamicRoute) {
+    let served_by_route = |key: &str| {
+        // Drop any vary variant suffix before matching the path
+        let key = key.split('\n').next().unwrap_or(key);
+        match key.split_once(':') {
+            Some((method, path)) => method == route.method && route.regex.is_match(path),
+            None => false,
+        }
+    };
+    RESPONSE_CACHE.retain(|key, _| !served_by_route(key));
+    // Otherwise a later static route at the same path would be looked up per vary variant
+    CACHE_VARY_HEADERS.retain(|key, _| !served_by_route(key));
+}
+
+#[no_mangle]
+pub extern "C" fn precompile_static_routes() -> u64 {
+    // Pre-compile default static routes
+    let routes = vec![
+        (
+            "GET:/",
+            r#"{"message":"Welcome to Sufast v3.0!","server":"sufast-ultra/3.0","docs":"/docs"}"#,
+            200,
+            "application/json",
+        ),
+        (
+            "GET:/health",
+            r#"{"status":"healthy","service":"sufast","server":"sufast-ultra/3.0"}"#,
+            200,
+            "application/json",
+        ),
+        (
+            "GET:/api/status",
+            r#"{"api":"active","status":"operational","server":"sufast-ultra/3.0"}"#,
+            200,
+            "application/json",
+        ),
+    ];
+
+    for (route_key, body, status, content_type) in routes {
+        // Only add if not already registered by Python
+        if !STATIC_RESPONSES.contains_key(route_key) {
+            let mut headers = HashMap::new();
+            headers.insert("content-type".to_string(), content_type.to_string());
+            headers.insert("x-sufast-precompiled".to_string(), "true".to_string());
+            headers.insert(
+                "cache-control".to_string(),
+                "public, max-age=3600".to_string(),
+            );
+            headers.insert("server".to_string(), "sufast-ultra/3.0".to_string());
+
+            let static_response = StaticResponse::new(body.to_string(), status, headers);
+
+            STATIC_RESPONSES.insert(route_key.to_string(), static_response);
+        }
+    }
+
+    STATIC_RESPONSES.len() as u64
+}
+
+#[no_mangle]
+pub extern "C" fn start_ultra_fast_server(host: *const c_char, port: u16) -> i32 {
+    if host.is_null() {
+        eprintln!("[sufast] Host parameter cannot be null");
+        return -1;
+    }
+
+    let host_str = unsafe {
+        match CStr::from_ptr(host).to_str() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("[sufast] Invalid host string: {}", e);
+                return -1;
+            }
+        }
+    };
+
+    let runtime = match build_server_runtime() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[sufast] Failed to create runtime: {}", e);
+            return -1;
+        }
+    };
+
+    runtime.block_on(async {
+        let app = build_router();
+        let options = *LISTENER_OPTIONS.lock().unwrap();
+        tokio::spawn(cache_sweeper());
+        connect_configured_database().await;
+
+        let addr = format!("{}:{}", host_str, port);
+        let listener = match bind_listener(&addr, &options).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("[sufast] Failed to bind to {}: {}", addr, e);
+                return -1;
+            }
+        };
+
+        eprintln!(
+            "[sufast] Rust core listening on {} (routes: {} static, {} dynamic, {} ws)",
+            addr,
+            STATIC_RESPONSES.len(),
+            DYNAMIC_ROUTES.len(),
+            WS_ROUTES.len()
+        );
+
+        match axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<ConnectionInfo>(),
+        )
+        .tcp_nodelay(options.nodelay)
+        .await
+        {
+            Ok(_) => 0,
+            Err(e) => {
+                eprintln!("[sufast] Server error: {}", e);
+                -1
+            }
+        }
+    })
+}
+
+/// Start the HTTPS server with a PEM certificate chain and private key; ALPN offers h2
+/// and http/1.1. Returns 0 on clean shutdown, -1 for bad arguments or runtime failure,
+/// -2 when the cert or key file is missing, -3 when it can't be parsed, -4 for an
+/// unresolvable address and -5 for server errors.
+#[no_mangle]
+pub extern "C" fn start_sufast_server_tls(
+    host: *const c_char,
+    port: u16,
+    cert_path: *const c_char,
+    key_path: *const c_char,
+) -> i32 {
+    if host.is_null() || cert_path.is_null() || key_path.is_null() {
+        eprintln!("[sufast] Host, certificate and key paths cannot be null");
+        return -1;
+    }
+
+    let args = unsafe {
+        (
+            CStr::from_ptr(host).to_str(),
+            CStr::from_ptr(cert_path).to_str(),
+            CStr::from_ptr(key_path).to_str(),
+        )
+    };
+    let (host_str, cert_str, key_str) = match args {
+        (Ok(host), Ok(cert), Ok(key)) => (host, cert, key),
+        _ => {
+            eprintln!("[sufast] Host, certificate and key paths must be valid UTF-8");
+            return -1;
+        }
+    };
+
+    let runtime = match build_server_runtime() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[sufast] Failed to create runtime: {}", e);
+            return -1;
+        }
+    };
+
+    runtime.block_on(serve_tls(
+        format!("{}:{}", host_str, port),
+        cert_str,
+        key_str,
+    ))
+}
+
+async fn serve_tls(addr: String, cert_path: &str, key_path: &str) -> i32 {
+    for file in [cert_path, key_path] {
+        if !std::path::Path::new(file).is_file() {
+            eprintln!("[sufast] Certificate or key file not found: {}", file);
+            return -2;
+        }
+    }
+
+    let tls_config =
+        match axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_path, key_path).await {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("[sufast] Invalid certificate or key: {}", e);
+                return -3;
+            }
+        };
+
+    let socket_addr = match tokio::net::lookup_host(&addr)
+        .await
+        .map(|mut addrs| addrs.next())
+    {
+        Ok(Some(socket_addr)) => socket_addr,
+        Ok(None) => {
+            eprintln!("[sufast] Invalid address {}: no address resolved", addr);
+            return -4;
+        }
+        Err(e) => {
+            eprintln!("[sufast] Invalid address {}: {}", addr, e);
+            return -4;
+        }
+    };
+
+    tokio::spawn(cache_sweeper());
+    connect_configured_database().await;
+    eprintln!(
+        "[sufast] Rust core listening on https://{} (routes: {} static, {} dynamic, {} ws)",
+        socket_addr,
+        STATIC_RESPONSES.len(),
+        DYNAMIC_ROUTES.len(),
+        WS_ROUTES.len()
+    );
+
+    match axum_server::bind_rustls(socket_addr, tls_config)
+        .serve(build_router().into_make_service_with_connect_info::<ConnectionInfo>())
+        .await
+    {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("[sufast] Server error: {}", e);
+            -5
+        }
+    }
+}
+
+// Expired cache entries are otherwise only dropped when their key is requested again, so
+// keys that never come back would linger. Runs for the life of the server runtime.
+async fn cache_sweeper() {
+    loop {
+        let interval = CACHE_SWEEP_INTERVAL_SECS.load(Ordering::Relaxed);
+        if interval == 0 {
+            // Sweeping disabled; check again later in case it is re-enabled
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        sweep_expired_cache();
+    }
+}
+
+// DashMap::retain locks one shard at a time, so request handling is never fully blocked
+fn sweep_expired_cache() {
+    RESPONSE_CACHE.retain(|_, cached| cached.cached_at.elapsed() < cached.ttl);
+}
+
+/// The router served by `start_ultra_fast_server`, exposed so benchmarks and embedders
+/// can drive the three-tier dispatch without binding a socket.
+pub fn build_router() -> Router {
+    serving_layers(
+        Router::new()
+            .route("/metrics", axum::routing::get(prometheus_metrics))
+            .route("/healthz", axum::routing::get(healthz))
+            .fallback(ultra_fast_handler),
+    )
+}
+
+// Request ids are assigned outside CatchPanicLayer so a panic's 500 can report the same
+// id the handler responded under
+fn serving_layers(router: Router) -> Router {
+    router
+        .layer(axum::middleware::from_fn(count_connection_requests))
+        .layer(CatchPanicLayer::custom(panic_response))
+        .layer(axum::middleware::from_fn(assign_request_id))
+        .layer(axum::middleware::from_fn(count_response_status))
+        .layer(
+            CORS_LAYER
+                .read()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(CorsLayer::permissive),
+        )
+}
+
+// The `set_cors_config` JSON; keys mirror the CorsMiddleware config
+#[derive(Deserialize)]
+struct CorsConfig {
+    #[serde(default = "CorsConfig::wildcard")]
+    allow_origins: Vec<String>,
+    #[serde(default = "CorsConfig::default_methods")]
+    allow_methods: Vec<String>,
+    #[serde(default = "CorsConfig::wildcard")]
+    allow_headers: Vec<String>,
+    #[serde(default)]
+    max_age: Option<u64>,
+    #[serde(default)]
+    allow_credentials: bool,
+}
+
+impl CorsConfig {
+    fn wildcard() -> Vec<String> {
+        vec!["*".to_string()]
+    }
+
+    fn default_methods() -> Vec<String> {
+        ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+            .iter()
+            .map(|method| method.to_string())
+            .collect()
+    }
+
+    // Browsers reject a literal "*" alongside credentials, so with credentials allowed
+    // wildcards mirror the request instead
+    fn to_layer(&self) -> Result<CorsLayer, String> {
+        let wildcard = |values: &[String]| values.iter().any(|value| value == "*");
+        fn parse_all<T: std::str::FromStr>(
+            kind: &str,
+            values: &[String],
+        ) -> Result<Vec<T>, String> {
+            values
+                .iter()
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid CORS {}: {:?}", kind, value))
+                })
+                .collect()
+        }
+
+        let origins = match (wildcard(&self.allow_origins), self.allow_credentials) {
+            (true, true) => AllowOrigin::mirror_request(),
+            (true, false) => AllowOrigin::any(),
+            (false, _) => {
+                AllowOrigin::list(parse_all::<HeaderValue>("origin", &self.allow_origins)?)
+            }
+        };
+        let methods = match (wildcard(&self.allow_methods), self.allow_credentials) {
+            (true, true) => AllowMethods::mirror_request(),
+            (true, false) => AllowMethods::any(),
+            (false, _) => {
+                let upper: Vec<String> = self
+                    .allow_methods
+                    .iter()
+                    .map(|method| method.to_ascii_uppercase())
+                    .collect();
+                AllowMethods::list(parse_all::<Method>("method", &upper)?)
+            }
+        };
+        let headers = match (wildcard(&self.allow_headers), self.allow_credentials) {
+            (true, true) => AllowHeaders::mirror_request(),
+            (true, false) => AllowHeaders::any(),
+            (false, _) => AllowHeaders::list(parse_all::<axum::http::HeaderName>(
+                "header",
+                &self.allow_headers,
+            )?),
+        };
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .allow_credentials(self.allow_credentials);
+        if let Some(max_age) = self.max_age {
+            layer = layer.max_age(Duration::from_secs(max_age));
+        }
+        Ok(layer)
+    }
+}
+
+/// Per-connection state, created when a connection is accepted and attached to each of
+/// its requests as `ConnectInfo<ConnectionInfo>`. Dropped once the connection closes.
+#[derive(Clone)]
+pub struct ConnectionInfo(Arc<ConnectionState>);
+
+struct ConnectionState {
+    requests: AtomicU64,
+}
+
+impl ConnectionInfo {
+    fn open() -> Self {
+        ACTIVE_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        TOTAL_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        Self(Arc::new(ConnectionState {
+            requests: AtomicU64::new(0),
+        }))
+    }
+
+    /// Requests received on this connection so far.
+    pub fn requests(&self) -> u64 {
+        self.0.requests.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ConnectionState {
+    fn drop(&mut self) {
+        ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl axum::extract::connect_info::Connected<axum::serve::IncomingStream<'_>> for ConnectionInfo {
+    fn connect_info(_target: axum::serve::IncomingStream<'_>) -> Self {
+        Self::open()
+    }
+}
+
+// axum-server, used for TLS, hands over the peer address instead of the stream
+impl axum::extract::connect_info::Connected<std::net::SocketAddr> for ConnectionInfo {
+    fn connect_info(_target: std::net::SocketAddr) -> Self {
+        Self::open()
+    }
+}
+
+// Counts requests per connection and, once MAX_REQUESTS_PER_CONNECTION is reached, marks
+// the response `Connection: close` so hyper closes the socket after sending it. Only
+// HTTP/1 has per-connection keep-alive to end this way.
+async fn count_connection_requests(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response<Body> {
+    let connection = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<ConnectionInfo>>()
+        .map(|info| info.0.clone());
+    let http1 = request.version() <= axum::http::Version::HTTP_11;
+
+    let mut response = next.run(request).await;
+    if let Some(connection) = connection {
+        let served = connection.0.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        let max = MAX_REQUESTS_PER_CONNECTION.load(Ordering::Relaxed);
+        if http1 && max > 0 && served >= max {
+            response.headers_mut().insert(
+                axum::http::header::CONNECTION,
+                HeaderValue::from_static("close"),
+            );
+        }
+    }
+    response
+}
+
+/// The caller's `X-Request-ID` when it's safe to reuse, else the request's sequence
+/// number. Set by `assign_request_id` before any handler runs.
+#[derive(Clone)]
+struct RequestId(String);
+
+// Marks the 500 built by `panic_response`, which only sees the panic payload; the
+// request id is filled in by `assign_request_id` on the way out
+#[derive(Clone)]
+struct HandlerPanicked(String);
+
+async fn assign_request_id(
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response<Body> {
+    let request_number = TOTAL_REQUESTS.fetch_add(1, Ordering::Relaxed) + 1;
+    let request_id =
+        incoming_request_id(request.headers()).unwrap_or_else(|| request_number.to_string());
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let response = next.run(request).await;
+    let mut response = match response.extensions().get::<HandlerPanicked>() {
+        Some(HandlerPanicked(message)) => {
+            eprintln!(
+                "[sufast] Handler panicked (request {}): {}",
+                request_id, message
+            );
+            panic_body(&request_id)
+        }
+        None => response,
+    };
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+    response
+}
+
+/// A caller-supplied `X-Request-ID`, if it's safe to reuse: 1-128 characters of
+/// `[A-Za-z0-9-_.:]`, so it can't smuggle header or log injection.
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get("x-request-id")?.to_str().ok()?.trim();
+    let is_safe = !value.is_empty()
+        && value.len() <= 128
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'));
+    is_safe.then(|| value.to_string())
+}
+
+// Handler panics become a JSON 500 carrying the request id instead of a dropped connection
+fn panic_response(panic: Box<dyn std::any::Any + Send + 'static>) -> Response<Body> {
+    let message = if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "unknown panic payload".to_string()
+    };
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+    response.extensions_mut().insert(HandlerPanicked(message));
+    response
+}
+
+fn panic_body(request_id: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header("content-type", "application/json")
+        .header("x-sufast-request-id", request_id)
+        .header("server", "sufast-ultra/3.0")
+        .body(Body::from(
+            json!({
+                "error": "Internal Server Error",
+                "request_id": request_id
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+async fn count_response_status(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response<Body> {
+    let response = next.run(request).await;
+    RESPONSE_STATUS_COUNTS
+        .entry(response.status().as_u16())
+        .or_insert_with(|| AtomicU64::new(0))
+        .fetch_add(1, Ordering::Relaxed);
+    response
+}
+
+// Readiness probe: 200 when every dependency is up, 503 with the breakdown otherwise
+async fn healthz() -> Response<Body> {
+    let configured = DATABASE_URL.read().unwrap().is_some();
+    let pool = DATABASE_POOL.read().unwrap().clone();
+    let (healthy, report) = health_report(configured, pool).await;
+
+    Response::builder()
+        .status(if healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        })
+        .header("content-type", "application/json")
+        .header("cache-control", "no-store")
+        .body(Body::from(report.to_string()))
+        .unwrap()
+}
+
+async fn health_report(
+    database_configured: bool,
+    pool: Option<Arc<database::DatabasePool>>,
+) -> (bool, Value) {
+    const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+    let mut healthy = true;
+    let mut down = |error: String| {
+        healthy = false;
+        json!({"status": "down", "error": error})
+    };
+
+    // SELECT 1 against the pool, if a database is configured
+    let database_check = match (database_configured, pool) {
+        (false, _) => json!({"status": "not_configured"}),
+        (true, None) => down("database pool not connected".to_string()),
+        (true, Some(pool)) => {
+            let started = Instant::now();
+            match tokio::time::timeout(CHECK_TIMEOUT, pool.ping()).await {
+                Ok(Ok(())) => json!({"status": "up", "latency_ms": started.elapsed().as_millis()}),
+                Ok(Err(e)) => down(e.to_string()),
+                Err(_) => down("timed out".to_string()),
+            }
+        }
+    };
+
+    // A probe lookup on a blocking thread, so a stuck shard lock times out instead of
+    // hanging the probe
+    let started = Instant::now();
+    let cache_probe = tokio::task::spawn_blocking(|| {
+        let _ = RESPONSE_CACHE.get("__sufast_healthz__");
+        RESPONSE_CACHE.len()
+    });
+    let cache_check = match tokio::time::timeout(CHECK_TIMEOUT, cache_probe).await {
+        Ok(Ok(entries)) => json!({
+            "status": "up",
+            "entries": entries,
+            "latency_ms": started.elapsed().as_millis()
+        }),
+        Ok(Err(e)) => down(e.to_string()),
+        Err(_) => down("timed out".to_string()),
+    };
+
+    let report = json!({
+        "status": if healthy { "healthy" } else { "unhealthy" },
+        "checks": {
+            "database": database_check,
+            "response_cache": cache_check
+        }
+    });
+    (healthy, report)
+}
+
+// Opens the pool for the database set with `set_database_url`, if any. A failed
+// connection is reported by /healthz rather than aborting startup.
+async fn connect_configured_database() {
+    let url = DATABASE_URL.read().unwrap().clone();
+    if let Some(url) = url {
+        match database::DatabasePool::new(&url).await {
+            Ok(pool) => *DATABASE_POOL.write().unwrap() = Some(Arc::new(pool)),
+            Err(e) => eprintln!("[sufast] Database connection failed: {}", e),
+        }
+    }
+}
+
+// Prometheus text exposition format (version 0.0.4)
+async fn prometheus_metrics() -> Response<Body> {
+    let mut body = String::new();
+    let metrics = [
+        (
+            "sufast_requests_total",
+            "counter",
+            "Requests received by the Rust core.",
+            TOTAL_REQUESTS.load(Ordering::Relaxed),
+        ),
+        (
+            "sufast_static_hits_total",
+            "counter",
+            "Requests served from the static route tier.",
+            STATIC_HITS.load(Ordering::Relaxed),
+        ),
+        (
+            "sufast_cache_hits_total",
+            "counter",
+            "Requests served from the response cache tier.",
+            CACHE_HITS.load(Ordering::Relaxed),
+        ),
+        (
+            "sufast_dynamic_hits_total",
+            "counter",
+            "Requests that reached the Python tier.",
+            DYNAMIC_HITS.load(Ordering::Relaxed),
+        ),
+        (
+            "sufast_cache_entries",
+            "gauge",
+            "Entries currently in the response cache.",
+            RESPONSE_CACHE.len() as u64,
+        ),
+        (
+            "sufast_static_routes",
+            "gauge",
+            "Registered static routes.",
+            STATIC_RESPONSES.len() as u64,
+        ),
+        (
+            "sufast_dynamic_routes",
+            "gauge",
+            "Registered dynamic routes.",
+            DYNAMIC_ROUTES.len() as u64,
+        ),
+    ];
+    for (name, kind, help, value) in metrics {
+        body.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+            name, help, name, kind, name, value
+        ));
+    }
+
+    body.push_str("# HELP sufast_responses_total Responses sent, by HTTP status code.\n");
+    body.push_str("# TYPE sufast_responses_total counter\n");
+    let mut status_counts: Vec<(u16, u64)> = RESPONSE_STATUS_COUNTS
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+        .collect();
+    status_counts.sort_unstable_by_key(|(status, _)| *status);
+    for (status, count) in status_counts {
+        body.push_str(&format!(
+            "sufast_responses_total{{status=\"{}\"}} {}\n",
+            status, count
+        ));
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
+        .header("cache-control", "no-store")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+// available_parallelism honours cgroup CPU quotas and affinity masks, so a container
+// limited to 2 CPUs gets 2 workers even on a 64-core host
+fn worker_thread_count() -> usize {
+    let available = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    match WORKER_THREADS.load(Ordering::Relaxed) {
+        0 => available,
+        requested => requested.min(available),
+    }
+}
+
+fn build_server_runtime() -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(worker_thread_count())
+        .enable_all()
+        .build()
+}
+
+/// Worker threads for the runtime of the next `start_ultra_fast_server`, and the
+/// default worker count of `start_sufast_server_multi`. 0 (the default) uses one per CPU
+/// available to the process; larger counts are capped at that so a CPU-limited container
+/// isn't oversubscribed.
+#[no_mangle]
+pub extern "C" fn set_worker_threads(n: u32) {
+    WORKER_THREADS.store(n as usize, Ordering::Relaxed);
+}
+
+/// Like `start_ultra_fast_server`, but with `workers` accept loops: each runs on its own
+/// thread, with its own single-threaded runtime and its own `SO_REUSEPORT` listener on
+/// `host:port`, so the kernel spreads connections across threads instead of funnelling
+/// them through one accept loop. Routes and caches are global, so every worker serves
+/// the same app; `workers` of 0 uses the `set_worker_threads` count. Returns -1 if any
+/// listener fails to bind, before any of them serves.
+/// `SO_REUSEPORT` is Unix-only; elsewhere this is `start_ultra_fast_server`.
+#[no_mangle]
+pub extern "C" fn start_sufast_server_multi(host: *const c_char, port: u16, workers: u32) -> i32 {
+    #[cfg(not(unix))]
+    {
+        let _ = workers;
+        start_ultra_fast_server(host, port)
+    }
+
+    #[cfg(unix)]
+    {
+        let host_str = match unsafe { c_str_arg(host, "host") } {
+            Ok(host_str) => host_str,
+            Err(e) => {
+                eprintln!("[sufast] {}", e.message);
+                return -1;
+            }
+        };
+        let workers = match workers {
+            0 => worker_thread_count(),
+            workers => workers as usize,
+        };
+        serve_reuseport(host_str, port, workers)
+    }
+}
+
+#[cfg(unix)]
+fn serve_reuseport(host: &str, port: u16, workers: usize) -> i32 {
+    let options = ListenerOptions {
+        reuse_port: true,
+        ..*LISTENER_OPTIONS.lock().unwrap()
+    };
+    let addr = format!("{}:{}", host, port);
+
+    // Bind every listener up front so a bad address fails the call instead of one worker
+    let bind_runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[sufast] Failed to create runtime: {}", e);
+            return -1;
+        }
+    };
+    let listeners: std::io::Result<Vec<std::net::TcpListener>> = bind_runtime.block_on(async {
+        let mut listeners = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            listeners.push(bind_listener(&addr, &options).await?.into_std()?);
+        }
+        Ok(listeners)
+    });
+    drop(bind_runtime);
+
+    let listeners = match listeners {
+        Ok(listeners) => listeners,
+        Err(e) => {
+            eprintln!("[sufast] Failed to bind to {}: {}", addr, e);
+            return -1;
+        }
+    };
+
+    eprintln!(
+        "[sufast] Rust core listening on {} with {} SO_REUSEPORT workers (routes: {} static, {} dynamic, {} ws)",
+        addr,
+        workers,
+        STATIC_RESPONSES.len(),
+        DYNAMIC_ROUTES.len(),
+        WS_ROUTES.len()
+    );
+
+    let mut handles = Vec::with_capacity(workers);
+    for (worker, listener) in listeners.into_iter().enumerate() {
+        let spawned = std::thread::Builder::new()
+            .name(format!("sufast-worker-{}", worker))
+            .spawn(move || -> std::io::Result<()> {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?;
+                runtime.block_on(async move {
+                    // The cache is shared by every worker, so one sweeper is enough
+                    if worker == 0 {
+                        tokio::spawn(cache_sweeper());
+                        connect_configured_database().await;
+                    }
+                    let listener = TcpListener::from_std(listener)?;
+                    axum::serve(
+                        listener,
+                        build_router().into_make_service_with_connect_info::<ConnectionInfo>(),
+                    )
+                    .tcp_nodelay(options.nodelay)
+                    .await
+                })
+            });
+        match spawned {
+            Ok(handle) => handles.push(handle),
+            Err(e) => eprintln!("[sufast] Failed to start worker {}: {}", worker, e),
+        }
+    }
+
+    let mut status = if handles.len() == workers { 0 } else { -1 };
+    for handle in handles {
+        match handle.join() {
+            Ok(Ok(())) => {}
+            // Includes a worker whose runtime couldn't be built
+            Ok(Err(e)) => {
+                eprintln!("[sufast] A server worker stopped with an error: {}", e);
+                status = -1;
+            }
+            Err(_) => {
+                eprintln!("[sufast] A server worker panicked");
+                status = -1;
+            }
+        }
+    }
+    status
+}
+
+// Bind through TcpSocket rather than TcpListener::bind so the backlog and socket options
+// can be set before listen()
+async fn bind_listener(addr: &str, options: &ListenerOptions) -> std::io::Result<TcpListener> {
+    let socket_addr = tokio::net::lookup_host(addr).await?.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "address did not resolve")
+    })?;
+
+    let socket = if socket_addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    #[cfg(unix)]
+    socket.set_reuseport(options.reuse_port)?;
+    // Accepted connections inherit SO_KEEPALIVE from the listening socket
+    socket.set_keepalive(options.keepalive)?;
+    socket.bind(socket_addr)?;
+    socket.listen(options.backlog)
+}
+
+/// Socket options for the next `start_ultra_fast_server`: the accept `backlog` (0 keeps
+/// the default of 1024), `TCP_NODELAY` on accepted connections (default on),
+/// `SO_KEEPALIVE` (default off) and `SO_REUSEPORT` (default off; Unix only) so several
+/// processes can share a port.
+#[no_mangle]
+pub extern "C" fn set_listener_options(
+    backlog: u32,
+    nodelay: bool,
+    keepalive: bool,
+    reuse_port: bool,
+) {
+    *LISTENER_OPTIONS.lock().unwrap() = ListenerOptions {
+        backlog: if backlog > 0 {
+            backlog
+        } else {
+            ListenerOptions::default().backlog
+        },
+        nodelay,
+        keepalive,
+        reuse_port,
+    };
+}
+
+// ========================
+// UTILITY FUNCTIONS 
+// ========================
+
+#[no_mangle]
+pub extern "C" fn get_route_count() -> u64 {
+    (STATIC_RESPONSES.len() + DYNAMIC_ROUTES.len()) as u64
+}
+
+#[no_mangle]
+pub extern "C" fn get_ws_route_count() -> u64 {
+    WS_ROUTES.len() as u64
+}
+
+// ========================
+// COMPATIBILITY ALIASES
+// Names from the removed lib.rs surface with identical semantics here; see
+// rust-core/README.md for the full migration table.
+// ========================
+
+/// Legacy name for `start_ultra_fast_server`.
+#[no_mangle]
+pub extern "C" fn start_sufast_server(host: *const c_char, port: u16) -> i32 {
+    start_ultra_fast_server(host, port)
+}
+
+/// Number of live entries in the response cache.
+#[no_mangle]
+pub extern "C" fn cache_size() -> u64 {
+    RESPONSE_CACHE.len() as u64
+}
+
+/// Number of registered static routes.
+#[no_mangle]
+pub extern "C" fn static_routes_count() -> u64 {
+    STATIC_RESPONSES.len() as u64
+}
+
+/// JSON array of the registered static routes, `[{"method", "path", "status",
+/// "content_type"}]`, sorted by path then method. Free with `free_sufast_string`.
+#[no_mangle]
+pub extern "C" fn list_static_routes() -> *mut c_char {
+    let mut routes: Vec<(String, String, u16, Option<String>)> = STATIC_RESPONSES
+        .iter()
+        .map(|entry| {
+            let (method, path) = entry.key().split_once(':').unwrap_or(("", entry.key()));
+            (
+                path.to_string(),
+                method.to_string(),
+                entry.status,
+                entry.headers.get("content-type").cloned(),
+            )
+        })
+        .collect();
+    routes.sort();
+
+    let routes: Vec<Value> = routes
+        .into_iter()
+        .map(|(path, method, status, content_type)| {
+            json!({"method": method, "path": path, "status": status, "content_type": content_type})
+        })
+        .collect();
+
+    CString::new(Value::Array(routes).to_string())
+        .unwrap_or_else(|_| CString::new("[]").unwrap())
+        .into_raw()
+}
+
+/// Remove every static route, e.g. before re-registering them on a dev-mode hot reload.
+/// Dynamic routes are untouched. Returns the number of routes removed.
+#[no_mangle]
+pub extern "C" fn clear_static_routes() -> u64 {
+    let removed = STATIC_RESPONSES.len() as u64;
+    STATIC_RESPONSES.clear();
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_sweep_evicts_only_expired_entries() {
+        let entry = |ttl: Duration| CachedResponse {
+            body: "{}".to_string(),
+            status: 200,
+            headers: HashMap::new(),
+            cached_at: Instant::now() - Duration::from_secs(10),
+            ttl,
+            etag: "\"sweep\"".to_string(),
+            request_fingerprint: None,
+        };
+        RESPONSE_CACHE.insert(
+            "GET:/sweep-test/expired".to_string(),
+            entry(Duration::from_secs(1)),
+        );
+        RESPONSE_CACHE.insert(
+            "GET:/sweep-test/fresh".to_string(),
+            entry(Duration::from_secs(3600)),
+        );
+
+        sweep_expired_cache();
+
+        assert!(!RESPONSE_CACHE.contains_key("GET:/sweep-test/expired"));
+        assert!(RESPONSE_CACHE.contains_key("GET:/sweep-test/fresh"));
+        RESPONSE_CACHE.remove("GET:/sweep-test/fresh");
+    }
+
+    #[test]
+    fn test_render_template_ffi() {
+        let dir = std::env::temp_dir().join(format!("sufast-templates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello.html"), "Hello, {{ name }}!").unwrap();
+
+        let dir_c = CString::new(dir.to_str().unwrap()).unwrap();
+        assert!(set_template_dir(dir_c.as_ptr()));
+        assert!(!set_template_dir(std::ptr::null()));
+
+        let render = |name: &str, context: &str| {
+            let name = CString::new(name).unwrap();
+            let context = CString::new(context).unwrap();
+            let ptr = render_template(name.as_ptr(), context.as_ptr());
+            let output = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+            free_sufast_string(ptr);
+            output
+        };
+
+        assert_eq!(render("hello.html", r#"{"name":"World"}"#), "Hello, World!");
+        assert_eq!(
+            render("missing.html", "{}"),
+            format!("{}not_found:missing.html", TEMPLATE_ERROR_MARKER)
+        );
+        assert!(render("hello.html", "[1, 2]")
+            .starts_with(&format!("{}invalid_context:", TEMPLATE_ERROR_MARKER)));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_cors_config_echoes_only_listed_origins() {
+        use tower::ServiceExt;
+
+        let config: CorsConfig = serde_json::from_value(json!({
+            "allow_origins": ["https://app.example.com"],
+            "allow_methods": ["get", "post"],
+            "allow_headers": ["content-type"],
+            "max_age": 600,
+            "allow_credentials": true
+        }))
+        .unwrap();
+        let router = Router::new()
+            .route("/cors", axum::routing::get(|| async { "ok" }))
+            .layer(config.to_layer().unwrap());
+        let request = |method: Method, origin: &str| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri("/cors")
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let preflight = router
+            .clone()
+            .oneshot(request(Method::OPTIONS, "https://app.example.com"))
+            .await
+            .unwrap();
+        let headers = preflight.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-allow-methods"], "GET,POST");
+        assert_eq!(headers["access-control-max-age"], "600");
+
+        let foreign = router
+            .oneshot(request(Method::GET, "https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(!foreign
+            .headers()
+            .contains_key("access-control-allow-origin"));
+    }
+
+    #[test]
+    fn test_cors_config_rejects_invalid_values() {
+        let config = CString::new(r#"{"allow_origins": ["https://ok.example.com\n"]}"#).unwrap();
+        assert!(!set_cors_config(config.as_ptr()));
+        let not_json = CString::new("allow everything").unwrap();
+        assert!(!set_cors_config(not_json.as_ptr()));
+    }
+
+    #[tokio::test]
+    async fn test_health_report_checks_the_database() {
+        let (healthy, report) = health_report(false, None).await;
+        assert!(healthy);
+        assert_eq!(report["checks"]["database"]["status"], "not_configured");
+        assert_eq!(report["checks"]["response_cache"]["status"], "up");
+
+        let (healthy, report) = health_report(true, None).await;
+        assert!(!healthy);
+        assert_eq!(report["status"], "unhealthy");
+        assert_eq!(report["checks"]["database"]["status"], "down");
+
+        let pool = database::DatabasePool::new("sqlite::memory:")
+            .await
+            .unwrap();
+        let (healthy, report) = health_report(true, Some(Arc::new(pool))).await;
+        assert!(healthy);
+        assert_eq!(report["checks"]["database"]["status"], "up");
+    }
+
+    #[tokio::test]
+    async fn test_healthz_route_is_served_by_the_router() {
+        use tower::ServiceExt;
+
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+    }
+
+    #[tokio::test]
+    async fn test_nul_byte_in_python_call_is_a_400() {
+        set_python_callback(test_callback);
+
+        let error = match call_ultra_fast_python_handler("GET", "/nul\0byte", "{}").await {
+            Err(error @ PythonCallError::InvalidRequest(_)) => error,
+            other => panic!("expected an invalid request, got {:?}", other.map(|r| r.1)),
+        };
+
+        let response = python_error_response(&error, "7").unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["x-sufast-request-id"], "7");
+    }
+
+    #[test]
+    fn test_tls_server_reports_missing_certificate() {
+        let host = CString::new("127.0.0.1").unwrap();
+        let cert = CString::new("/nonexistent/sufast-cert.pem").unwrap();
+        let key = CString::new("/nonexistent/sufast-key.pem").unwrap();
+        assert_eq!(
+            start_sufast_server_tls(host.as_ptr(), 0, cert.as_ptr(), key.as_ptr()),
+            -2
+        );
+        assert_eq!(
+            start_sufast_server_tls(host.as_ptr(), 0, std::ptr::null(), key.as_ptr()),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_list_routes_includes_static_and_dynamic_routes() {
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/route-list/{id}").unwrap();
+        let handler = CString::new("route_list").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 30),
+            FfiStatus::Ok as i32
+        );
+        // Re-registering replaces the route rather than adding a second one
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 30),
+            FfiStatus::Replaced as i32
+        );
+
+        let method_path = CString::new("DELETE:/route-list/cache").unwrap();
+        let body = CString::new("").unwrap();
+        assert_eq!(
+            add_static_route(method_path.as_ptr(), body.as_ptr(), 204, std::ptr::null()),
+            FfiStatus::Ok as i32
+        );
+
+        let listing = list_routes();
+        let routes: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(listing) }.to_string_lossy()).unwrap();
+        free_sufast_string(listing);
+        let routes = routes.as_array().unwrap();
+        let dynamic = json!({
+            "method": "GET",
+            "path": "/route-list/{id}",
+            "is_dynamic": true,
+            "cache_ttl": 30
+        });
+        assert_eq!(routes.iter().filter(|route| **route == dynamic).count(), 1);
+        assert!(routes.contains(&json!({
+            "method": "DELETE",
+            "path": "/route-list/cache",
+            "is_dynamic": false,
+            "cache_ttl": null
+        })));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_responses_by_status() {
+        use tower::ServiceExt;
+
+        let get = |uri: &'static str| {
+            build_router().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // Other tests register catch-all routes, so count a status this test controls
+        get("/metrics").await.unwrap();
+
+        let response = get("/metrics").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("# TYPE sufast_requests_total counter\n"));
+        assert!(body.contains("# TYPE sufast_cache_entries gauge\n"));
+        assert!(body.contains("sufast_responses_total{status=\"200\"} "));
+    }
+
+    #[test]
+    fn test_literal_route_beats_parameterized_route() {
+        let method = CString::new("GET").unwrap();
+        let param_pattern = CString::new("/users/{id}").unwrap();
+        let literal_pattern = CString::new("/users/me").unwrap();
+        let by_id = CString::new("get_user").unwrap();
+        let me = CString::new("get_me").unwrap();
+
+        // Register the parameterized route first so insertion order can't explain the result
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), param_pattern.as_ptr(), by_id.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), literal_pattern.as_ptr(), me.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+
+        let (route, _) = match_dynamic_route("GET", "/users/me").unwrap();
+        assert_eq!(route.handler_name, "get_me");
+
+        let (route, params) = match_dynamic_route("GET", "/users/42").unwrap();
+        assert_eq!(route.handler_name, "get_user");
+        assert_eq!(params.get("id"), Some(&json!("42")));
+    }
+
+    #[test]
+    fn test_callback_payload_includes_headers_and_query() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer abc".parse().unwrap());
+        headers.append("accept", "text/html".parse().unwrap());
+        headers.append("accept", "application/json".parse().unwrap());
+
+        let mut params = serde_json::Map::new();
+        params.insert("id".to_string(), json!("42"));
+
+        let payload =
+            build_callback_payload(params, &headers, Some("page=2&q=a%20b"), "", "req-1");
+        let payload: Value = serde_json::from_str(&payload).unwrap();
+
+        assert_eq!(payload["id"], "42");
+        assert_eq!(payload["headers"]["authorization"], "Bearer abc");
+        assert_eq!(payload["headers"]["accept"], "text/html, application/json");
+        assert_eq!(payload["query"]["page"], "2");
+        assert_eq!(payload["query"]["q"], "a b");
+        assert_eq!(payload["request_id"], "req-1");
+    }
+
+    #[test]
+    fn test_incoming_request_id_is_validated() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(incoming_request_id(&headers), None);
+
+        headers.insert("x-request-id", "gw-7f3a:42".parse().unwrap());
+        assert_eq!(incoming_request_id(&headers).as_deref(), Some("gw-7f3a:42"));
+
+        headers.insert("x-request-id", "bad id; drop".parse().unwrap());
+        assert_eq!(incoming_request_id(&headers), None);
+
+        headers.insert("x-request-id", "a".repeat(129).parse().unwrap());
+        assert_eq!(incoming_request_id(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_echoed_in_response() {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .header("x-request-id", "trace-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = build_router().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "trace-123");
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap();
+        let response = build_router().oneshot(request).await.unwrap();
+        assert!(response.headers().contains_key("x-request-id"));
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_shares_port_with_reuse_port() {
+        let options = ListenerOptions {
+            reuse_port: true,
+            ..ListenerOptions::default()
+        };
+        let first = bind_listener("127.0.0.1:0", &options).await.unwrap();
+        let addr = first.local_addr().unwrap().to_string();
+
+        // SO_REUSEPORT lets a second listener bind the same port (Unix only)
+        #[cfg(unix)]
+        assert!(bind_listener(&addr, &options).await.is_ok());
+        assert!(bind_listener(&addr, &ListenerOptions::default())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_callback_payload_escapes_quotes_and_control_characters() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-note", r#"say "hi" \ bye"#.parse().unwrap());
+
+        let mut params = serde_json::Map::new();
+        params.insert("slug".to_string(), json!("a\"b\\c\nd"));
+
+        let payload = build_callback_payload(
+            params,
+            &headers,
+            Some(r#"q=%22quoted%22%0Aline&x=%5C"#),
+            "{\"note\": \"a \\\"b\\\"\"}\n",
+            "r",
+        );
+        let payload: Value = serde_json::from_str(&payload).unwrap();
+
+        assert_eq!(payload["slug"], "a\"b\\c\nd");
+        assert_eq!(payload["headers"]["x-note"], r#"say "hi" \ bye"#);
+        assert_eq!(payload["query"]["q"], "\"quoted\"\nline");
+        assert_eq!(payload["query"]["x"], "\\");
+        assert_eq!(payload["body"], "{\"note\": \"a \\\"b\\\"\"}\n");
+    }
+
+    #[test]
+    fn test_worker_thread_count_capped_at_available_cpus() {
+        let available = std::thread::available_parallelism().unwrap().get();
+
+        set_worker_threads(0);
+        assert_eq!(worker_thread_count(), available);
+        set_worker_threads(1);
+        assert_eq!(worker_thread_count(), 1);
+        set_worker_threads(u32::MAX);
+        assert_eq!(worker_thread_count(), available);
+        set_worker_threads(0);
+    }
+
+    #[test]
+    fn test_in_flight_guard_sheds_above_high_water_mark() {
+        let counter = AtomicUsize::new(0);
+        let first = InFlightGuard::acquire(&counter, 2);
+        let second = InFlightGuard::acquire(&counter, 2);
+        assert!(first.is_some() && second.is_some());
+        assert!(InFlightGuard::acquire(&counter, 2).is_none());
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+        drop(first);
+        assert!(InFlightGuard::acquire(&counter, 2).is_some());
+        assert!(InFlightGuard::acquire(&counter, 0).is_some());
+        drop(second);
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+        let response = overloaded_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "1");
+    }
+
+    #[test]
+    fn test_maintenance_allowlist_matches_whole_segments() {
+        assert!(path_under("/health", "/health"));
+        assert!(path_under("/admin/deploys", "/admin/"));
+        assert!(!path_under("/administrator", "/admin"));
+        assert!(!path_under("/api/users", "/admin"));
+
+        let allowlist = CString::new("/health, /admin").unwrap();
+        assert_eq!(
+            unsafe { parse_maintenance_allowlist(allowlist.as_ptr()) }.ok(),
+            Some(vec!["/health".to_string(), "/admin".to_string()])
+        );
+        let invalid = CString::new("admin").unwrap();
+        assert_eq!(
+            set_maintenance_allowlist(invalid.as_ptr()),
+            FfiStatus::BadPattern as i32
+        );
+
+        let response = maintenance_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "120");
+    }
+
+    #[test]
+    fn test_query_parsing_is_capped() {
+        let query: Vec<String> = (0..MAX_QUERY_PARAMS + 50)
+            .map(|i| format!("k{}=v", i))
+            .collect();
+        let pairs = parse_query(Some(&query.join("&")));
+        assert_eq!(pairs.len(), MAX_QUERY_PARAMS);
+        assert_eq!(
+            pairs.last().unwrap().0,
+            format!("k{}", MAX_QUERY_PARAMS - 1)
+        );
+        assert_eq!(
+            parse_query(Some("a=1&b=%20two")),
+            vec![("a".into(), "1".into()), ("b".into(), " two".into())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overlong_uri_is_a_414() {
+        use tower::ServiceExt;
+
+        let uri = format!("/{}", "a".repeat(9000));
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::URI_TOO_LONG);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_utf8_is_a_400() {
+        use tower::ServiceExt;
+
+        let send = |request: axum::http::Request<Body>| async move {
+            build_router().oneshot(request).await.unwrap().status()
+        };
+        let get = |uri: &str| {
+            axum::http::Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        assert_eq!(send(get("/files/%FF%FE")).await, StatusCode::BAD_REQUEST);
+        assert_eq!(send(get("/search?q=%C3%28")).await, StatusCode::BAD_REQUEST);
+
+        let bad_header = axum::http::Request::builder()
+            .uri("/health")
+            .header("x-name", HeaderValue::from_bytes(b"caf\xe9").unwrap())
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(bad_header).await, StatusCode::BAD_REQUEST);
+
+        // Well-formed multi-byte UTF-8 is fine
+        assert_ne!(send(get("/files/caf%C3%A9")).await, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_pooled_cstring_reuses_buffers() {
+        let first = pooled_cstring("/users/42/profile").unwrap();
+        recycle_cstring(first);
+
+        let second = pooled_cstring("/a").unwrap();
+        assert_eq!(second.to_str().unwrap(), "/a");
+        recycle_cstring(second);
+
+        assert!(pooled_cstring("bad\0value").is_err());
+    }
+
+    extern "C" fn slow_callback(
+        _method: *const c_char,
+        _path: *const c_char,
+        _params: *const c_char,
+    ) -> *const c_char {
+        std::thread::sleep(Duration::from_millis(100));
+        c"{\"body\":\"ok\",\"status\":200}".as_ptr()
+    }
+
+    // The Python callback is global and tests run in parallel, so every test installs
+    // this one; it picks the behaviour a test needs by path
+    extern "C" fn test_callback(
+        method: *const c_char,
+        path: *const c_char,
+        params: *const c_char,
+    ) -> *const c_char {
+        match unsafe { CStr::from_ptr(path) }.to_bytes() {
+            b"/flight/hot" => counting_slow_callback(method, path, params),
+            b"/vary/greeting" => language_callback(params),
+            b"/params/orders/42/items/7" => params_callback(params),
+            b"/body/orders" | b"/body/idempotent" => body_callback(params),
+            b"/garbage" => c"hello from python".as_ptr(),
+            b"/timeout/hung" => {
+                std::thread::sleep(Duration::from_millis(1500));
+                c"{\"body\":\"late\",\"status\":200}".as_ptr()
+            }
+            _ => slow_callback(method, path, params),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_python_callback_does_not_serialize_requests() {
+        set_python_callback(test_callback);
+
+        let started = Instant::now();
+        let (a, b, c, d) = tokio::join!(
+            call_ultra_fast_python_handler("GET", "/slow", "{}"),
+            call_ultra_fast_python_handler("GET", "/slow", "{}"),
+            call_ultra_fast_python_handler("GET", "/slow", "{}"),
+            call_ultra_fast_python_handler("GET", "/slow", "{}"),
+        );
+
+        // Four 100ms handlers in parallel, not 400ms back to back
+        assert!(started.elapsed() < Duration::from_millis(300));
+        for result in [a, b, c, d] {
+            let (body, status, _) = result.unwrap();
+            assert!(matches!(body, PythonBody::Full(body) if body == "ok"));
+            assert_eq!(status, 200);
+        }
+    }
+
+    static FLIGHT_CALLS: AtomicU64 = AtomicU64::new(0);
+
+    extern "C" fn counting_slow_callback(
+        method: *const c_char,
+        path: *const c_char,
+        params: *const c_char,
+    ) -> *const c_char {
+        FLIGHT_CALLS.fetch_add(1, Ordering::SeqCst);
+        slow_callback(method, path, params)
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_coalesces_concurrent_misses() {
+        set_python_callback(test_callback);
+
+        let key = "GET:/flight/hot";
+        let (a, b, c) = tokio::join!(
+            call_python_single_flight(key, "GET", "/flight/hot", "{}"),
+            call_python_single_flight(key, "GET", "/flight/hot", "{}"),
+            call_python_single_flight(key, "GET", "/flight/hot", "{}"),
+        );
+
+        assert_eq!(FLIGHT_CALLS.load(Ordering::SeqCst), 1);
+        for result in [a, b, c] {
+            let (body, status, _) = result.unwrap();
+            assert!(matches!(body, PythonBody::Full(body) if body == "ok"));
+            assert_eq!(status, 200);
+        }
+        assert!(!IN_FLIGHT.contains_key(key));
+
+        // Once the flight has landed, the next miss calls Python again
+        call_python_single_flight(key, "GET", "/flight/hot", "{}")
+            .await
+            .unwrap();
+        assert_eq!(FLIGHT_CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_cache_ttl_jitter_stays_within_fraction() {
+        let ttl = Duration::from_secs(100);
+        assert_eq!(jittered_ttl(ttl, 0.0), ttl);
+
+        let samples: Vec<Duration> = (0..200).map(|_| jittered_ttl(ttl, 0.2)).collect();
+        assert!(samples
+            .iter()
+            .all(|t| *t >= Duration::from_secs(80) && *t <= Duration::from_secs(120)));
+        assert!(samples.iter().any(|t| *t != ttl));
+
+        assert!(!set_cache_ttl_jitter(-0.1));
+        assert!(!set_cache_ttl_jitter(1.5));
+        assert!(!set_cache_ttl_jitter(f64::NAN));
+    }
+
+    fn language_callback(params: *const c_char) -> *const c_char {
+        let params = unsafe { CStr::from_ptr(params) }.to_string_lossy();
+        if params.contains(r#""accept-language":"de""#) {
+            c"{\"body\":\"hallo\",\"status\":200}".as_ptr()
+        } else {
+            c"{\"body\":\"hello\",\"status\":200}".as_ptr()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vary_headers_cache_one_response_per_variant() {
+        use tower::ServiceExt;
+
+        set_python_callback(test_callback);
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/vary/greeting").unwrap();
+        let handler = CString::new("greeting").unwrap();
+        let vary = CString::new("Accept-Language").unwrap();
+        assert_eq!(
+            add_dynamic_route_with_vary(
+                method.as_ptr(),
+                pattern.as_ptr(),
+                handler.as_ptr(),
+                60,
+                vary.as_ptr()
+            ),
+            FfiStatus::Ok as i32
+        );
+
+        let get = |language: &'static str| async move {
+            let response = build_router()
+                .oneshot(
+                    axum::http::Request::builder()
+                        .uri("/vary/greeting")
+                        .header("accept-language", language)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let tier = response.headers()["x-sufast-tier"]
+                .to_str()
+                .unwrap()
+                .to_string();
+            let vary = response.headers()["vary"].to_str().unwrap().to_string();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (tier, vary, String::from_utf8(body.to_vec()).unwrap())
+        };
+
+        assert_eq!(
+            get("de").await,
+            ("dynamic".into(), "accept-language".into(), "hallo".into())
+        );
+        assert_eq!(
+            get("en").await,
+            ("dynamic".into(), "accept-language".into(), "hello".into())
+        );
+        assert_eq!(
+            get("de").await,
+            ("cached".into(), "accept-language".into(), "hallo".into())
+        );
+        assert_eq!(get("en").await.2, "hello");
+
+        let bad = CString::new("Accept Language").unwrap();
+        assert_eq!(
+            add_dynamic_route_with_vary(
+                method.as_ptr(),
+                pattern.as_ptr(),
+                handler.as_ptr(),
+                60,
+                bad.as_ptr()
+            ),
+            FfiStatus::InvalidHeader as i32
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_route_drops_vary_headers() {
+        use tower::ServiceExt;
+
+        set_python_callback(test_callback);
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/vary/removed").unwrap();
+        let handler = CString::new("greeting").unwrap();
+        let vary = CString::new("Accept-Language").unwrap();
+        add_dynamic_route_with_vary(
+            method.as_ptr(),
+            pattern.as_ptr(),
+            handler.as_ptr(),
+            60,
+            vary.as_ptr(),
+        );
+        build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/vary/removed")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(CACHE_VARY_HEADERS.contains_key("GET:/vary/removed"));
+
+        assert!(remove_route(method.as_ptr(), pattern.as_ptr()));
+        assert!(!CACHE_VARY_HEADERS.contains_key("GET:/vary/removed"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_python_response_is_a_502() {
+        set_python_callback(test_callback);
+
+        let error = match call_ultra_fast_python_handler("GET", "/garbage", "{}").await {
+            Err(error @ PythonCallError::InvalidResponse { .. }) => error,
+            other => panic!("expected an invalid response, got {:?}", other.map(|r| r.1)),
+        };
+
+        let response = invalid_python_response(&error, "req-1");
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body: Value = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(body["preview"], "hello from python");
+    }
+
+    #[test]
+    fn test_remove_dynamic_route_invalidates_cache() {
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/removable/{id}").unwrap();
+        let handler = CString::new("removable").unwrap();
+
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 60),
+            FfiStatus::Ok as i32
+        );
+        RESPONSE_CACHE.insert(
+            "GET:/removable/1".to_string(),
+            CachedResponse {
+                body: "{}".to_string(),
+                status: 200,
+                headers: HashMap::new(),
+                cached_at: Instant::now(),
+                ttl: Duration::from_secs(60),
+                etag: compute_weak_etag("{}"),
+                request_fingerprint: None,
+            },
+        );
+        assert!(match_dynamic_route("GET", "/removable/1").is_some());
+
+        assert!(remove_dynamic_route(pattern.as_ptr()));
+        assert!(match_dynamic_route("GET", "/removable/1").is_none());
+        assert!(!RESPONSE_CACHE.contains_key("GET:/removable/1"));
+
+        // Nothing left to remove
+        assert!(!remove_dynamic_route(pattern.as_ptr()));
+        assert!(!remove_route(method.as_ptr(), pattern.as_ptr()));
+    }
+
+    #[test]
+    fn test_add_routes_batch() {
+        let batch = r#"[
+            {"method": "GET", "path": "/batch/users/{id}", "handler_type": "get_user", "cache_ttl": 30},
+            {"method": "post", "path": "/batch/users", "handler_type": "create_user"},
+            {"method": "GET", "path": "/batch/broken/(", "handler_type": "broken"}
+        ]"#;
+
+        assert_eq!(
+            add_routes_batch(batch.as_ptr() as *const c_char, batch.len()),
+            2
+        );
+
+        let (route, _) = match_dynamic_route("GET", "/batch/users/7").unwrap();
+        assert_eq!(route.handler_name, "get_user");
+        assert_eq!(route.cache_ttl, Some(Duration::from_secs(30)));
+        let (route, _) = match_dynamic_route("POST", "/batch/users").unwrap();
+        assert_eq!(route.cache_ttl, None);
+
+        let invalid = "{\"method\": \"GET\"}";
+        assert_eq!(
+            add_routes_batch(invalid.as_ptr() as *const c_char, invalid.len()),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_warm_cache_populates_cached_routes_only() {
+        set_python_callback(test_callback);
+        let batch = r#"[
+            {"method": "GET", "path": "/warm/{id}", "handler_type": "warm", "cache_ttl": 60},
+            {"method": "GET", "path": "/warm-uncached/{id}", "handler_type": "warm"}
+        ]"#;
+        assert_eq!(
+            add_routes_batch(batch.as_ptr() as *const c_char, batch.len()),
+            2
+        );
+
+        let warm_list = CString::new(
+            r#"[{"path": "/warm/1"}, {"method": "get", "path": "/warm/2"}, {"path": "/warm-uncached/1"}, {"path": "/warm-missing"}]"#,
+        )
+        .unwrap();
+        assert_eq!(warm_cache(warm_list.as_ptr()), 2);
+        assert!(RESPONSE_CACHE.contains_key("GET:/warm/1"));
+        assert!(RESPONSE_CACHE.contains_key("GET:/warm/2"));
+        assert!(!RESPONSE_CACHE.contains_key("GET:/warm-uncached/1"));
+
+        let invalid = CString::new(r#"{"path": "/warm/1"}"#).unwrap();
+        assert_eq!(warm_cache(invalid.as_ptr()), -1);
+    }
+
+    #[test]
+    fn test_list_and_clear_static_routes() {
+        let method_path = CString::new("POST:/static-list/b").unwrap();
+        let body = CString::new("created").unwrap();
+        let content_type = CString::new("text/plain").unwrap();
+        assert_eq!(
+            add_static_route(
+                method_path.as_ptr(),
+                body.as_ptr(),
+                201,
+                content_type.as_ptr()
+            ),
+            FfiStatus::Ok as i32
+        );
+
+        let listing = list_static_routes();
+        let routes: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(listing) }.to_string_lossy()).unwrap();
+        free_sufast_string(listing);
+        assert!(routes.as_array().unwrap().contains(&json!({
+            "method": "POST",
+            "path": "/static-list/b",
+            "status": 201,
+            "content_type": "text/plain"
+        })));
+    }
+
+    #[test]
+    fn test_validate_routes_reports_failed_patterns() {
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/validate/(broken/{id}").unwrap();
+        let handler = CString::new("broken").unwrap();
+
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::BadPattern as i32
+        );
+
+        let report_ptr = validate_routes();
+        let report: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(report_ptr) }.to_string_lossy()).unwrap();
+        free_sufast_string(report_ptr);
+
+        assert_eq!(report["valid"], false);
+        let errors = report["errors"].as_array().unwrap();
+        let error = errors
+            .iter()
+            .find(|e| e["pattern"] == "/validate/(broken/{id}")
+            .unwrap();
+        assert_eq!(error["kind"], "dynamic");
+        assert_eq!(error["method"], "GET");
+        assert!(error["reason"].as_str().unwrap().contains("unclosed"));
+    }
+
+    #[test]
+    fn test_validate_routes_reports_static_route_without_method() {
+        let method_path = CString::new("/validate/no-method").unwrap();
+        let body = CString::new("{}").unwrap();
+        assert_eq!(
+            add_static_route(method_path.as_ptr(), body.as_ptr(), 200, std::ptr::null()),
+            FfiStatus::BadPattern as i32
+        );
+
+        let report_ptr = validate_routes();
+        let report: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(report_ptr) }.to_string_lossy()).unwrap();
+        free_sufast_string(report_ptr);
+
+        let error = report["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["pattern"] == "/validate/no-method")
+            .unwrap();
+        assert_eq!(error["kind"], "static");
+        assert_eq!(error["reason"], "expected METHOD:path");
+    }
+
+    #[test]
+    fn test_method_override_only_applies_to_post() {
+        enable_method_override(true);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-http-method-override", "delete".parse().unwrap());
+        assert_eq!(
+            overridden_method(&Method::POST, &headers, None),
+            Some(Method::DELETE)
+        );
+        assert_eq!(overridden_method(&Method::GET, &headers, None), None);
+
+        let empty = HeaderMap::new();
+        assert_eq!(
+            overridden_method(&Method::POST, &empty, Some("a=1&_method=PUT")),
+            Some(Method::PUT)
+        );
+        // Only PUT / PATCH / DELETE are honoured
+        assert_eq!(
+            overridden_method(&Method::POST, &empty, Some("_method=TRACE")),
+            None
+        );
+        assert_eq!(
+            overridden_method(&Method::POST, &empty, Some("_method=bogus")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_route_serves_precompressed_gzip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+        use tower::ServiceExt;
+
+        let body = format!(r#"{{"items":"{}"}}"#, "static ".repeat(100));
+        let route = CString::new("GET:/gzip/static").unwrap();
+        let body_c = CString::new(body.clone()).unwrap();
+        assert_eq!(
+            add_static_route(route.as_ptr(), body_c.as_ptr(), 200, std::ptr::null()),
+            FfiStatus::Ok as i32
+        );
+
+        let request = axum::http::Request::builder()
+            .uri("/gzip/static")
+            .header("accept-encoding", "br;q=1, gzip;q=0.8")
+            .body(Body::empty())
+            .unwrap();
+        let response = build_router().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        assert_eq!(response.headers()["vary"], "accept-encoding");
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut decoded = String::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+
+        // Clients that don't accept gzip (or refuse it with q=0) get plaintext
+        for accept in [None, Some("gzip;q=0")] {
+            let mut request = axum::http::Request::builder().uri("/gzip/static");
+            if let Some(accept) = accept {
+                request = request.header("accept-encoding", accept);
+            }
+            let response = build_router()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert!(!response.headers().contains_key("content-encoding"));
+            let plain = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&plain[..], body.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_replays_and_rejects_mismatched_body() {
+        use tower::ServiceExt;
+
+        enable_idempotency_keys(true);
+        let route = CString::new("POST:/idempotent/orders").unwrap();
+        let body = CString::new(r#"{"created":true}"#).unwrap();
+        assert_eq!(
+            add_static_route(route.as_ptr(), body.as_ptr(), 201, std::ptr::null()),
+            FfiStatus::Ok as i32
+        );
+
+        let post = |payload: &'static str| {
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/idempotent/orders")
+                .header("idempotency-key", "order-abc")
+                .body(Body::from(payload))
+                .unwrap()
+        };
+
+        let first = build_router().oneshot(post(r#"{"qty":1}"#)).await.unwrap();
+        assert_eq!(first.status(), 201);
+        assert!(!first.headers().contains_key("idempotent-replayed"));
+
+        // Drop the route so a replay can only come from the idempotency store
+        let method = CString::new("POST").unwrap();
+        let path = CString::new("/idempotent/orders").unwrap();
+        assert!(remove_route(method.as_ptr(), path.as_ptr()));
+
+        let replay = build_router().oneshot(post(r#"{"qty":1}"#)).await.unwrap();
+        assert_eq!(replay.status(), 201);
+        assert_eq!(replay.headers()["idempotent-replayed"], "true");
+        let replay_body = axum::body::to_bytes(replay.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&replay_body[..], br#"{"created":true}"#);
+
+        let conflict = build_router().oneshot(post(r#"{"qty":2}"#)).await.unwrap();
+        assert_eq!(conflict.status(), 409);
+
+        // A duplicate that arrives while the first request is still running
+        IDEMPOTENCY_STORE.insert(
+            "order-pending:POST:/idempotent/orders".to_string(),
+            IdempotencyEntry::InProgress,
+        );
+        let mut duplicate = post(r#"{"qty":1}"#);
+        duplicate
+            .headers_mut()
+            .insert("idempotency-key", HeaderValue::from_static("order-pending"));
+        let in_progress = build_router().oneshot(duplicate).await.unwrap();
+        assert_eq!(in_progress.status(), 409);
+    }
+
+    #[tokio::test]
+    async fn test_long_poll_waits_for_publish_or_times_out() {
+        use tower::ServiceExt;
+
+        let pattern = CString::new("/poll/changes/{room}").unwrap();
+        assert_eq!(
+            add_long_poll_route(pattern.as_ptr(), 1),
+            FfiStatus::Ok as i32
+        );
+
+        let poll = |uri: &'static str| {
+            build_router().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // Nothing published within the timeout
+        let idle = poll("/poll/changes/quiet").await.unwrap();
+        assert_eq!(idle.status(), StatusCode::NO_CONTENT);
+        assert_eq!(idle.headers()["x-poll-version"], "0");
+        assert!(!LONG_POLL_CHANNELS.contains_key("/poll/changes/quiet"));
+
+        // A parked request is woken by the publish
+        let parked = tokio::spawn(poll("/poll/changes/lobby"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let path = CString::new("/poll/changes/lobby").unwrap();
+        let data = CString::new(r#"{"messages":1}"#).unwrap();
+        assert_eq!(
+            publish_long_poll(path.as_ptr(), data.as_ptr()),
+            FfiStatus::Ok as i32
+        );
+        let woken = parked.await.unwrap().unwrap();
+        assert_eq!(woken.status(), StatusCode::OK);
+        assert_eq!(woken.headers()["x-poll-version"], "1");
+        let body = axum::body::to_bytes(woken.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], br#"{"messages":1}"#);
+
+        // A client that is behind gets the latest data without waiting
+        let behind = poll("/poll/changes/lobby?since=0").await.unwrap();
+        assert_eq!(behind.status(), StatusCode::OK);
+
+        // Paths outside every long-poll route never get a channel
+        let unrouted = CString::new("/poll/elsewhere").unwrap();
+        assert_eq!(
+            publish_long_poll(unrouted.as_ptr(), data.as_ptr()),
+            FfiStatus::NotFound as i32
+        );
+        assert!(!LONG_POLL_CHANNELS.contains_key("/poll/elsewhere"));
+    }
+
+    #[test]
+    fn test_long_poll_route_matching_prefers_specific_patterns() {
+        for (pattern, timeout) in [("/poll/feeds/{feed}", 5), ("/poll/feeds/main", 2)] {
+            let pattern = CString::new(pattern).unwrap();
+            add_long_poll_route(pattern.as_ptr(), timeout);
+        }
+
+        for _ in 0..10 {
+            assert_eq!(
+                match_long_poll_route("/poll/feeds/main"),
+                Some(Duration::from_secs(2))
+            );
+        }
+        assert_eq!(
+            match_long_poll_route("/poll/feeds/other"),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_span_carries_request_fields() {
+        use std::io::Write;
+        use tower::ServiceExt;
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        #[derive(Clone, Default)]
+        struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for CapturedLogs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .finish();
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        let route = CString::new("GET:/span/static").unwrap();
+        let body = CString::new("{}").unwrap();
+        add_static_route(route.as_ptr(), body.as_ptr(), 200, std::ptr::null());
+
+        let request = axum::http::Request::builder()
+            .uri("/span/static")
+            .header("x-request-id", "span-test-1")
+            .body(Body::empty())
+            .unwrap();
+        build_router().oneshot(request).await.unwrap();
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let closed = output
+            .lines()
+            .find(|line| line.contains("request_id=span-test-1"))
+            .expect("request span was not closed");
+        assert!(closed.contains("method=GET"));
+        assert!(closed.contains("path=\"/span/static\""));
+        assert!(closed.contains("tier=\"static\""));
+        assert!(closed.contains("status=200"));
+    }
+
+    #[tokio::test]
+    async fn test_response_transform_wraps_matching_paths() {
+        use tower::ServiceExt;
+
+        extern "C" fn envelope(input: *const c_char) -> *const c_char {
+            let input: Value =
+                serde_json::from_str(&unsafe { CStr::from_ptr(input) }.to_string_lossy()).unwrap();
+            let data: Value = serde_json::from_str(input["body"].as_str().unwrap()).unwrap();
+            let mut headers = input["headers"].as_array().unwrap().clone();
+            headers.push(json!(["x-transformed", "yes"]));
+            let output = json!({
+                "headers": headers,
+                "body": {"data": data, "status": input["status"]},
+            });
+            // Leaked so the pointer outlives the call, as a Python-owned buffer would
+            Box::leak(CString::new(output.to_string()).unwrap().into_boxed_c_str()).as_ptr()
+        }
+
+        for (route, body) in [
+            ("GET:/transform/api/item", r#"{"id":1}"#),
+            ("GET:/transform/other", r#"{"id":2}"#),
+        ] {
+            let route = CString::new(route).unwrap();
+            let body = CString::new(body).unwrap();
+            add_static_route(route.as_ptr(), body.as_ptr(), 200, std::ptr::null());
+        }
+        let prefix = CString::new("/transform/api/").unwrap();
+        assert_eq!(
+            add_response_transform(prefix.as_ptr(), envelope),
+            FfiStatus::Ok as i32
+        );
+
+        let get = |uri: &'static str| {
+            build_router().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        let wrapped = get("/transform/api/item").await.unwrap();
+        assert_eq!(wrapped.headers()["x-transformed"], "yes");
+        assert_eq!(wrapped.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(wrapped.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({"data": {"id": 1}, "status": 200})
+        );
+
+        let untouched = get("/transform/other").await.unwrap();
+        assert!(!untouched.headers().contains_key("x-transformed"));
+        let body = axum::body::to_bytes(untouched.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], br#"{"id":2}"#);
+    }
+
+    #[tokio::test]
+    async fn test_connection_closed_after_max_requests() {
+        use tower::ServiceExt;
+
+        let route = CString::new("GET:/connection/ping").unwrap();
+        let body = CString::new("{}").unwrap();
+        add_static_route(route.as_ptr(), body.as_ptr(), 200, std::ptr::null());
+        set_max_requests_per_connection(2);
+
+        let connection = ConnectionInfo::open();
+        let active = ACTIVE_CONNECTIONS.load(Ordering::Relaxed);
+        let mut closes = Vec::new();
+        for _ in 0..2 {
+            let mut request = axum::http::Request::builder()
+                .uri("/connection/ping")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(connection.clone()));
+            let response = build_router().oneshot(request).await.unwrap();
+            closes.push(response.headers().get("connection").cloned());
+        }
+        assert_eq!(closes, vec![None, Some(HeaderValue::from_static("close"))]);
+        assert_eq!(connection.requests(), 2);
+
+        // Requests without connection info (e.g. driven in-process) are never closed
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/connection/ping")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key("connection"));
+        set_max_requests_per_connection(0);
+
+        drop(connection);
+        assert_eq!(ACTIVE_CONNECTIONS.load(Ordering::Relaxed), active - 1);
+    }
+
+    #[tokio::test]
+    async fn test_static_route_cache_headers() {
+        use tower::ServiceExt;
+
+        let register =
+            |route: &str, body: &str, cache_control: Option<&str>, vary: Option<&str>| {
+                let route = CString::new(route).unwrap();
+                let body = CString::new(body).unwrap();
+                let cache_control = cache_control.map(|v| CString::new(v).unwrap());
+                let vary = vary.map(|v| CString::new(v).unwrap());
+                add_static_route_with_cache(
+                    route.as_ptr(),
+                    body.as_ptr(),
+                    200,
+                    std::ptr::null(),
+                    cache_control
+                        .as_ref()
+                        .map_or(std::ptr::null(), |v| v.as_ptr()),
+                    vary.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
+                )
+            };
+        let fetch = |uri: &'static str| {
+            build_router().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(
+            register("GET:/cached/default", "{}", None, None),
+            FfiStatus::Ok as i32
+        );
+        let response = fetch("/cached/default").await.unwrap();
+        assert_eq!(
+            response.headers()["cache-control"],
+            STATIC_DEFAULT_CACHE_CONTROL
+        );
+        // Only CorsLayer's own Vary; the route adds none
+        let vary: Vec<_> = response.headers().get_all("vary").iter().collect();
+        assert_eq!(
+            vary,
+            ["origin, access-control-request-method, access-control-request-headers"]
+        );
+
+        let localized = format!(r#"{{"greeting":"{}"}}"#, "hello ".repeat(100));
+        assert_eq!(
+            register(
+                "GET:/cached/localized",
+                &localized,
+                Some("private, max-age=60"),
+                Some("Accept-Language")
+            ),
+            FfiStatus::Ok as i32
+        );
+        let response = fetch("/cached/localized").await.unwrap();
+        assert_eq!(response.headers()["cache-control"], "private, max-age=60");
+        assert_eq!(
+            response.headers()["vary"],
+            "Accept-Language, accept-encoding"
+        );
+
+        assert_eq!(
+            register("GET:/cached/bad", "{}", Some("no-store\r\nx-evil: 1"), None),
+            FfiStatus::InvalidHeader as i32
+        );
+    }
+
+    #[test]
+    fn test_registration_status_codes_and_last_error() {
+        let method = CString::new("GET").unwrap();
+        let handler = CString::new("handler").unwrap();
+
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), std::ptr::null(), handler.as_ptr(), 0),
+            FfiStatus::NullArg as i32
+        );
+        let message_ptr = last_error_message();
+        let message = unsafe { CStr::from_ptr(message_ptr) }
+            .to_string_lossy()
+            .to_string();
+        free_sufast_string(message_ptr);
+        assert_eq!(message, "pattern must not be null");
+
+        let invalid_utf8 = b"/caf\xe9\0";
+        assert_eq!(
+            add_dynamic_route(
+                method.as_ptr(),
+                invalid_utf8.as_ptr() as *const c_char,
+                handler.as_ptr(),
+                0
+            ),
+            FfiStatus::InvalidUtf8 as i32
+        );
+
+        // A successful call clears the previous error
+        let pattern = CString::new("/status-codes/{id}").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+        assert!(last_error_message().is_null());
+    }
+
+    // Stream 1 yields three chunks then ends; stream 2 yields one chunk then fails
+    static STREAM_CALLS: Lazy<Mutex<HashMap<u64, usize>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+    static STREAM_CHUNKS: [&[u8]; 3] = [b"alpha,", b"beta,", b"gamma"];
+
+    extern "C" fn test_stream_callback(stream_id: u64, len: *mut i64) -> *const c_char {
+        let mut calls = STREAM_CALLS.lock().unwrap();
+        let call = calls.entry(stream_id).or_insert(0);
+        let index = *call;
+        *call += 1;
+
+        let len = unsafe { &mut *len };
+        match (stream_id, index) {
+            (1, i) if i < STREAM_CHUNKS.len() => {
+                *len = STREAM_CHUNKS[i].len() as i64;
+                STREAM_CHUNKS[i].as_ptr() as *const c_char
+            }
+            (2, 0) => {
+                *len = STREAM_CHUNKS[0].len() as i64;
+                STREAM_CHUNKS[0].as_ptr() as *const c_char
+            }
+            (2, _) => {
+                *len = -1;
+                std::ptr::null()
+            }
+            _ => {
+                *len = 0;
+                std::ptr::null()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_pulls_chunks_until_end_or_error() {
+        set_python_stream_callback(test_stream_callback);
+
+        let body = axum::body::to_bytes(stream_body(1), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"alpha,beta,gamma");
+
+        // A generator failure surfaces as a body error instead of a short, "complete" body
+        assert!(axum::body::to_bytes(stream_body(2), usize::MAX)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_route_specificity_ordering() {
+        use std::cmp::Ordering::*;
+
+        assert_eq!(compare_route_specificity("/users/me", "/users/{id}"), Less);
+        assert_eq!(compare_route_specificity("/a/b/{c}", "/a/{b}/c"), Less);
+        assert_eq!(compare_route_specificity("/a/{b}/c", "/a/{b}/{c}"), Less);
+        assert_eq!(
+            compare_route_specificity("/users/{id}", "/users/{id}"),
+            Equal
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hung_python_handler_times_out_with_504() {
+        use tower::ServiceExt;
+
+        set_python_callback(test_callback);
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/timeout/hung").unwrap();
+        let handler = CString::new("hung").unwrap();
+        assert_eq!(
+            add_dynamic_route_with_timeout(
+                method.as_ptr(),
+                pattern.as_ptr(),
+                handler.as_ptr(),
+                0,
+                std::ptr::null(),
+                1
+            ),
+            FfiStatus::Ok as i32
+        );
+
+        let started = Instant::now();
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/timeout/hung")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(1400));
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        let body: Value = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(body["timeout_seconds"], 1);
+    }
+
+    #[test]
+    fn test_route_timeout_overrides_default() {
+        let mut route = DynamicRoute {
+            method: "GET".to_string(),
+            pattern: "/slow".to_string(),
+            regex: compile_ultra_fast_pattern("/slow").unwrap(),
+            handler_name: "slow".to_string(),
+            cache_ttl: None,
+            vary_headers: Vec::new().into(),
+            timeout: Some(Duration::from_secs(5)),
+        };
+        assert_eq!(effective_timeout(&route), Some(Duration::from_secs(5)));
+
+        route.timeout = None;
+        let default = REQUEST_TIMEOUT_SECS.load(Ordering::Relaxed);
+        assert_eq!(
+            effective_timeout(&route),
+            (default > 0).then(|| Duration::from_secs(default))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_routes_serve_any_method() {
+        use tower::ServiceExt;
+
+        let method_path = CString::new("delete:/static-any/cache").unwrap();
+        let body = CString::new("").unwrap();
+        assert_eq!(
+            add_static_route(method_path.as_ptr(), body.as_ptr(), 204, std::ptr::null()),
+            FfiStatus::Ok as i32
+        );
+        assert!(STATIC_RESPONSES.contains_key("DELETE:/static-any/cache"));
+
+        let send = |method: Method| {
+            build_router().oneshot(
+                axum::http::Request::builder()
+                    .method(method)
+                    .uri("/static-any/cache")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        let deleted = send(Method::DELETE).await.unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+        assert_eq!(deleted.headers()["x-sufast-tier"], "static");
+
+        // The static response belongs to DELETE only
+        let fetched = send(Method::GET).await.unwrap();
+        assert_ne!(
+            fetched.headers().get("x-sufast-tier").map(|v| v.as_bytes()),
+            Some(&b"static"[..])
+        );
+    }
+
+    #[test]
+    fn test_route_registration_normalizes_methods() {
+        let body = CString::new("{}").unwrap();
+        let no_method = CString::new("/static-no-method").unwrap();
+        assert_eq!(
+            add_static_route(no_method.as_ptr(), body.as_ptr(), 200, std::ptr::null()),
+            FfiStatus::BadPattern as i32
+        );
+
+        let method = CString::new("patch").unwrap();
+        let pattern = CString::new("/lowercase-method/{id}").unwrap();
+        let handler = CString::new("lowercase").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+        assert!(DYNAMIC_ROUTES.contains_key("PATCH:/lowercase-method/{id}"));
+        assert!(match_dynamic_route("PATCH", "/lowercase-method/7").is_some());
+    }
+
+    fn params_callback(params: *const c_char) -> *const c_char {
+        let params: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(params) }.to_string_lossy()).unwrap();
+        if params["order_id"] == "42" && params["item_id"] == "7" {
+            c"{\"body\":\"params ok\",\"status\":200}".as_ptr()
+        } else {
+            c"{\"body\":\"params missing\",\"status\":500}".as_ptr()
+        }
+    }
+
+    fn body_callback(params: *const c_char) -> *const c_char {
+        let params: Value =
+            serde_json::from_str(&unsafe { CStr::from_ptr(params) }.to_string_lossy()).unwrap();
+        if params["body"] == r#"{"item": "tea", "note": "say \"hi\""}"# {
+            c"{\"body\":\"body ok\",\"status\":201}".as_ptr()
+        } else {
+            c"{\"body\":\"body missing\",\"status\":500}".as_ptr()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_body_reaches_the_python_callback() {
+        use tower::ServiceExt;
+
+        set_python_callback(test_callback);
+        let method = CString::new("POST").unwrap();
+        let pattern = CString::new("/body/orders").unwrap();
+        let handler = CString::new("create_order").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/body/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"item": "tea", "note": "say \"hi\""}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"body ok");
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_post_body_reaches_the_python_callback() {
+        use tower::ServiceExt;
+
+        enable_idempotency_keys(true);
+        set_python_callback(test_callback);
+        let method = CString::new("POST").unwrap();
+        let pattern = CString::new("/body/idempotent").unwrap();
+        let handler = CString::new("create_order").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/body/idempotent")
+                    .header("idempotency-key", "order-body-1")
+                    .body(Body::from(r#"{"item": "tea", "note": "say \"hi\""}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"body ok");
+    }
+
+    #[tokio::test]
+    async fn test_path_params_reach_the_python_callback() {
+        use tower::ServiceExt;
+
+        set_python_callback(test_callback);
+        let method = CString::new("GET").unwrap();
+        let pattern = CString::new("/params/orders/{order_id}/items/{item_id}").unwrap();
+        let handler = CString::new("order_item").unwrap();
+        assert_eq!(
+            add_dynamic_route(method.as_ptr(), pattern.as_ptr(), handler.as_ptr(), 0),
+            FfiStatus::Ok as i32
+        );
+
+        let response = build_router()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/params/orders/42/items/7")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-sufast-handler"], "order_item");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"params ok");
+    }
+
+    #[test]
+    fn test_route_index_narrows_candidates_by_literal_prefix() {
+        let route = |pattern: &str| DynamicRoute {
+            method: "GET".to_string(),
+            pattern: pattern.to_string(),
+            regex: compile_ultra_fast_pattern(pattern).unwrap(),
+            handler_name: pattern.to_string(),
+            cache_ttl: None,
+            vary_headers: Vec::new().into(),
+            timeout: None,
+        };
+        let index = DynamicRouteIndex::build(vec![
+            route("/api/v1/users/me"),
+            route("/api/v1/users/{id}"),
+            route("/blog/{slug}"),
+            route("/{page}"),
+        ]);
+
+        let candidates: Vec<&str> = index
+            .candidates("/api/v1/users/42")
+            .map(|r| r.pattern.as_str())
+            .collect();
+        // "/api/v1/users/me" is fully literal, so only its own path reaches it
+        assert_eq!(candidates, vec!["/api/v1/users/{id}", "/{page}"]);
+
+        let candidates: Vec<&str> = index
+            .candidates("/api/v1/users/me")
+            .map(|r| r.pattern.as_str())
+            .collect();
+        assert_eq!(
+            candidates,
+            vec!["/api/v1/users/me", "/api/v1/users/{id}", "/{page}"]
+        );
+
+        let candidates: Vec<&str> = index
+            .candidates("/blog/hello")
+            .map(|r| r.pattern.as_str())
+            .collect();
+        assert_eq!(candidates, vec!["/blog/{slug}", "/{page}"]);
+
+        assert_eq!(literal_prefix("/files/{name}.json"), "/files");
+        assert_eq!(literal_prefix("/{slug}"), "");
+    }
+
+    #[tokio::test]
+    async fn test_panic_response_reports_request_id() {
+        use tower::ServiceExt;
+
+        async fn boom() -> &'static str {
+            panic!("boom")
+        }
+
+        let router = serving_layers(Router::new().route("/boom", axum::routing::get(boom)));
+        let request = axum::http::Request::builder()
+            .uri("/boom")
+            .header("x-request-id", "trace-panic")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.headers()["x-request-id"], "trace-panic");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "trace-panic");
+    }
 }
@@ -2,11 +2,23 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, Map};
-use std::collections::HashMap;
 use axum::response::Response;
 use crate::request::HttpRequest;
 use crate::response::HttpResponse;
+use crate::rate_limiting::{build_rate_limiter, RateLimit};
+use crate::security::verify_body_signature;
 use async_trait::async_trait;
+use jsonschema::JSONSchema;
+use regex::Regex;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MiddlewareDefinition {
@@ -14,6 +26,28 @@ pub struct MiddlewareDefinition {
     pub config: Map<String, Value>,
     pub enabled: bool,
     pub order: i32,
+    // Only run for paths under this prefix ("/admin" or "/admin/*"); None runs everywhere
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+}
+
+impl MiddlewareDefinition {
+    /// Whether this middleware runs for `path`. Prefixes match whole segments, so
+    /// `/admin` covers `/admin` and `/admin/users` but not `/administrator`.
+    pub fn applies_to(&self, path: &str) -> bool {
+        let Some(prefix) = &self.path_prefix else {
+            return true;
+        };
+        let prefix = prefix.trim_end_matches('*').trim_end_matches('/');
+        if prefix.is_empty() {
+            return true;
+        }
+        
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +55,12 @@ pub struct MiddlewareChain {
     pub middleware: Vec<MiddlewareDefinition>,
 }
 
+impl Default for MiddlewareChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MiddlewareChain {
     pub fn new() -> Self {
         Self {
@@ -28,9 +68,11 @@ impl MiddlewareChain {
         }
     }
     
+    /// Middleware runs in ascending `order`; entries with equal `order` keep their
+    /// insertion order.
     pub fn add(&mut self, middleware: MiddlewareDefinition) {
         self.middleware.push(middleware);
-        // Sort by order
+        // Stable sort, so ties stay in insertion order
         self.middleware.sort_by_key(|m| m.order);
     }
     
@@ -42,6 +84,17 @@ impl MiddlewareChain {
 #[async_trait]
 pub trait Middleware: Send + Sync {
     async fn process(&self, request: &HttpRequest) -> Result<(), Response>;
+    
+    // Middleware that rewrites the request (e.g. decompression) overrides this
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        self.process(request).await
+    }
+    
+    // Response phase. Runs for every middleware whose request phase succeeded, including
+    // when a later middleware or the handler short-circuited with an error response.
+    async fn process_response(&self, _request: &HttpRequest, response: Response) -> Response {
+        response
+    }
 }
 
 // CORS Middleware
@@ -50,6 +103,7 @@ pub struct CorsMiddleware {
     pub allow_methods: Vec<String>,
     pub allow_headers: Vec<String>,
     pub max_age: Option<u32>,
+    pub allow_credentials: bool,
 }
 
 impl CorsMiddleware {
@@ -72,28 +126,115 @@ impl CorsMiddleware {
         let max_age = config.get("max_age")
             .and_then(|v| v.as_u64())
             .map(|v| v as u32);
+            
+        let allow_credentials = config.get("allow_credentials")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
         
         Self {
             allow_origins,
             allow_methods,
             allow_headers,
             max_age,
+            allow_credentials,
+        }
+    }
+    
+    // Value for Access-Control-Allow-Origin, or None if the origin isn't allowed.
+    // Specific origins are echoed back; "*" can't be combined with credentials, so the
+    // origin is echoed in that case too.
+    fn allowed_origin(&self, origin: &str) -> Option<String> {
+        if self.allow_origins.iter().any(|o| o == "*") {
+            return Some(if self.allow_credentials { origin.to_string() } else { "*".to_string() });
+        }
+        self.allow_origins.iter()
+            .find(|allowed| allowed.eq_ignore_ascii_case(origin))
+            .map(|_| origin.to_string())
+    }
+    
+    fn is_preflight(request: &HttpRequest) -> bool {
+        request.method.eq_ignore_ascii_case("OPTIONS")
+            && request.has_header("origin")
+            && request.has_header("access-control-request-method")
+    }
+    
+    fn apply_origin_headers(&self, origin: &str, headers: &mut axum::http::HeaderMap) {
+        use axum::http::HeaderValue;
+        
+        let Some(allowed) = self.allowed_origin(origin) else {
+            return;
+        };
+        if let Ok(value) = HeaderValue::from_str(&allowed) {
+            headers.insert("access-control-allow-origin", value);
+        }
+        if allowed != "*" {
+            headers.append("vary", HeaderValue::from_static("Origin"));
+        }
+        if self.allow_credentials {
+            headers.insert("access-control-allow-credentials", HeaderValue::from_static("true"));
         }
     }
+    
+    fn preflight_response(&self, request: &HttpRequest) -> Response {
+        let mut response = HttpResponse::no_content();
+        
+        let allow_headers = if self.allow_headers.iter().any(|h| h == "*") && self.allow_credentials {
+            // Wildcards aren't honored with credentials; echo what the browser asked for
+            request.get_header("access-control-request-headers").cloned().unwrap_or_default()
+        } else {
+            self.allow_headers.join(", ")
+        };
+        
+        response = response.with_header("access-control-allow-methods", &self.allow_methods.join(", "));
+        if !allow_headers.is_empty() {
+            response = response.with_header("access-control-allow-headers", &allow_headers);
+        }
+        if let Some(max_age) = self.max_age {
+            response = response.with_header("access-control-max-age", &max_age.to_string());
+        }
+        
+        let mut response = response.into_axum_response();
+        if let Some(origin) = request.get_header("origin") {
+            self.apply_origin_headers(origin, response.headers_mut());
+        }
+        response
+    }
 }
 
 #[async_trait]
 impl Middleware for CorsMiddleware {
     async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
-        // CORS is handled in response headers, not request validation
+        // Preflight requests are answered here and never reach the handler
+        if Self::is_preflight(request) {
+            return Err(self.preflight_response(request));
+        }
         Ok(())
     }
+    
+    async fn process_response(&self, request: &HttpRequest, mut response: Response) -> Response {
+        if Self::is_preflight(request) {
+            return response;
+        }
+        if let Some(origin) = request.get_header("origin") {
+            self.apply_origin_headers(origin, response.headers_mut());
+        }
+        response
+    }
 }
 
+// Limiters shared by every request with the same configuration. Middleware instances are
+// rebuilt from config for every request, so the counters can't live on them.
+static RATE_LIMITERS: Lazy<DashMap<String, Arc<dyn RateLimit>>> = Lazy::new(DashMap::new);
+
 // Rate Limiting Middleware
+//
+// `backend` is "memory" (per instance, the default) or "redis" with a `redis_url`, which
+// enforces one limit across every instance sharing that Redis.
 pub struct RateLimitingMiddleware {
     pub requests_per_minute: u32,
     pub window_seconds: u64,
+    pub backend: String,
+    pub redis_url: Option<String>,
 }
 
 impl RateLimitingMiddleware {
@@ -106,19 +247,156 @@ impl RateLimitingMiddleware {
             .and_then(|v| v.as_u64())
             .unwrap_or(60);
         
+        let backend = config.get("backend")
+            .and_then(|v| v.as_str())
+            .unwrap_or("memory")
+            .to_string();
+            
+        let redis_url = config.get("redis_url")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        
         Self {
             requests_per_minute,
             window_seconds,
+            backend,
+            redis_url,
         }
     }
+    
+    fn limiter(&self) -> Arc<dyn RateLimit> {
+        let key = format!(
+            "{}|{}|{}|{}",
+            self.backend,
+            self.redis_url.as_deref().unwrap_or(""),
+            self.requests_per_minute,
+            self.window_seconds
+        );
+        RATE_LIMITERS.entry(key)
+            .or_insert_with(|| build_rate_limiter(
+                &self.backend,
+                self.redis_url.as_deref(),
+                self.requests_per_minute,
+                self.window_seconds,
+            ))
+            .clone()
+    }
 }
 
 #[async_trait]
 impl Middleware for RateLimitingMiddleware {
     async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
-        // Rate limiting is handled at the server level
+        let limiter = self.limiter();
+        let client = client_ip(request);
+        
+        // The Redis backend does network I/O; keep it off the async workers
+        let (allowed, reset_at) = tokio::task::spawn_blocking(move || {
+            let allowed = limiter.check_rate_limit(&client);
+            let reset_at = if allowed { None } else { limiter.get_reset_time(&client) };
+            (allowed, reset_at)
+        })
+        .await
+        .unwrap_or((true, None));
+        
+        if allowed {
+            return Ok(());
+        }
+        
+        let retry_after = reset_at
+            .map(|reset_at| reset_at.saturating_duration_since(std::time::Instant::now()).as_secs().max(1))
+            .unwrap_or(self.window_seconds);
+        let response = HttpResponse::too_many_requests("Rate limit exceeded")
+            .with_header("retry-after", &retry_after.to_string());
+        Err(response.into_axum_response())
+    }
+}
+
+// Client identity for per-client limits: the IP from `remote_addr`, without the port
+fn client_ip(request: &HttpRequest) -> String {
+    if request.remote_addr.is_empty() {
+        return "unknown".to_string();
+    }
+    request.remote_addr.parse::<std::net::SocketAddr>()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| request.remote_addr.clone())
+}
+
+// Per-client in-flight request slots, keyed by client IP. Middleware instances are
+// rebuilt from config for every request, so the semaphores live here.
+static CONCURRENCY_SLOTS: Lazy<DashMap<String, Arc<Semaphore>>> = Lazy::new(DashMap::new);
+
+// Request extension marking that this request holds a slot to give back
+const CONCURRENCY_PERMIT_EXTENSION: &str = "concurrency_permit";
+
+// Concurrency Limiting Middleware
+//
+// Caps in-flight requests per client IP. A slot is taken in the request phase and given
+// back in the response phase, so callers must run `execute_response_middleware` for every
+// request that passed `execute_middleware`.
+pub struct ConcurrencyLimitMiddleware {
+    pub max_concurrent_requests: usize,
+}
+
+impl ConcurrencyLimitMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let max_concurrent_requests = config.get("max_concurrent_requests")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(64) as usize;
+        
+        Self {
+            max_concurrent_requests: max_concurrent_requests.max(1),
+        }
+    }
+    
+    fn try_acquire(&self, client: String) -> bool {
+        // Acquire under the map entry lock so `release` can't drop the semaphore in between
+        let entry = CONCURRENCY_SLOTS.entry(client)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_concurrent_requests)));
+        let acquired = match entry.value().try_acquire() {
+            Ok(permit) => {
+                permit.forget();
+                true
+            }
+            Err(_) => false,
+        };
+        acquired
+    }
+    
+    fn release(&self, client: &str) {
+        if let Some(semaphore) = CONCURRENCY_SLOTS.get(client) {
+            semaphore.add_permits(1);
+        }
+        // Forget idle clients so the map doesn't grow with every IP ever seen
+        CONCURRENCY_SLOTS.remove_if(client, |_, semaphore| {
+            semaphore.available_permits() >= self.max_concurrent_requests
+        });
+    }
+}
+
+#[async_trait]
+impl Middleware for ConcurrencyLimitMiddleware {
+    async fn process(&self, _request: &HttpRequest) -> Result<(), Response> {
+        // Taking a slot has to be recorded on the request; see `process_mut`
         Ok(())
     }
+    
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        if !self.try_acquire(client_ip(request)) {
+            let response = HttpResponse::too_many_requests("Too many concurrent requests")
+                .with_header("retry-after", "1");
+            return Err(response.into_axum_response());
+        }
+        
+        request.set_extension(CONCURRENCY_PERMIT_EXTENSION, Value::Bool(true));
+        Ok(())
+    }
+    
+    async fn process_response(&self, request: &HttpRequest, response: Response) -> Response {
+        if request.get_extension(CONCURRENCY_PERMIT_EXTENSION).is_some() {
+            self.release(&client_ip(request));
+        }
+        response
+    }
 }
 
 // Authentication Middleware
@@ -163,9 +441,7 @@ impl Middleware for AuthMiddleware {
         
         // Check for authorization header
         if let Some(auth_header) = request.get_header(&self.header_name) {
-            if auth_header.starts_with("Bearer ") {
-                let token = &auth_header[7..];
-                
+            if let Some(token) = auth_header.strip_prefix("Bearer ") {
                 // Validate JWT token (simplified validation)
                 if self.validate_token(token) {
                     return Ok(());
@@ -177,6 +453,17 @@ impl Middleware for AuthMiddleware {
         let response = HttpResponse::unauthorized("Authentication required");
         Err(self.convert_to_axum_response(response))
     }
+    
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        self.process(request).await?;
+        
+        // Expose the token's subject so handlers don't have to decode it again
+        if let Some(user_id) = request.get_bearer_token().and_then(|token| Self::token_subject(&token)) {
+            request.set_extension("user_id", user_id);
+        }
+        
+        Ok(())
+    }
 }
 
 impl AuthMiddleware {
@@ -186,14 +473,165 @@ impl AuthMiddleware {
     }
     
     fn convert_to_axum_response(&self, http_response: HttpResponse) -> Response {
-        let mut response = Response::builder()
-            .status(http_response.status);
+        http_response.into_axum_response()
+    }
+    
+    // `sub` claim of a JWT-shaped token (header.payload.signature); None for opaque tokens
+    fn token_subject(token: &str) -> Option<Value> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        
+        let payload = token.split('.').nth(1)?;
+        let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?).ok()?;
+        claims.get("sub").filter(|sub| sub.is_string() || sub.is_number()).cloned()
+    }
+}
+
+// HTTP Basic Authentication Middleware
+//
+// Missing or wrong credentials get a 401 with `WWW-Authenticate: Basic realm="..."`, which
+// makes browsers show their login prompt.
+pub type BasicAuthVerifier = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+pub struct BasicAuthMiddleware {
+    pub realm: String,
+    pub exclude_paths: Vec<String>,
+    verifier: BasicAuthVerifier,
+}
+
+impl BasicAuthMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let realm = config.get("realm")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Restricted")
+            .to_string();
+            
+        let exclude_paths = config.get("exclude_paths")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        
+        // Without a configured username and password every request is challenged
+        let expected = match (
+            config.get("username").and_then(|v| v.as_str()),
+            config.get("password").and_then(|v| v.as_str()),
+        ) {
+            (Some(username), Some(password)) => Some((username.to_string(), password.to_string())),
+            _ => None,
+        };
+        let verifier: BasicAuthVerifier = Arc::new(move |username, password| {
+            expected.as_ref().is_some_and(|(expected_user, expected_password)| {
+                // Evaluate both so the timing doesn't reveal which one was wrong
+                let user_ok = constant_time_eq(username.as_bytes(), expected_user.as_bytes());
+                let password_ok = constant_time_eq(password.as_bytes(), expected_password.as_bytes());
+                user_ok & password_ok
+            })
+        });
+        
+        Self {
+            realm,
+            exclude_paths,
+            verifier,
+        }
+    }
+    
+    /// Check credentials with `verifier(username, password)` instead of a fixed pair.
+    pub fn with_verifier(realm: &str, verifier: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            realm: realm.to_string(),
+            exclude_paths: Vec::new(),
+            verifier: Arc::new(verifier),
+        }
+    }
+    
+    fn challenge(&self) -> Response {
+        let realm = self.realm.replace('\\', "\\\\").replace('"', "\\\"");
+        HttpResponse::unauthorized("Authentication required")
+            .with_header("www-authenticate", &format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm))
+            .into_axum_response()
+    }
+}
+
+#[async_trait]
+impl Middleware for BasicAuthMiddleware {
+    async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
+        if self.exclude_paths.contains(&request.path) {
+            return Ok(());
+        }
+        
+        match request.get_basic_auth() {
+            Some((username, password)) if (self.verifier)(&username, &password) => Ok(()),
+            _ => Err(self.challenge()),
+        }
+    }
+    
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        self.process(request).await?;
+        
+        if let Some((username, _)) = request.get_basic_auth() {
+            request.set_extension("user_id", Value::String(username));
+        }
+        
+        Ok(())
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Webhook Signature Middleware
+//
+// Verifies an HMAC-SHA256 of the raw request body sent in a header, as GitHub
+// (`X-Hub-Signature-256: sha256=<hex>`) and similar providers do.
+pub struct WebhookSignatureMiddleware {
+    pub header: String,
+    pub prefix: String,
+    secret: Option<String>,
+}
+
+impl WebhookSignatureMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let header = config.get("header")
+            .and_then(|v| v.as_str())
+            .unwrap_or("x-hub-signature-256")
+            .to_lowercase();
             
-        for (key, value) in &http_response.headers {
-            response = response.header(key, value);
+        let prefix = config.get("prefix")
+            .and_then(|v| v.as_str())
+            .unwrap_or("sha256=")
+            .to_string();
+        
+        // Without a secret nothing can be verified, so every request is rejected
+        let secret = config.get("secret")
+            .and_then(|v| v.as_str())
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| secret.to_string());
+        
+        Self {
+            header,
+            prefix,
+            secret,
         }
+    }
+}
+
+#[async_trait]
+impl Middleware for WebhookSignatureMiddleware {
+    async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
+        let verified = match (&self.secret, request.get_header(&self.header)) {
+            (Some(secret), Some(value)) => value.trim()
+                .strip_prefix(self.prefix.as_str())
+                .is_some_and(|signature| verify_body_signature(request.raw_body(), signature, secret)),
+            _ => false,
+        };
         
-        response.body(axum::body::Body::from(http_response.body)).unwrap()
+        if verified {
+            Ok(())
+        } else {
+            tracing::warn!(path = %request.path, "rejected webhook with missing or invalid signature");
+            Err(HttpResponse::unauthorized("Invalid webhook signature").into_axum_response())
+        }
     }
 }
 
@@ -235,10 +673,30 @@ impl SecurityHeadersMiddleware {
 
 #[async_trait]
 impl Middleware for SecurityHeadersMiddleware {
-    async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
+    async fn process(&self, _request: &HttpRequest) -> Result<(), Response> {
         // Security headers are added to responses, not request validation
         Ok(())
     }
+    
+    async fn process_response(&self, _request: &HttpRequest, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        let mut insert = |name: &'static str, value: String| {
+            if let Ok(value) = value.parse() {
+                headers.insert(name, value);
+            }
+        };
+        
+        insert("strict-transport-security", format!("max-age={}", self.hsts_max_age));
+        if self.content_type_options {
+            insert("x-content-type-options", "nosniff".to_string());
+        }
+        insert("x-frame-options", self.frame_options.clone());
+        if self.xss_protection {
+            insert("x-xss-protection", "1; mode=block".to_string());
+        }
+        
+        response
+    }
 }
 
 // Logging Middleware
@@ -246,8 +704,13 @@ pub struct LoggingMiddleware {
     pub log_level: String,
     pub include_body: bool,
     pub include_headers: bool,
+    // Fraction of requests logged (0.0-1.0); 4xx/5xx responses are always logged
+    pub sample_rate: f64,
 }
 
+// Request extension holding the sampling decision, so both phases agree on it
+const LOG_SAMPLED_EXTENSION: &str = "log_sampled";
+
 impl LoggingMiddleware {
     pub fn new(config: &Map<String, Value>) -> Self {
         let log_level = config.get("log_level")
@@ -262,11 +725,18 @@ impl LoggingMiddleware {
         let include_headers = config.get("include_headers")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+            
+        let sample_rate = config.get("sample_rate")
+            .and_then(|v| v.as_f64())
+            .filter(|rate| !rate.is_nan())
+            .map(|rate| rate.clamp(0.0, 1.0))
+            .unwrap_or(1.0);
         
         Self {
             log_level,
             include_body,
             include_headers,
+            sample_rate,
         }
     }
 }
@@ -274,6 +744,10 @@ impl LoggingMiddleware {
 #[async_trait]
 impl Middleware for LoggingMiddleware {
     async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
+        if !self.is_sampled(request) {
+            return Ok(());
+        }
+        
         // Log the request
         let mut log_msg = format!("{} {} from {}", 
             request.method, 
@@ -289,16 +763,51 @@ impl Middleware for LoggingMiddleware {
             log_msg.push_str(&format!(" Body: {}", request.body));
         }
         
-        match self.log_level.as_str() {
-            "debug" => tracing::debug!("{}", log_msg),
-            "info" => tracing::info!("{}", log_msg),
-            "warn" => tracing::warn!("{}", log_msg),
-            "error" => tracing::error!("{}", log_msg),
-            _ => tracing::info!("{}", log_msg),
-        }
+        self.log(&log_msg);
         
         Ok(())
     }
+    
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        if self.sample_rate < 1.0 {
+            let sampled = rand::random::<f64>() < self.sample_rate;
+            request.set_extension(LOG_SAMPLED_EXTENSION, Value::Bool(sampled));
+        }
+        self.process(request).await
+    }
+    
+    async fn process_response(&self, request: &HttpRequest, response: Response) -> Response {
+        let is_error = response.status().is_client_error() || response.status().is_server_error();
+        if !is_error && !self.is_sampled(request) {
+            return response;
+        }
+        
+        self.log(&format!(
+            "{} {} -> {}",
+            request.method,
+            request.path,
+            response.status().as_u16()
+        ));
+        response
+    }
+}
+
+impl LoggingMiddleware {
+    // Without a decision from `process_mut` only a full sample rate logs
+    fn is_sampled(&self, request: &HttpRequest) -> bool {
+        request.get_extension_as::<bool>(LOG_SAMPLED_EXTENSION)
+            .unwrap_or(self.sample_rate >= 1.0)
+    }
+    
+    fn log(&self, message: &str) {
+        match self.log_level.as_str() {
+            "debug" => tracing::debug!("{}", message),
+            "info" => tracing::info!("{}", message),
+            "warn" => tracing::warn!("{}", message),
+            "error" => tracing::error!("{}", message),
+            _ => tracing::info!("{}", message),
+        }
+    }
 }
 
 // Validation Middleware
@@ -306,6 +815,9 @@ pub struct ValidationMiddleware {
     pub max_content_length: usize,
     pub allowed_content_types: Vec<String>,
     pub required_headers: Vec<String>,
+    pub required_query_params: Vec<String>,
+    // Query param name -> pattern its value must fully match
+    pub param_patterns: HashMap<String, Regex>,
 }
 
 impl ValidationMiddleware {
@@ -323,11 +835,32 @@ impl ValidationMiddleware {
             .and_then(|v| v.as_array())
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
             .unwrap_or_default();
+            
+        let required_query_params = config.get("required_query_params")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+            
+        let mut param_patterns = HashMap::new();
+        if let Some(patterns) = config.get("param_patterns").and_then(|v| v.as_object()) {
+            for (name, pattern) in patterns {
+                let Some(pattern) = pattern.as_str() else { continue };
+                // Anchored so the whole value has to match, not just a substring
+                match Regex::new(&format!("^(?:{})$", pattern)) {
+                    Ok(regex) => {
+                        param_patterns.insert(name.clone(), regex);
+                    }
+                    Err(e) => tracing::warn!("Invalid pattern for query parameter {}: {}", name, e),
+                }
+            }
+        }
         
         Self {
             max_content_length,
             allowed_content_types,
             required_headers,
+            required_query_params,
+            param_patterns,
         }
     }
 }
@@ -360,64 +893,350 @@ impl Middleware for ValidationMiddleware {
             }
         }
         
+        // Check required query parameters
+        for required_param in &self.required_query_params {
+            if request.get_query_param(required_param).is_none() {
+                let response = HttpResponse::bad_request(&format!("Missing required query parameter: {}", required_param));
+                return Err(self.convert_to_axum_response(response));
+            }
+        }
+        
+        // Check query parameter values against their patterns
+        for (name, pattern) in &self.param_patterns {
+            if let Some(value) = request.get_query_param(name) {
+                if !pattern.is_match(value) {
+                    let response = HttpResponse::bad_request(&format!("Invalid value for query parameter: {}", name));
+                    return Err(self.convert_to_axum_response(response));
+                }
+            }
+        }
+        
         Ok(())
     }
 }
 
 impl ValidationMiddleware {
     fn convert_to_axum_response(&self, http_response: HttpResponse) -> Response {
-        let mut response = Response::builder()
-            .status(http_response.status);
-            
-        for (key, value) in &http_response.headers {
-            response = response.header(key, value);
+        http_response.into_axum_response()
+    }
+}
+
+// JSON Schema validation middleware
+//
+// Config: `{"schemas": {"POST /users": {...}, "/items": {...}}}`. A key is either
+// "METHOD path" or a bare path that applies to every method; the method-specific entry
+// wins. Only requests with a JSON content type are checked.
+pub struct SchemaValidationMiddleware {
+    schemas: HashMap<String, JSONSchema>,
+}
+
+impl SchemaValidationMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let mut schemas = HashMap::new();
+        
+        if let Some(entries) = config.get("schemas").and_then(|v| v.as_object()) {
+            for (route, schema) in entries {
+                match JSONSchema::compile(schema) {
+                    Ok(compiled) => {
+                        schemas.insert(route.clone(), compiled);
+                    }
+                    Err(e) => tracing::warn!("Invalid JSON schema for {}: {}", route, e),
+                }
+            }
         }
         
-        response.body(axum::body::Body::from(http_response.body)).unwrap()
+        Self { schemas }
+    }
+    
+    fn schema_for(&self, request: &HttpRequest) -> Option<&JSONSchema> {
+        self.schemas.get(&format!("{} {}", request.method.to_uppercase(), request.path))
+            .or_else(|| self.schemas.get(&request.path))
     }
 }
 
-// Execute middleware chain
-pub async fn execute_middleware(chain: &MiddlewareChain, request: &HttpRequest) -> Result<(), Response> {
-    for middleware_def in &chain.middleware {
-        if !middleware_def.enabled {
-            continue;
+#[async_trait]
+impl Middleware for SchemaValidationMiddleware {
+    async fn process(&self, request: &HttpRequest) -> Result<(), Response> {
+        if !request.is_json() {
+            return Ok(());
         }
         
-        match middleware_def.name.as_str() {
-            "cors" => {
-                let middleware = CorsMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
-            }
-            "rate_limiting" => {
-                let middleware = RateLimitingMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
-            }
-            "auth" => {
-                let middleware = AuthMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
-            }
-            "security_headers" => {
-                let middleware = SecurityHeadersMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
-            }
-            "logging" => {
-                let middleware = LoggingMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
+        let schema = match self.schema_for(request) {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        
+        let body: Value = match serde_json::from_str(&request.body) {
+            Ok(body) => body,
+            Err(e) => {
+                let response = HttpResponse::bad_request(&format!("Invalid JSON body: {}", e));
+                return Err(response.into_axum_response());
             }
-            "validation" => {
-                let middleware = ValidationMiddleware::new(&middleware_def.config);
-                middleware.process(request).await?;
+        };
+        
+        if let Err(errors) = schema.validate(&body) {
+            let errors: Vec<Value> = errors
+                .map(|error| serde_json::json!({
+                    "path": error.instance_path.to_string(),
+                    "message": error.to_string(),
+                }))
+                .collect();
+            
+            let response = HttpResponse::json(&serde_json::json!({
+                "error": "Request body failed schema validation",
+                "errors": errors,
+            }))
+            .with_status(422);
+            return Err(response.into_axum_response());
+        }
+        
+        Ok(())
+    }
+}
+
+// Request body decompression middleware (Content-Encoding: gzip / deflate)
+pub struct DecompressionMiddleware {
+    pub max_decompressed_size: usize,
+}
+
+impl DecompressionMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let max_decompressed_size = config.get("max_decompressed_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(10 * 1024 * 1024) as usize; // 10MB default
+        
+        Self {
+            max_decompressed_size,
+        }
+    }
+    
+    fn decompress(&self, encoding: &str, compressed: &[u8]) -> Result<Vec<u8>, HttpResponse> {
+        // Read at most one byte past the limit so oversized (zip bomb) bodies are detected
+        // without inflating them fully
+        let limit = self.max_decompressed_size as u64 + 1;
+        let mut decompressed = Vec::new();
+        
+        let result = match encoding {
+            "gzip" | "x-gzip" => GzDecoder::new(compressed).take(limit).read_to_end(&mut decompressed),
+            "deflate" => {
+                // "deflate" is zlib-wrapped per RFC 9110, but some clients send raw deflate
+                let zlib = ZlibDecoder::new(compressed).take(limit).read_to_end(&mut decompressed);
+                if zlib.is_err() {
+                    decompressed.clear();
+                    DeflateDecoder::new(compressed).take(limit).read_to_end(&mut decompressed)
+                } else {
+                    zlib
+                }
             }
-            _ => {
-                tracing::warn!("Unknown middleware: {}", middleware_def.name);
+            _ => return Err(HttpResponse::unsupported_encoding(encoding)),
+        };
+        
+        if result.is_err() {
+            return Err(HttpResponse::bad_request(&format!("Malformed {} request body", encoding)));
+        }
+        
+        if decompressed.len() > self.max_decompressed_size {
+            return Err(HttpResponse::payload_too_large(&format!(
+                "Decompressed body too large. Max allowed: {} bytes",
+                self.max_decompressed_size
+            )));
+        }
+        
+        Ok(decompressed)
+    }
+}
+
+#[async_trait]
+impl Middleware for DecompressionMiddleware {
+    async fn process(&self, _request: &HttpRequest) -> Result<(), Response> {
+        // Decompression rewrites the body, see process_mut
+        Ok(())
+    }
+    
+    async fn process_mut(&self, request: &mut HttpRequest) -> Result<(), Response> {
+        let encoding = match request.get_header("content-encoding") {
+            Some(encoding) => encoding.trim().to_lowercase(),
+            None => return Ok(()),
+        };
+        
+        if encoding.is_empty() || encoding == "identity" {
+            return Ok(());
+        }
+        
+        let compressed = if request.body_bytes.is_empty() {
+            request.body.as_bytes().to_vec()
+        } else {
+            std::mem::take(&mut request.body_bytes)
+        };
+        
+        let decompressed = self.decompress(&encoding, &compressed)
+            .map_err(HttpResponse::into_axum_response)?;
+        
+        request.content_length = decompressed.len();
+        request.body = String::from_utf8_lossy(&decompressed).into_owned();
+        request.body_bytes = decompressed;
+        request.headers.remove("content-encoding");
+        
+        Ok(())
+    }
+}
+
+// Response compression middleware (gzip), limited to an allowlist of content types
+pub struct CompressionMiddleware {
+    pub compressible_types: Vec<String>,
+    pub min_size: usize,
+}
+
+impl CompressionMiddleware {
+    pub fn new(config: &Map<String, Value>) -> Self {
+        let compressible_types = config.get("compressible_types")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_lowercase())).collect())
+            .unwrap_or_else(|| vec!["text/*".to_string(), "application/json".to_string(), "application/javascript".to_string()]);
+            
+        let min_size = config.get("min_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1024) as usize;
+        
+        Self {
+            compressible_types,
+            min_size,
+        }
+    }
+    
+    // Entries ending in "/*" match the whole top-level type; parameters like charset are ignored
+    fn is_compressible(&self, content_type: &str) -> bool {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        self.compressible_types.iter().any(|allowed| match allowed.strip_suffix('*') {
+            Some(prefix) => mime.starts_with(prefix),
+            None => mime == *allowed,
+        })
+    }
+    
+    fn accepts_gzip(request: &HttpRequest) -> bool {
+        request.get_header("accept-encoding")
+            .map(|value| value.split(',').any(|encoding| {
+                let mut parts = encoding.split(';');
+                let name = parts.next().unwrap_or("").trim();
+                let rejected = parts.any(|param| {
+                    param.trim().strip_prefix("q=")
+                        .and_then(|q| q.trim().parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                });
+                (name.eq_ignore_ascii_case("gzip") || name == "*") && !rejected
+            }))
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait]
+impl Middleware for CompressionMiddleware {
+    async fn process(&self, _request: &HttpRequest) -> Result<(), Response> {
+        // Compression happens in the response phase
+        Ok(())
+    }
+    
+    async fn process_response(&self, request: &HttpRequest, response: Response) -> Response {
+        let content_type = response.headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        
+        if !self.is_compressible(&content_type) || response.headers().contains_key("content-encoding") {
+            return response;
+        }
+        
+        let (mut parts, body) = response.into_parts();
+        parts.headers.append("vary", axum::http::HeaderValue::from_static("Accept-Encoding"));
+        
+        let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(_) => return HttpResponse::internal_server_error("Failed to read response body").into_axum_response(),
+        };
+        
+        if bytes.len() < self.min_size || !Self::accepts_gzip(request) {
+            return Response::from_parts(parts, axum::body::Body::from(bytes));
+        }
+        
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let compressed = match encoder.write_all(&bytes).and_then(|_| encoder.finish()) {
+            Ok(compressed) => compressed,
+            Err(_) => return Response::from_parts(parts, axum::body::Body::from(bytes)),
+        };
+        
+        parts.headers.insert("content-encoding", axum::http::HeaderValue::from_static("gzip"));
+        parts.headers.insert("content-length", axum::http::HeaderValue::from(compressed.len()));
+        Response::from_parts(parts, axum::body::Body::from(compressed))
+    }
+}
+
+fn build_middleware(definition: &MiddlewareDefinition) -> Option<Box<dyn Middleware>> {
+    let config = &definition.config;
+    let middleware: Box<dyn Middleware> = match definition.name.as_str() {
+        "cors" => Box::new(CorsMiddleware::new(config)),
+        "rate_limiting" => Box::new(RateLimitingMiddleware::new(config)),
+        "concurrency_limit" => Box::new(ConcurrencyLimitMiddleware::new(config)),
+        "auth" => Box::new(AuthMiddleware::new(config)),
+        "basic_auth" => Box::new(BasicAuthMiddleware::new(config)),
+        "webhook_signature" => Box::new(WebhookSignatureMiddleware::new(config)),
+        "security_headers" => Box::new(SecurityHeadersMiddleware::new(config)),
+        "logging" => Box::new(LoggingMiddleware::new(config)),
+        "validation" => Box::new(ValidationMiddleware::new(config)),
+        "schema_validation" => Box::new(SchemaValidationMiddleware::new(config)),
+        "decompression" => Box::new(DecompressionMiddleware::new(config)),
+        "compression" => Box::new(CompressionMiddleware::new(config)),
+        _ => {
+            tracing::warn!("Unknown middleware: {}", definition.name);
+            return None;
+        }
+    };
+    Some(middleware)
+}
+
+fn enabled_middleware<'a>(chain: &'a MiddlewareChain, path: &'a str) -> impl DoubleEndedIterator<Item = Box<dyn Middleware>> + 'a {
+    chain.middleware.iter()
+        .filter(move |definition| definition.enabled && definition.applies_to(path))
+        .filter_map(build_middleware)
+}
+
+// Execute middleware chain (request phase)
+//
+// Middleware runs in chain order. The first `Err` short-circuits: later middleware is
+// skipped, and the error response is passed back through the response phase of every
+// middleware that already ran, in reverse order, before being returned.
+pub async fn execute_middleware(chain: &MiddlewareChain, request: &mut HttpRequest) -> Result<(), Response> {
+    let mut passed: Vec<Box<dyn Middleware>> = Vec::new();
+    
+    // Middleware may rewrite the request, but which middleware runs is decided by the
+    // path it arrived with
+    let path = request.path.clone();
+    for middleware in enabled_middleware(chain, &path) {
+        if let Err(response) = middleware.process_mut(request).await {
+            let mut response = response;
+            for middleware in passed.iter().rev() {
+                response = middleware.process_response(request, response).await;
             }
+            return Err(response);
         }
+        passed.push(middleware);
     }
     
     Ok(())
 }
 
+// Execute middleware chain (response phase)
+//
+// Call with the handler's response after `execute_middleware` returned `Ok`. Runs in
+// reverse chain order so the first middleware sees the final response.
+pub async fn execute_response_middleware(chain: &MiddlewareChain, request: &HttpRequest, response: Response) -> Response {
+    let mut response = response;
+    for middleware in enabled_middleware(chain, &request.path).rev() {
+        response = middleware.process_response(request, response).await;
+    }
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -432,6 +1251,7 @@ mod tests {
             config: Map::new(),
             enabled: true,
             order: 1,
+            path_prefix: None,
         };
         
         let auth_middleware = MiddlewareDefinition {
@@ -439,6 +1259,7 @@ mod tests {
             config: Map::new(),
             enabled: true,
             order: 2,
+            path_prefix: None,
         };
         
         chain.add(auth_middleware);
@@ -449,6 +1270,219 @@ mod tests {
         assert_eq!(chain.middleware[1].name, "auth");
     }
 
+    #[test]
+    fn test_middleware_chain_equal_order_keeps_insertion_order() {
+        let mut chain = MiddlewareChain::new();
+        for name in ["logging", "auth", "validation"] {
+            chain.add(MiddlewareDefinition {
+                name: name.to_string(),
+                config: Map::new(),
+                enabled: true,
+                order: 5,
+                path_prefix: None,
+            });
+        }
+        chain.add(MiddlewareDefinition {
+            name: "cors".to_string(),
+            config: Map::new(),
+            enabled: true,
+            order: 1,
+            path_prefix: None,
+        });
+        
+        let names: Vec<&str> = chain.middleware.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["cors", "logging", "auth", "validation"]);
+    }
+
+    #[tokio::test]
+    async fn test_response_phase_runs_after_short_circuit() {
+        let mut chain = MiddlewareChain::new();
+        chain.add(MiddlewareDefinition {
+            name: "security_headers".to_string(),
+            config: Map::new(),
+            enabled: true,
+            order: 1,
+            path_prefix: None,
+        });
+        chain.add(MiddlewareDefinition {
+            name: "auth".to_string(),
+            config: Map::new(),
+            enabled: true,
+            order: 2,
+            path_prefix: None,
+        });
+        
+        let mut request = HttpRequest::new();
+        request.path = "/private".to_string();
+        
+        let response = execute_middleware(&chain, &mut request).await.unwrap_err();
+        assert_eq!(response.status(), 401);
+        assert_eq!(response.headers()["x-frame-options"], "DENY");
+    }
+
+    #[tokio::test]
+    async fn test_path_prefix_scopes_middleware() {
+        let mut chain = MiddlewareChain::new();
+        chain.add(MiddlewareDefinition {
+            name: "auth".to_string(),
+            config: Map::new(),
+            enabled: true,
+            order: 0,
+            path_prefix: Some("/admin/*".to_string()),
+        });
+        
+        let request_for = |path: &str| {
+            let mut request = HttpRequest::new();
+            request.path = path.to_string();
+            request
+        };
+        
+        for path in ["/admin", "/admin/users"] {
+            let response = execute_middleware(&chain, &mut request_for(path)).await.unwrap_err();
+            assert_eq!(response.status(), 401);
+        }
+        for path in ["/administrator", "/public", "/"] {
+            assert!(execute_middleware(&chain, &mut request_for(path)).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_per_client() {
+        let mut chain = MiddlewareChain::new();
+        chain.add(MiddlewareDefinition {
+            name: "concurrency_limit".to_string(),
+            config: json!({"max_concurrent_requests": 2}).as_object().unwrap().clone(),
+            enabled: true,
+            order: 0,
+            path_prefix: None,
+        });
+        
+        let request_from = |addr: &str| {
+            let mut request = HttpRequest::new();
+            request.remote_addr = addr.to_string();
+            request
+        };
+        
+        let mut first = request_from("10.1.2.3:5000");
+        let mut second = request_from("10.1.2.3:5001");
+        let mut third = request_from("10.1.2.3:5002");
+        assert!(execute_middleware(&chain, &mut first).await.is_ok());
+        assert!(execute_middleware(&chain, &mut second).await.is_ok());
+        let rejected = execute_middleware(&chain, &mut third).await.unwrap_err();
+        assert_eq!(rejected.status(), 429);
+        
+        // Other clients have their own slots
+        let mut other = request_from("10.9.9.9:5000");
+        assert!(execute_middleware(&chain, &mut other).await.is_ok());
+        
+        // Completing a request frees its slot
+        execute_response_middleware(&chain, &first, HttpResponse::ok().into_axum_response()).await;
+        let mut retry = request_from("10.1.2.3:5003");
+        assert!(execute_middleware(&chain, &mut retry).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiting_shared_across_requests() {
+        let mut chain = MiddlewareChain::new();
+        chain.add(MiddlewareDefinition {
+            name: "rate_limiting".to_string(),
+            config: json!({"requests_per_minute": 2, "backend": "memory"}).as_object().unwrap().clone(),
+            enabled: true,
+            order: 0,
+            path_prefix: None,
+        });
+        
+        let mut request = HttpRequest::new();
+        request.remote_addr = "10.4.4.4:6000".to_string();
+        assert!(execute_middleware(&chain, &mut request).await.is_ok());
+        assert!(execute_middleware(&chain, &mut request).await.is_ok());
+        let rejected = execute_middleware(&chain, &mut request).await.unwrap_err();
+        assert_eq!(rejected.status(), 429);
+        assert!(rejected.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_exposes_user_id() {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        
+        let mut chain = MiddlewareChain::new();
+        chain.add(MiddlewareDefinition {
+            name: "auth".to_string(),
+            config: Map::new(),
+            enabled: true,
+            order: 1,
+            path_prefix: None,
+        });
+        
+        let payload = URL_SAFE_NO_PAD.encode(br#"{"sub":"user-42"}"#);
+        let token = format!("eyJhbGciOiJIUzI1NiJ9.{}.signature", payload);
+        
+        let mut request = HttpRequest::new();
+        request.path = "/profile".to_string();
+        request.headers.insert("authorization".to_string(), format!("Bearer {}", token));
+        
+        assert!(execute_middleware(&chain, &mut request).await.is_ok());
+        assert_eq!(request.get_extension_as::<String>("user_id"), Some("user-42".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_challenges_missing_and_wrong_credentials() {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+        
+        let config = json!({"username": "admin", "password": "s3cret", "realm": "Internal Tools"});
+        let middleware = BasicAuthMiddleware::new(config.as_object().unwrap());
+        
+        let mut request = HttpRequest::new();
+        request.path = "/admin".to_string();
+        let challenge = middleware.process(&request).await.unwrap_err();
+        assert_eq!(challenge.status(), 401);
+        assert_eq!(
+            challenge.headers()["www-authenticate"],
+            "Basic realm=\"Internal Tools\", charset=\"UTF-8\""
+        );
+        
+        request.headers.insert("authorization".to_string(), format!("Basic {}", STANDARD.encode("admin:wrong")));
+        assert_eq!(middleware.process(&request).await.unwrap_err().status(), 401);
+        
+        request.headers.insert("authorization".to_string(), format!("Basic {}", STANDARD.encode("admin:s3cret")));
+        assert!(middleware.process_mut(&mut request).await.is_ok());
+        assert_eq!(request.get_extension_as::<String>("user_id"), Some("admin".to_string()));
+        
+        let custom = BasicAuthMiddleware::with_verifier("Ops", |user, password| user == password);
+        request.headers.insert("authorization".to_string(), format!("Basic {}", STANDARD.encode("ops:ops")));
+        assert!(custom.process(&request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_signature_verifies_raw_body() {
+        use crate::security::sign_body;
+        
+        let config = json!({"secret": "whsec", "header": "X-Hub-Signature-256"});
+        let middleware = WebhookSignatureMiddleware::new(config.as_object().unwrap());
+        
+        let mut request = HttpRequest::new();
+        request.body = r#"{"action":"opened"}"#.to_string();
+        assert_eq!(middleware.process(&request).await.unwrap_err().status(), 401);
+        
+        let signature = sign_body(request.body.as_bytes(), "whsec");
+        request.headers.insert("x-hub-signature-256".to_string(), format!("sha256={}", signature.to_uppercase()));
+        assert!(middleware.process(&request).await.is_ok());
+        
+        // Any change to the body invalidates the signature
+        request.body = r#"{"action":"closed"}"#.to_string();
+        assert_eq!(middleware.process(&request).await.unwrap_err().status(), 401);
+        
+        // Non-UTF-8 payloads are verified over their raw bytes
+        request.body_bytes = vec![0xff, 0x00, 0x10];
+        request.headers.insert("x-hub-signature-256".to_string(), format!("sha256={}", sign_body(&request.body_bytes, "whsec")));
+        assert!(middleware.process(&request).await.is_ok());
+        
+        let unconfigured = WebhookSignatureMiddleware::new(&Map::new());
+        assert_eq!(unconfigured.process(&request).await.unwrap_err().status(), 401);
+    }
+
     #[test]
     fn test_cors_middleware_config() {
         let config = json!({
@@ -461,6 +1495,39 @@ mod tests {
         assert_eq!(cors.allow_origins, vec!["https://example.com"]);
         assert_eq!(cors.allow_methods, vec!["GET", "POST"]);
         assert_eq!(cors.max_age, Some(3600));
+        assert!(!cors.allow_credentials);
+    }
+
+    #[tokio::test]
+    async fn test_cors_middleware_preflight_and_origin_echo() {
+        let config = json!({
+            "allow_origins": ["https://app.example.com"],
+            "allow_methods": ["GET", "POST"],
+            "allow_headers": ["content-type"],
+            "max_age": 600,
+            "allow_credentials": true
+        });
+        let cors = CorsMiddleware::new(config.as_object().unwrap());
+        
+        let mut preflight = HttpRequest::new();
+        preflight.method = "OPTIONS".to_string();
+        preflight.headers.insert("origin".to_string(), "https://app.example.com".to_string());
+        preflight.headers.insert("access-control-request-method".to_string(), "POST".to_string());
+        
+        let response = cors.process(&preflight).await.unwrap_err();
+        assert_eq!(response.status(), 204);
+        assert_eq!(response.headers()["access-control-allow-origin"], "https://app.example.com");
+        assert_eq!(response.headers()["access-control-allow-methods"], "GET, POST");
+        assert_eq!(response.headers()["access-control-allow-headers"], "content-type");
+        assert_eq!(response.headers()["access-control-max-age"], "600");
+        assert_eq!(response.headers()["access-control-allow-credentials"], "true");
+        
+        let mut request = HttpRequest::new();
+        request.method = "GET".to_string();
+        request.headers.insert("origin".to_string(), "https://evil.example.com".to_string());
+        assert!(cors.process(&request).await.is_ok());
+        let response = cors.process_response(&request, HttpResponse::ok().into_axum_response()).await;
+        assert!(!response.headers().contains_key("access-control-allow-origin"));
     }
 
     #[test]
@@ -476,4 +1543,176 @@ mod tests {
         assert_eq!(validation.allowed_content_types, vec!["application/json"]);
         assert_eq!(validation.required_headers, vec!["authorization"]);
     }
+
+    #[tokio::test]
+    async fn test_validation_middleware_query_params() {
+        let config = json!({
+            "required_query_params": ["api_version"],
+            "param_patterns": {"api_version": "v[0-9]+", "limit": "[0-9]{1,3}", "broken": "("}
+        });
+        let validation = ValidationMiddleware::new(config.as_object().unwrap());
+        assert_eq!(validation.param_patterns.len(), 2);
+        
+        let mut request = HttpRequest::new();
+        let response = validation.process(&request).await.unwrap_err();
+        assert_eq!(response.status(), 400);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("api_version"));
+        
+        request.query_params.insert("api_version".to_string(), "v2".to_string());
+        assert!(validation.process(&request).await.is_ok());
+        
+        request.query_params.insert("limit".to_string(), "1000".to_string());
+        assert_eq!(validation.process(&request).await.unwrap_err().status(), 400);
+        
+        request.query_params.insert("limit".to_string(), "50".to_string());
+        request.query_params.insert("api_version".to_string(), "latest".to_string());
+        assert_eq!(validation.process(&request).await.unwrap_err().status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_schema_validation_middleware() {
+        let config = json!({
+            "schemas": {
+                "POST /users": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": {"type": "string"},
+                        "age": {"type": "integer", "minimum": 0}
+                    }
+                }
+            }
+        });
+        let middleware = SchemaValidationMiddleware::new(config.as_object().unwrap());
+        
+        let mut request = HttpRequest::new();
+        request.method = "POST".to_string();
+        request.path = "/users".to_string();
+        request.content_type = "application/json".to_string();
+        request.body = r#"{"name": "Ada", "age": 36}"#.to_string();
+        assert!(middleware.process(&request).await.is_ok());
+        
+        request.body = r#"{"age": -1}"#.to_string();
+        let response = middleware.process(&request).await.unwrap_err();
+        assert_eq!(response.status(), 422);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+        
+        // Non-JSON bodies and routes without a schema pass through untouched
+        request.content_type = "text/plain".to_string();
+        assert!(middleware.process(&request).await.is_ok());
+        request.content_type = "application/json".to_string();
+        request.path = "/other".to_string();
+        assert!(middleware.process(&request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_decompression_middleware() {
+        
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(br#"{"name": "John"}"#).unwrap();
+        let compressed = encoder.finish().unwrap();
+        
+        let mut request = HttpRequest::new();
+        request.headers.insert("content-encoding".to_string(), "gzip".to_string());
+        request.content_length = compressed.len();
+        request.body_bytes = compressed;
+        
+        let middleware = DecompressionMiddleware::new(&Map::new());
+        assert!(middleware.process_mut(&mut request).await.is_ok());
+        assert_eq!(request.body, r#"{"name": "John"}"#);
+        assert_eq!(request.content_length, request.body.len());
+        assert!(!request.has_header("content-encoding"));
+        
+        // Zip bomb protection
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0u8; 4096]).unwrap();
+        let mut request = HttpRequest::new();
+        request.headers.insert("content-encoding".to_string(), "gzip".to_string());
+        request.body_bytes = encoder.finish().unwrap();
+        
+        let config = json!({"max_decompressed_size": 1024});
+        let middleware = DecompressionMiddleware::new(config.as_object().unwrap());
+        let response = middleware.process_mut(&mut request).await.unwrap_err();
+        assert_eq!(response.status(), 413);
+    }
+
+    #[tokio::test]
+    async fn test_logging_sample_rate_decides_once_per_request() {
+        let config = json!({"sample_rate": 0.0});
+        let logging = LoggingMiddleware::new(config.as_object().unwrap());
+        let mut request = HttpRequest::new();
+        assert!(logging.process_mut(&mut request).await.is_ok());
+        assert_eq!(request.get_extension_as::<bool>(LOG_SAMPLED_EXTENSION), Some(false));
+        assert!(!logging.is_sampled(&request));
+        
+        let full = LoggingMiddleware::new(&Map::new());
+        assert_eq!(full.sample_rate, 1.0);
+        let mut request = HttpRequest::new();
+        assert!(full.process_mut(&mut request).await.is_ok());
+        assert!(full.is_sampled(&request));
+        
+        let config = json!({"sample_rate": 7.5});
+        assert_eq!(LoggingMiddleware::new(config.as_object().unwrap()).sample_rate, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_response_phase_chain_modifies_handler_response() {
+        let mut chain = MiddlewareChain::new();
+        for (order, (name, config)) in [
+            ("logging", json!({})),
+            ("security_headers", json!({})),
+            ("compression", json!({"min_size": 16})),
+        ].into_iter().enumerate() {
+            chain.add(MiddlewareDefinition {
+                name: name.to_string(),
+                config: config.as_object().unwrap().clone(),
+                enabled: true,
+                order: order as i32,
+                path_prefix: None,
+            });
+        }
+        
+        let mut request = HttpRequest::new();
+        request.headers.insert("accept-encoding".to_string(), "gzip".to_string());
+        assert!(execute_middleware(&chain, &mut request).await.is_ok());
+        
+        let handler_response = HttpResponse::json(&json!({"items": vec!["sufast"; 32]})).into_axum_response();
+        let response = execute_response_middleware(&chain, &request, handler_response).await;
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        assert_eq!(response.headers()["x-frame-options"], "DENY");
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    }
+
+    #[tokio::test]
+    async fn test_compression_middleware_respects_content_type() {
+        let middleware = CompressionMiddleware::new(json!({"min_size": 16}).as_object().unwrap());
+        let mut request = HttpRequest::new();
+        request.headers.insert("accept-encoding".to_string(), "gzip, deflate".to_string());
+        
+        let payload = json!({"items": vec!["sufast"; 32]});
+        let response = HttpResponse::json(&payload).into_axum_response();
+        let response = middleware.process_response(&request, response).await;
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        assert_eq!(response.headers()["vary"], "Accept-Encoding");
+        
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut decoded = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, payload.to_string());
+        
+        // Already-compressed formats pass through untouched
+        let response = HttpResponse::bytes(vec![0u8; 4096], "image/png").into_axum_response();
+        let response = middleware.process_response(&request, response).await;
+        assert!(!response.headers().contains_key("content-encoding"));
+        assert!(!response.headers().contains_key("vary"));
+        
+        // Below the size threshold only Vary is added
+        let response = HttpResponse::text("ok").into_axum_response();
+        let response = middleware.process_response(&request, response).await;
+        assert!(!response.headers().contains_key("content-encoding"));
+        assert_eq!(response.headers()["vary"], "Accept-Encoding");
+    }
 }
@@ -4,6 +4,14 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// Fixed-window request limiting per client. `RateLimiter` counts in process memory;
+/// `RedisRateLimiter` shares the counts between every instance using the same Redis.
+pub trait RateLimit: Send + Sync {
+    fn check_rate_limit(&self, client_id: &str) -> bool;
+    fn get_remaining_requests(&self, client_id: &str) -> u32;
+    fn get_reset_time(&self, client_id: &str) -> Option<Instant>;
+}
+
 #[derive(Debug, Clone)]
 pub struct RateLimitEntry {
     pub count: u32,
@@ -25,8 +33,10 @@ impl RateLimiter {
             window_duration: Duration::from_secs(window_seconds),
         }
     }
+}
 
-    pub fn check_rate_limit(&self, client_id: &str) -> bool {
+impl RateLimit for RateLimiter {
+    fn check_rate_limit(&self, client_id: &str) -> bool {
         let mut entries = self.entries.lock().unwrap();
         let now = Instant::now();
 
@@ -64,7 +74,7 @@ impl RateLimiter {
         }
     }
 
-    pub fn get_remaining_requests(&self, client_id: &str) -> u32 {
+    fn get_remaining_requests(&self, client_id: &str) -> u32 {
         let entries = self.entries.lock().unwrap();
         match entries.get(client_id) {
             Some(entry) => {
@@ -78,7 +88,7 @@ impl RateLimiter {
         }
     }
 
-    pub fn get_reset_time(&self, client_id: &str) -> Option<Instant> {
+    fn get_reset_time(&self, client_id: &str) -> Option<Instant> {
         let entries = self.entries.lock().unwrap();
         entries
             .get(client_id)
@@ -86,6 +96,182 @@ impl RateLimiter {
     }
 }
 
+// Increment and start the window in one round trip; EXPIRE only on the first hit so the
+// window isn't extended by later requests
+const REDIS_INCR_SCRIPT: &str = r"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+";
+
+// How long Redis may take before a request is counted in memory instead
+const REDIS_TIMEOUT: Duration = Duration::from_millis(100);
+
+// After a failure, skip Redis for this long rather than paying the timeout per request
+const REDIS_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Rate limiter whose counters live in Redis, so the limit holds across every instance
+/// behind a load balancer. While Redis is unreachable requests are counted by an
+/// in-memory `RateLimiter` instead, which limits per instance until Redis is back.
+pub struct RedisRateLimiter {
+    client: redis::Client,
+    connection: Mutex<Option<redis::Connection>>,
+    retry_at: Mutex<Option<Instant>>,
+    script: redis::Script,
+    key_prefix: String,
+    max_requests: u32,
+    window_duration: Duration,
+    fallback: RateLimiter,
+}
+
+impl RedisRateLimiter {
+    /// Fails only for a malformed `redis_url`; the server is not contacted until the
+    /// first check.
+    pub fn new(
+        redis_url: &str,
+        max_requests: u32,
+        window_seconds: u64,
+    ) -> redis::RedisResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(redis_url)?,
+            connection: Mutex::new(None),
+            retry_at: Mutex::new(None),
+            script: redis::Script::new(REDIS_INCR_SCRIPT),
+            key_prefix: "sufast:ratelimit:".to_string(),
+            max_requests,
+            window_duration: Duration::from_secs(window_seconds.max(1)),
+            fallback: RateLimiter::new(max_requests, window_seconds),
+        })
+    }
+
+    fn key(&self, client_id: &str) -> String {
+        format!("{}{}", self.key_prefix, client_id)
+    }
+
+    // Runs `command` on the shared connection, reconnecting if needed. `None` means Redis
+    // is unavailable and the caller should use the in-memory fallback.
+    fn with_connection<T>(
+        &self,
+        command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> Option<T> {
+        if let Some(retry_at) = *self.retry_at.lock().unwrap() {
+            if Instant::now() < retry_at {
+                return None;
+            }
+        }
+
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            match self.connect() {
+                Ok(new_connection) => *connection = Some(new_connection),
+                Err(e) => {
+                    self.mark_unavailable(&e);
+                    return None;
+                }
+            }
+        }
+
+        match command(connection.as_mut().unwrap()) {
+            Ok(value) => {
+                *self.retry_at.lock().unwrap() = None;
+                Some(value)
+            }
+            Err(e) => {
+                // The connection may be half-closed; start over on the next attempt
+                *connection = None;
+                self.mark_unavailable(&e);
+                None
+            }
+        }
+    }
+
+    fn connect(&self) -> redis::RedisResult<redis::Connection> {
+        let connection = self.client.get_connection_with_timeout(REDIS_TIMEOUT)?;
+        connection.set_read_timeout(Some(REDIS_TIMEOUT))?;
+        connection.set_write_timeout(Some(REDIS_TIMEOUT))?;
+        Ok(connection)
+    }
+
+    fn mark_unavailable(&self, error: &redis::RedisError) {
+        let mut retry_at = self.retry_at.lock().unwrap();
+        if retry_at.is_none() {
+            tracing::warn!(
+                "Redis rate limiting unavailable, falling back to in-memory limits: {}",
+                error
+            );
+        }
+        *retry_at = Some(Instant::now() + REDIS_RETRY_INTERVAL);
+    }
+}
+
+impl RateLimit for RedisRateLimiter {
+    fn check_rate_limit(&self, client_id: &str) -> bool {
+        let key = self.key(client_id);
+        let window_seconds = self.window_duration.as_secs();
+        let count: Option<u64> = self.with_connection(|connection| {
+            self.script.key(&key).arg(window_seconds).invoke(connection)
+        });
+
+        match count {
+            Some(count) => count <= u64::from(self.max_requests),
+            None => self.fallback.check_rate_limit(client_id),
+        }
+    }
+
+    fn get_remaining_requests(&self, client_id: &str) -> u32 {
+        let key = self.key(client_id);
+        let count: Option<Option<u64>> =
+            self.with_connection(|connection| redis::cmd("GET").arg(&key).query(connection));
+
+        match count {
+            Some(count) => {
+                let used = count.unwrap_or(0).min(u64::from(self.max_requests)) as u32;
+                self.max_requests - used
+            }
+            None => self.fallback.get_remaining_requests(client_id),
+        }
+    }
+
+    fn get_reset_time(&self, client_id: &str) -> Option<Instant> {
+        let key = self.key(client_id);
+        let ttl_ms: Option<i64> =
+            self.with_connection(|connection| redis::cmd("PTTL").arg(&key).query(connection));
+
+        match ttl_ms {
+            // -2: no window open for this client, -1: key without expiry
+            Some(ttl_ms) if ttl_ms >= 0 => {
+                Some(Instant::now() + Duration::from_millis(ttl_ms as u64))
+            }
+            Some(_) => None,
+            None => self.fallback.get_reset_time(client_id),
+        }
+    }
+}
+
+/// Build the limiter for a `backend` name: `"redis"` (requires `redis_url`) or
+/// `"memory"`. Anything unusable falls back to the in-memory limiter with a warning.
+pub fn build_rate_limiter(
+    backend: &str,
+    redis_url: Option<&str>,
+    max_requests: u32,
+    window_seconds: u64,
+) -> Arc<dyn RateLimit> {
+    match (backend, redis_url) {
+        ("memory", _) => {}
+        ("redis", Some(redis_url)) => {
+            match RedisRateLimiter::new(redis_url, max_requests, window_seconds) {
+                Ok(limiter) => return Arc::new(limiter),
+                Err(e) => tracing::warn!("Invalid redis_url for rate limiting: {}", e),
+            }
+        }
+        ("redis", None) => tracing::warn!("Redis rate limiting needs a redis_url"),
+        (other, _) => tracing::warn!("Unknown rate limiting backend: {}", other),
+    }
+    Arc::new(RateLimiter::new(max_requests, window_seconds))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,4 +331,26 @@ mod tests {
         limiter.check_rate_limit("client1");
         assert_eq!(limiter.get_remaining_requests("client1"), 3);
     }
+
+    #[test]
+    fn test_redis_limiter_falls_back_to_memory_when_unreachable() {
+        // Nothing listens on port 1, so every check takes the in-memory path
+        let limiter = RedisRateLimiter::new("redis://127.0.0.1:1/", 2, 60).unwrap();
+
+        assert!(limiter.check_rate_limit("client1"));
+        assert!(limiter.check_rate_limit("client1"));
+        assert!(!limiter.check_rate_limit("client1"));
+        assert_eq!(limiter.get_remaining_requests("client1"), 0);
+        assert!(limiter.get_reset_time("client1").is_some());
+    }
+
+    #[test]
+    fn test_build_rate_limiter_rejects_bad_redis_config() {
+        assert!(RedisRateLimiter::new("not a url", 5, 60).is_err());
+
+        // Misconfigured Redis still limits, just per instance
+        let limiter = build_rate_limiter("redis", Some("not a url"), 1, 60);
+        assert!(limiter.check_rate_limit("client1"));
+        assert!(!limiter.check_rate_limit("client1"));
+    }
 }
@@ -1,8 +1,28 @@
 // Enhanced request handling with full HTTP support
 
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use chrono::{DateTime, Utc};
+use crate::response::HttpResponse;
+use crate::security::verify_cookie_value;
+
+pub const DEFAULT_MAX_JSON_DEPTH: usize = 64;
+pub const DEFAULT_MAX_JSON_BYTES: usize = 1024 * 1024;
+
+static MAX_JSON_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_JSON_DEPTH);
+static MAX_JSON_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_JSON_BYTES);
+
+/// Limits applied to every JSON body before it reaches the parser. Zero restores the
+/// default for that limit.
+pub fn set_json_limits(max_depth: usize, max_bytes: usize) {
+    let or_default = |value: usize, default: usize| if value == 0 { default } else { value };
+    MAX_JSON_DEPTH.store(or_default(max_depth, DEFAULT_MAX_JSON_DEPTH), Ordering::Relaxed);
+    MAX_JSON_BYTES.store(or_default(max_bytes, DEFAULT_MAX_JSON_BYTES), Ordering::Relaxed);
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpRequest {
@@ -10,6 +30,9 @@ pub struct HttpRequest {
     pub path: String,
     pub headers: HashMap<String, String>,
     pub body: String,
+    // Raw body bytes, for payloads that aren't valid UTF-8 (e.g. compressed uploads)
+    #[serde(skip)]
+    pub body_bytes: Vec<u8>,
     pub query_string: String,
     pub query_params: HashMap<String, String>,
     pub path_params: HashMap<String, String>,
@@ -19,6 +42,15 @@ pub struct HttpRequest {
     pub remote_addr: String,
     pub request_id: u64,
     pub timestamp: DateTime<Utc>,
+    // Values attached by middleware for later middleware and the handler (e.g. "user_id")
+    #[serde(default)]
+    pub extensions: HashMap<String, Value>,
+}
+
+impl Default for HttpRequest {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HttpRequest {
@@ -28,6 +60,7 @@ impl HttpRequest {
             path: String::new(),
             headers: HashMap::new(),
             body: String::new(),
+            body_bytes: Vec::new(),
             query_string: String::new(),
             query_params: HashMap::new(),
             path_params: HashMap::new(),
@@ -37,6 +70,7 @@ impl HttpRequest {
             remote_addr: String::new(),
             request_id: 0,
             timestamp: Utc::now(),
+            extensions: HashMap::new(),
         }
     }
     
@@ -56,34 +90,163 @@ impl HttpRequest {
         self.path_params.get(name)
     }
     
+    pub fn get_path_param_as<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.path_params.get(name)?.parse().ok()
+    }
+    
+    /// Parse a path parameter, or build a 400 response naming it so handlers can write
+    /// `let id: i64 = req.require_path_param_as("id")?;`.
+    pub fn require_path_param_as<T: FromStr>(&self, name: &str) -> Result<T, HttpResponse> {
+        parse_param("path", name, self.path_params.get(name))
+    }
+    
+    pub fn get_query_param_as<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.query_params.get(name)?.parse().ok()
+    }
+    
+    pub fn require_query_param_as<T: FromStr>(&self, name: &str) -> Result<T, HttpResponse> {
+        parse_param("query", name, self.query_params.get(name))
+    }
+    
+    pub fn set_extension(&mut self, key: &str, value: Value) {
+        self.extensions.insert(key.to_string(), value);
+    }
+    
+    pub fn get_extension(&self, key: &str) -> Option<&Value> {
+        self.extensions.get(key)
+    }
+    
+    pub fn get_extension_as<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_value(self.extensions.get(key)?.clone()).ok()
+    }
+    
     pub fn is_json(&self) -> bool {
-        self.content_type.contains("application/json")
+        self.media_type().contains("application/json")
     }
     
     pub fn is_form(&self) -> bool {
-        self.content_type.contains("application/x-www-form-urlencoded")
+        self.media_type().contains("application/x-www-form-urlencoded")
     }
     
     pub fn is_multipart(&self) -> bool {
-        self.content_type.contains("multipart/form-data")
+        self.media_type().contains("multipart/form-data")
+    }
+    
+    // `content_type` when the server filled it in, otherwise the raw header
+    fn media_type(&self) -> &str {
+        if self.content_type.is_empty() {
+            self.get_header("content-type").map_or("", |value| value.as_str())
+        } else {
+            &self.content_type
+        }
+    }
+    
+    /// Guard for handlers that only accept one media type, e.g.
+    /// `req.require_content_type("application/json")?;`. Parameters such as `charset` are
+    /// ignored and `expected` may be a `type/*` range. Anything else gets 415.
+    pub fn require_content_type(&self, expected: &str) -> Result<(), HttpResponse> {
+        let actual = self.content_type.split(';').next().unwrap_or("").trim();
+        let matches = match expected.strip_suffix("/*") {
+            Some(top_level) => actual.split_once('/')
+                .is_some_and(|(actual_top, _)| actual_top.eq_ignore_ascii_case(top_level)),
+            None => actual.eq_ignore_ascii_case(expected),
+        };
+        
+        if matches {
+            return Ok(());
+        }
+        let received = if actual.is_empty() { "none" } else { actual };
+        Err(HttpResponse::unsupported_media_type(&format!(
+            "Unsupported Content-Type: expected {}, got {}",
+            expected, received
+        )))
     }
     
     pub fn is_secure(&self) -> bool {
-        self.get_header("x-forwarded-proto") == Some(&"https".to_string()) ||
-        self.get_header("x-forwarded-ssl") == Some(&"on".to_string())
+        self.get_header("x-forwarded-proto").is_some_and(|proto| proto == "https") ||
+        self.get_header("x-forwarded-ssl").is_some_and(|ssl| ssl == "on")
     }
     
     pub fn parse_json<T>(&self) -> Result<T, serde_json::Error>
     where
         T: for<'de> Deserialize<'de>,
     {
+        check_json_limits(self.body.as_bytes()).map_err(serde::de::Error::custom)?;
         serde_json::from_str(&self.body)
     }
     
+    /// Parse the body as JSON, or build a 400 response describing the offending field
+    /// (path + expected type) so handlers can write `let body = req.json_or_400()?;`.
+    pub fn json_or_400<T>(&self) -> Result<T, HttpResponse>
+    where
+        T: DeserializeOwned,
+    {
+        if let Err(err) = check_json_limits(self.body.as_bytes()) {
+            return Err(HttpResponse::json(&json!({
+                "error": "Invalid request body",
+                "message": err.to_string(),
+            })).with_status(400));
+        }
+        
+        let mut deserializer = serde_json::Deserializer::from_str(&self.body);
+        serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+            let path = err.path().to_string();
+            let field_error = describe_json_error(&path, err.inner());
+            
+            HttpResponse::json(&json!({
+                "error": "Invalid request body",
+                "fields": [field_error],
+            })).with_status(400)
+        })
+    }
+    
     pub fn parse_form(&self) -> Result<HashMap<String, String>, serde_urlencoded::de::Error> {
         serde_urlencoded::from_str(&self.body)
     }
     
+    /// Parse a urlencoded body, rebuilding bracketed names into nested values:
+    /// `user[name]=Ada` becomes `{"user": {"name": "Ada"}}`, and `items[0]=a&items[1]=b`
+    /// or `items[]=a&items[]=b` become `{"items": ["a", "b"]}`. Names that aren't valid
+    /// bracket notation are kept as flat fields.
+    pub fn parse_form_nested(&self) -> Result<Value, serde_urlencoded::de::Error> {
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_bytes(self.raw_body())?;
+        let mut fields = Map::new();
+        for (name, value) in &pairs {
+            insert_nested_field(&mut fields, &form_field_path(name), Value::String(value.clone()));
+        }
+        Ok(indexed_objects_to_arrays(Value::Object(fields)))
+    }
+    
+    /// Parse the body according to `content_type` into one JSON value: JSON as-is, and
+    /// urlencoded or multipart forms as an object of fields. Repeated field names become
+    /// arrays; multipart file parts become `{"filename", "content_type", "size"}`.
+    pub fn parse_body(&self) -> Result<Value, BodyParseError> {
+        if self.is_json() {
+            check_json_limits(self.raw_body())?;
+            Ok(serde_json::from_slice(self.raw_body())?)
+        } else if self.is_form() {
+            let pairs: Vec<(String, String)> = serde_urlencoded::from_bytes(self.raw_body())?;
+            let mut fields = Map::new();
+            for (name, value) in pairs {
+                insert_field(&mut fields, name, Value::String(value));
+            }
+            Ok(Value::Object(fields))
+        } else if self.is_multipart() {
+            parse_multipart(self.raw_body(), &self.content_type).map(Value::Object)
+        } else {
+            Err(BodyParseError::UnsupportedContentType(self.content_type.clone()))
+        }
+    }
+    
+    // The undecoded body when middleware kept one, otherwise the text body
+    pub(crate) fn raw_body(&self) -> &[u8] {
+        if self.body_bytes.is_empty() {
+            self.body.as_bytes()
+        } else {
+            &self.body_bytes
+        }
+    }
+    
     pub fn get_cookies(&self) -> HashMap<String, String> {
         let mut cookies = HashMap::new();
         
@@ -99,33 +262,509 @@ impl HttpRequest {
         cookies
     }
     
+    /// Read a cookie set with `HttpResponse::with_signed_cookie`.
+    /// Returns None if the cookie is missing or its signature doesn't verify.
+    pub fn get_signed_cookie(&self, name: &str, secret: &str) -> Option<String> {
+        let cookies = self.get_cookies();
+        let signed_value = cookies.get(name)?;
+        verify_cookie_value(name, signed_value, secret)
+    }
+    
+    /// Best match for the client's `Accept-Language` among `supported`, or `None` if none
+    /// is acceptable. Ranges are tried in descending q order; `en` matches `en-GB`, and
+    /// `en-US` falls back to `en` when only the primary language is supported. `*` picks
+    /// the first supported language not refused with `q=0`. Entries with a malformed
+    /// q-value are ignored. The result uses the spelling from `supported`.
+    pub fn preferred_language(&self, supported: &[&str]) -> Option<String> {
+        let header = self.get_header("accept-language")?;
+        
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let range = parts.next()?.trim();
+                if range.is_empty() {
+                    return None;
+                }
+                let mut quality = 1.0;
+                for param in parts {
+                    if let Some(q) = param.trim().strip_prefix("q=") {
+                        quality = q.trim().parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                    }
+                }
+                Some((range, quality))
+            })
+            .collect();
+        // Stable, so equal q-values keep header order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        
+        let refused = |tag: &str| ranges.iter().any(|(range, q)| *q == 0.0 && range.eq_ignore_ascii_case(tag));
+        let covers = |range: &str, tag: &str| {
+            tag.eq_ignore_ascii_case(range)
+                || (tag.len() > range.len()
+                    && tag.as_bytes()[range.len()] == b'-'
+                    && tag[..range.len()].eq_ignore_ascii_case(range))
+        };
+        
+        for (range, quality) in &ranges {
+            if *quality == 0.0 {
+                continue;
+            }
+            if *range == "*" {
+                if let Some(tag) = supported.iter().find(|tag| !refused(tag)) {
+                    return Some(tag.to_string());
+                }
+                continue;
+            }
+            
+            // Exact or more specific supported tag, then progressively shorter prefixes
+            let mut prefix = *range;
+            loop {
+                if let Some(tag) = supported.iter().find(|tag| covers(prefix, tag) && !refused(tag)) {
+                    return Some(tag.to_string());
+                }
+                match prefix.rfind('-') {
+                    Some(end) => prefix = &prefix[..end],
+                    None => break,
+                }
+            }
+        }
+        
+        None
+    }
+    
+    /// Entity tags listed in `If-Match`, or `None` without the header. `*` is kept as-is.
+    pub fn if_match(&self) -> Option<Vec<String>> {
+        self.get_header("if-match").map(|value| parse_etag_list(value))
+    }
+    
+    /// Entity tags listed in `If-None-Match`, or `None` without the header.
+    pub fn if_none_match(&self) -> Option<Vec<String>> {
+        self.get_header("if-none-match").map(|value| parse_etag_list(value))
+    }
+    
+    /// Optimistic concurrency check for PUT/PATCH/DELETE. The handler loads the
+    /// resource's current ETag, the same value it sends with `with_etag` on GET (e.g. a
+    /// quoted version counter or content hash; `None` if the resource doesn't exist),
+    /// and calls this before writing, returning the `Err` as-is:
+    ///
+    /// ```ignore
+    /// request.check_if_match(Some(&format!("\"v{}\"", doc.version)))?;
+    /// ```
+    ///
+    /// Passes when `If-Match` is absent, or lists the current ETag (strong comparison,
+    /// so weak tags never match) or `*` for an existing resource; otherwise 412. The
+    /// compare-and-write must still be atomic in storage (e.g. `UPDATE ... WHERE
+    /// version = ?`) so two requests can't both pass the check.
+    pub fn check_if_match(&self, current_etag: Option<&str>) -> Result<(), HttpResponse> {
+        let Some(tags) = self.if_match() else {
+            return Ok(());
+        };
+        
+        let matches = match current_etag {
+            Some(current) => tags.iter().any(|tag| {
+                tag == "*" || (tag == current && !tag.starts_with("W/"))
+            }),
+            None => false,
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(HttpResponse::precondition_failed("Resource has been modified"))
+        }
+    }
+    
     pub fn get_authorization(&self) -> Option<String> {
         self.get_header("authorization").cloned()
     }
     
     pub fn get_bearer_token(&self) -> Option<String> {
-        if let Some(auth) = self.get_authorization() {
-            if auth.starts_with("Bearer ") {
-                return Some(auth[7..].to_string());
+        self.get_authorization()?
+            .strip_prefix("Bearer ")
+            .map(|token| token.to_string())
+    }
+    
+    pub fn get_basic_auth(&self) -> Option<(String, String)> {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+        
+        let auth = self.get_authorization()?;
+        let decoded = STANDARD.decode(auth.strip_prefix("Basic ")?).ok()?;
+        let credentials = String::from_utf8(decoded).ok()?;
+        let (username, password) = credentials.split_once(':')?;
+        Some((username.to_string(), password.to_string()))
+    }
+}
+
+// Turn a serde_json error at `path` into {"field", "error", "expected"}
+fn describe_json_error(path: &str, err: &serde_json::Error) -> Value {
+    use serde_json::error::Category;
+    
+    // serde_json appends " at line X column Y"; keep only the message itself
+    let message = err.to_string();
+    let message = message.split(" at line ").next().unwrap_or(&message).to_string();
+    
+    match err.classify() {
+        Category::Syntax | Category::Eof | Category::Io => json!({
+            "field": Value::Null,
+            "error": "malformed JSON",
+            "detail": message,
+            "line": err.line(),
+            "column": err.column(),
+        }),
+        Category::Data => {
+            if let Some(rest) = message.strip_prefix("missing field `") {
+                let name = rest.trim_end_matches('`');
+                let field = if path == "." { name.to_string() } else { format!("{}.{}", path, name) };
+                json!({
+                    "field": field,
+                    "error": "missing required field",
+                })
+            } else {
+                let expected = message
+                    .split_once("expected ")
+                    .map(|(_, expected)| Value::String(expected.to_string()))
+                    .unwrap_or(Value::Null);
+                json!({
+                    "field": if path == "." { Value::Null } else { Value::String(path.to_string()) },
+                    "error": message,
+                    "expected": expected,
+                })
             }
         }
-        None
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BodyParseError {
+    #[error("Invalid JSON body: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    JsonLimit(#[from] JsonLimitError),
+    #[error("Invalid form body: {0}")]
+    Form(#[from] serde_urlencoded::de::Error),
+    #[error("Invalid multipart body: {0}")]
+    Multipart(String),
+    #[error("Unsupported content type: {0}")]
+    UnsupportedContentType(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum JsonLimitError {
+    #[error("JSON body exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("JSON body nested deeper than {0} levels")]
+    TooDeep(usize),
+}
+
+// Cheap scan before parsing so oversized or deeply nested bodies never reach the
+// recursive parser. Brackets inside strings don't count.
+fn check_json_limits(body: &[u8]) -> Result<(), JsonLimitError> {
+    let max_bytes = MAX_JSON_BYTES.load(Ordering::Relaxed);
+    if body.len() > max_bytes {
+        return Err(JsonLimitError::TooLarge(max_bytes));
     }
     
-    pub fn get_basic_auth(&self) -> Option<(String, String)> {
-        if let Some(auth) = self.get_authorization() {
-            if auth.starts_with("Basic ") {
-                if let Ok(decoded) = base64::decode(&auth[6..]) {
-                    if let Ok(credentials) = String::from_utf8(decoded) {
-                        let parts: Vec<&str> = credentials.splitn(2, ':').collect();
-                        if parts.len() == 2 {
-                            return Some((parts[0].to_string(), parts[1].to_string()));
-                        }
+    let max_depth = MAX_JSON_DEPTH.load(Ordering::Relaxed);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(JsonLimitError::TooDeep(max_depth));
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    
+    Ok(())
+}
+
+// `"a", W/"b,c", *` -> ["\"a\"", "W/\"b,c\"", "*"]; commas inside quotes don't split
+fn parse_etag_list(value: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut rest = value.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    
+    while !rest.is_empty() {
+        let opaque_start = if rest.starts_with("W/") { 2 } else { 0 };
+        let end = if rest[opaque_start..].starts_with('"') {
+            // Through the closing quote, or the rest of the header if unterminated
+            rest[opaque_start + 1..]
+                .find('"')
+                .map_or(rest.len(), |i| opaque_start + i + 2)
+        } else {
+            rest.find(',').unwrap_or(rest.len())
+        };
+        tags.push(rest[..end].trim().to_string());
+        rest = rest[end..].trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    
+    tags
+}
+
+// Add a form field, collecting repeated names into an array
+fn insert_field(fields: &mut Map<String, Value>, name: String, value: Value) {
+    match fields.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            fields.insert(name, value);
+        }
+    }
+}
+
+// Deepest bracket nesting rebuilt by parse_form_nested; deeper names stay flat
+const MAX_FORM_NESTING: usize = 32;
+
+// `user[address][city]` -> ["user", "address", "city"]; `items[]` -> ["items", ""]
+fn form_field_path(name: &str) -> Vec<&str> {
+    let Some(open) = name.find('[') else {
+        return vec![name];
+    };
+    let mut path = vec![&name[..open]];
+    let mut rest = &name[open..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(close) = inner.find(']') else {
+            return vec![name];
+        };
+        path.push(&inner[..close]);
+        rest = &inner[close + 1..];
+    }
+    
+    if path[0].is_empty() || !rest.is_empty() || path.len() > MAX_FORM_NESTING {
+        return vec![name];
+    }
+    path
+}
+
+// Arrays are built as objects keyed by index ("" appends) and converted afterwards, so
+// sparse or out-of-order indexes never allocate placeholder elements
+fn insert_nested_field(fields: &mut Map<String, Value>, path: &[&str], value: Value) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    let name = if first.is_empty() {
+        let next = fields.keys().filter_map(|key| key.parse::<usize>().ok()).max().map_or(0, |max| max + 1);
+        next.to_string()
+    } else {
+        first.to_string()
+    };
+    
+    if rest.is_empty() {
+        insert_field(fields, name, value);
+        return;
+    }
+    let child = fields.entry(name).or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        // `a=1&a[b]=2`: the nested field wins
+        *child = Value::Object(Map::new());
+    }
+    if let Value::Object(children) = child {
+        insert_nested_field(children, rest, value);
+    }
+}
+
+// Objects whose keys are all indexes become arrays, ordered by index
+fn indexed_objects_to_arrays(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let all_indexed = !fields.is_empty() && fields.keys().all(|key| key.parse::<usize>().is_ok());
+            if all_indexed {
+                let mut items: Vec<(usize, Value)> = fields.into_iter()
+                    .map(|(key, value)| (key.parse().unwrap_or(0), indexed_objects_to_arrays(value)))
+                    .collect();
+                items.sort_by_key(|(index, _)| *index);
+                Value::Array(items.into_iter().map(|(_, value)| value).collect())
+            } else {
+                Value::Object(fields.into_iter()
+                    .map(|(key, value)| (key, indexed_objects_to_arrays(value)))
+                    .collect())
+            }
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(indexed_objects_to_arrays).collect()),
+        other => other,
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// multipart/form-data (RFC 7578) into field name -> value
+fn parse_multipart(body: &[u8], content_type: &str) -> Result<Map<String, Value>, BodyParseError> {
+    let malformed = |reason: &str| BodyParseError::Multipart(reason.to_string());
+    
+    let boundary = content_type.split(';')
+        .filter_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .find(|boundary| !boundary.is_empty())
+        .ok_or_else(|| malformed("missing boundary"))?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let part_end = [b"\r\n".as_slice(), &delimiter].concat();
+    
+    let start = find_bytes(body, &delimiter).ok_or_else(|| malformed("boundary not found"))?;
+    let mut rest = &body[start + delimiter.len()..];
+    let mut fields = Map::new();
+    
+    // Each part: CRLF, headers, blank line, content, CRLF + delimiter; "--" closes the body
+    while !rest.starts_with(b"--") {
+        rest = rest.strip_prefix(b"\r\n").ok_or_else(|| malformed("expected CRLF after boundary"))?;
+        let header_end = find_bytes(rest, b"\r\n\r\n").ok_or_else(|| malformed("unterminated part headers"))?;
+        let headers = std::str::from_utf8(&rest[..header_end]).map_err(|_| malformed("part headers are not UTF-8"))?;
+        let content_start = &rest[header_end + 4..];
+        let content_len = find_bytes(content_start, &part_end).ok_or_else(|| malformed("unterminated part"))?;
+        let content = &content_start[..content_len];
+        rest = &content_start[content_len + part_end.len()..];
+        
+        let mut name = None;
+        let mut filename = None;
+        let mut part_type = None;
+        for line in headers.split("\r\n") {
+            let Some((header, value)) = line.split_once(':') else { continue };
+            if header.trim().eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').skip(1) {
+                    match param.trim().split_once('=') {
+                        Some(("name", v)) => name = Some(v.trim_matches('"').to_string()),
+                        Some(("filename", v)) => filename = Some(v.trim_matches('"').to_string()),
+                        _ => {}
                     }
                 }
+            } else if header.trim().eq_ignore_ascii_case("content-type") {
+                part_type = Some(value.trim().to_string());
             }
         }
-        None
+        
+        let name = name.ok_or_else(|| malformed("part without a name"))?;
+        let value = match filename {
+            Some(filename) => json!({
+                "filename": filename,
+                "content_type": part_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+                "size": content.len(),
+            }),
+            None => Value::String(String::from_utf8_lossy(content).into_owned()),
+        };
+        insert_field(&mut fields, name, value);
+    }
+    
+    Ok(fields)
+}
+
+// 400 for a missing or unparseable path/query parameter; `kind` is "path" or "query"
+fn parse_param<T: FromStr>(kind: &str, name: &str, raw: Option<&String>) -> Result<T, HttpResponse> {
+    let raw = raw.ok_or_else(|| {
+        HttpResponse::json(&json!({
+            "error": format!("Missing {} parameter '{}'", kind, name),
+            "parameter": name,
+        })).with_status(400)
+    })?;
+    
+    raw.parse().map_err(|_| {
+        HttpResponse::json(&json!({
+            "error": format!("Invalid {} parameter '{}'", kind, name),
+            "parameter": name,
+            "expected": std::any::type_name::<T>(),
+        })).with_status(400)
+    })
+}
+
+/// The request body as an async stream of chunks, for large uploads that shouldn't be
+/// buffered into `HttpRequest::body`. A route that opts into streaming hands its handler
+/// one of these (built from the axum `Body`) instead; buffering stays the default.
+///
+/// Chunks are yielded as they arrive, so a handler can write them to disk or process
+/// them incrementally. With `max_bytes` set, the stream ends with `TooLarge` once the
+/// body exceeds it.
+pub struct BodyStream {
+    inner: axum::body::BodyDataStream,
+    max_bytes: Option<u64>,
+    received: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BodyStreamError {
+    #[error("Failed to read request body: {0}")]
+    Read(#[from] axum::Error),
+    #[error("Request body exceeds {0} bytes")]
+    TooLarge(u64),
+    #[error("Failed to write request body: {0}")]
+    Write(#[from] std::io::Error),
+}
+
+impl BodyStream {
+    pub fn new(body: axum::body::Body, max_bytes: Option<u64>) -> Self {
+        Self {
+            inner: body.into_data_stream(),
+            max_bytes,
+            received: 0,
+        }
+    }
+    
+    /// Bytes read from the body so far.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+    
+    /// Copy the rest of the body into `writer` (e.g. a `tokio::fs::File`), one chunk at a
+    /// time. Returns the number of bytes written.
+    pub async fn copy_to<W>(mut self, writer: &mut W) -> Result<u64, BodyStreamError>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+        use tokio_stream::StreamExt;
+        
+        let mut written = 0u64;
+        while let Some(chunk) = self.next().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
+
+impl tokio_stream::Stream for BodyStream {
+    type Item = Result<bytes::Bytes, BodyStreamError>;
+    
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::task::Poll;
+        
+        match std::pin::Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.received += chunk.len() as u64;
+                match self.max_bytes {
+                    Some(max_bytes) if self.received > max_bytes => {
+                        Poll::Ready(Some(Err(BodyStreamError::TooLarge(max_bytes))))
+                    }
+                    _ => Poll::Ready(Some(Ok(chunk))),
+                }
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
     }
 }
 
@@ -187,4 +826,235 @@ mod tests {
         assert_eq!(data.name, "John");
         assert_eq!(data.age, 30);
     }
+
+    #[test]
+    fn test_json_limits() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let mut request = HttpRequest::new();
+        request.content_type = "application/json".to_string();
+        
+        request.body = nested(DEFAULT_MAX_JSON_DEPTH);
+        assert!(request.parse_json::<Value>().is_ok());
+        
+        request.body = nested(100_000);
+        assert!(request.parse_json::<Value>().is_err());
+        assert!(matches!(request.parse_body(), Err(BodyParseError::JsonLimit(JsonLimitError::TooDeep(_)))));
+        assert_eq!(request.json_or_400::<Value>().unwrap_err().status, 400);
+        
+        // Brackets inside strings aren't nesting
+        request.body = format!(r#"{{"text": "{}"}}"#, "[".repeat(1000));
+        assert!(request.parse_json::<Value>().is_ok());
+        
+        request.body = format!(r#""{}""#, "a".repeat(DEFAULT_MAX_JSON_BYTES));
+        assert!(matches!(request.parse_body(), Err(BodyParseError::JsonLimit(JsonLimitError::TooLarge(_)))));
+    }
+
+    #[test]
+    fn test_json_or_400_missing_field() {
+        let mut request = HttpRequest::new();
+        request.body = r#"{"name": "John"}"#.to_string();
+        
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct TestData {
+            name: String,
+            age: u32,
+        }
+        
+        let response = request.json_or_400::<TestData>().unwrap_err();
+        assert_eq!(response.status, 400);
+        
+        let body: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["fields"][0]["field"], "age");
+        assert_eq!(body["fields"][0]["error"], "missing required field");
+    }
+
+    #[test]
+    fn test_json_or_400_invalid_type() {
+        let mut request = HttpRequest::new();
+        request.body = r#"{"name": "John", "age": "thirty"}"#.to_string();
+        
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct TestData {
+            name: String,
+            age: u32,
+        }
+        
+        let response = request.json_or_400::<TestData>().unwrap_err();
+        let body: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["fields"][0]["field"], "age");
+        assert_eq!(body["fields"][0]["expected"], "u32");
+    }
+
+    #[test]
+    fn test_typed_path_and_query_params() {
+        let mut request = HttpRequest::new();
+        request.path_params.insert("id".to_string(), "42".to_string());
+        request.path_params.insert("slug".to_string(), "abc".to_string());
+        request.query_params.insert("limit".to_string(), "10".to_string());
+        
+        assert_eq!(request.get_path_param_as::<i64>("id"), Some(42));
+        assert_eq!(request.get_path_param_as::<i64>("slug"), None);
+        assert_eq!(request.require_query_param_as::<u32>("limit").unwrap(), 10);
+        
+        let invalid = request.require_path_param_as::<i64>("slug").unwrap_err();
+        assert_eq!(invalid.status, 400);
+        let body: Value = serde_json::from_str(&invalid.body).unwrap();
+        assert_eq!(body["parameter"], "slug");
+        assert_eq!(body["expected"], "i64");
+        
+        let missing = request.require_query_param_as::<u32>("page").unwrap_err();
+        assert_eq!(missing.status, 400);
+    }
+
+    #[test]
+    fn test_parse_body_dispatches_on_content_type() {
+        let mut request = HttpRequest::new();
+        
+        request.content_type = "application/json".to_string();
+        request.body = r#"{"name": "Ada"}"#.to_string();
+        assert_eq!(request.parse_body().unwrap(), json!({"name": "Ada"}));
+        
+        request.content_type = "application/x-www-form-urlencoded".to_string();
+        request.body = "name=Ada&tag=a&tag=b".to_string();
+        assert_eq!(request.parse_body().unwrap(), json!({"name": "Ada", "tag": ["a", "b"]}));
+        
+        request.content_type = "multipart/form-data; boundary=XyZ".to_string();
+        request.body = concat!(
+            "--XyZ\r\n",
+            "Content-Disposition: form-data; name=\"name\"\r\n\r\n",
+            "Ada\r\n",
+            "--XyZ\r\n",
+            "Content-Disposition: form-data; name=\"avatar\"; filename=\"a.png\"\r\n",
+            "Content-Type: image/png\r\n\r\n",
+            "PNGDATA\r\n",
+            "--XyZ--\r\n",
+        ).to_string();
+        assert_eq!(request.parse_body().unwrap(), json!({
+            "name": "Ada",
+            "avatar": {"filename": "a.png", "content_type": "image/png", "size": 7},
+        }));
+        
+        request.content_type = "text/plain".to_string();
+        assert!(matches!(request.parse_body(), Err(BodyParseError::UnsupportedContentType(_))));
+    }
+
+    #[test]
+    fn test_require_content_type() {
+        let mut request = HttpRequest::new();
+        request.content_type = "Application/JSON; charset=utf-8".to_string();
+        assert!(request.require_content_type("application/json").is_ok());
+        
+        request.content_type = "text/xml".to_string();
+        let rejected = request.require_content_type("application/json").unwrap_err();
+        assert_eq!(rejected.status, 415);
+        let body: Value = serde_json::from_str(&rejected.body).unwrap();
+        assert_eq!(body["error"], "Unsupported Content-Type: expected application/json, got text/xml");
+        assert!(request.require_content_type("text/*").is_ok());
+        
+        request.content_type = String::new();
+        assert_eq!(request.require_content_type("text/*").unwrap_err().status, 415);
+    }
+
+    #[test]
+    fn test_parse_form_nested() {
+        let mut request = HttpRequest::new();
+        request.content_type = "application/x-www-form-urlencoded".to_string();
+        request.body = concat!(
+            "items%5B1%5D=b&items%5B0%5D=a",
+            "&user[name]=John&user[address][city]=Paris",
+            "&tags[]=x&tags[]=y",
+            "&rows[0][id]=1&rows[1][id]=2",
+            "&plain=1&plain=2",
+            "&broken[a=1",
+        ).to_string();
+        
+        assert_eq!(request.parse_form_nested().unwrap(), json!({
+            "items": ["a", "b"],
+            "user": {"name": "John", "address": {"city": "Paris"}},
+            "tags": ["x", "y"],
+            "rows": [{"id": "1"}, {"id": "2"}],
+            "plain": ["1", "2"],
+            "broken[a": "1",
+        }));
+        
+        // The flat parser is unchanged
+        assert_eq!(request.parse_form().unwrap().get("user[name]"), Some(&"John".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_body_stream_copies_chunks_and_enforces_limit() {
+        let chunks: Vec<Result<&'static str, std::io::Error>> = vec![Ok("first,"), Ok("second,"), Ok("third")];
+        let body = axum::body::Body::from_stream(tokio_stream::iter(chunks));
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.bin");
+        let mut file = tokio::fs::File::create(&path).await.unwrap();
+        let written = BodyStream::new(body, None).copy_to(&mut file).await.unwrap();
+        assert_eq!(written, 18);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first,second,third");
+        
+        let body = axum::body::Body::from("x".repeat(64));
+        let result = BodyStream::new(body, Some(16)).copy_to(&mut tokio::io::sink()).await;
+        assert!(matches!(result, Err(BodyStreamError::TooLarge(16))));
+    }
+
+    #[test]
+    fn test_preferred_language() {
+        let mut request = HttpRequest::new();
+        assert_eq!(request.preferred_language(&["en"]), None);
+        
+        let mut with_header = |value: &str, supported: &[&str]| {
+            request.headers.insert("accept-language".to_string(), value.to_string());
+            request.preferred_language(supported)
+        };
+        
+        assert_eq!(with_header("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7", &["en", "de", "fr"]), Some("fr".to_string()));
+        assert_eq!(with_header("de;q=0.5, en-US;q=0.9", &["de", "en-GB"]), Some("en-GB".to_string()));
+        assert_eq!(with_header("en", &["de", "EN-gb"]), Some("EN-gb".to_string()));
+        assert_eq!(with_header("ja, *;q=0.1, de;q=0", &["de", "it"]), Some("it".to_string()));
+        assert_eq!(with_header("de;q=abc, it;q=2, en;q=0.3", &["de", "it", "en"]), Some("en".to_string()));
+        assert_eq!(with_header("ja, ko", &["en"]), None);
+    }
+
+    #[test]
+    fn test_if_match_optimistic_concurrency() {
+        let mut request = HttpRequest::new();
+        assert!(request.check_if_match(Some("\"v1\"")).is_ok());
+        
+        request.headers.insert("if-match".to_string(), r#""v1", W/"v2", "a,b""#.to_string());
+        assert_eq!(request.if_match().unwrap(), vec!["\"v1\"", "W/\"v2\"", "\"a,b\""]);
+        assert!(request.check_if_match(Some("\"v1\"")).is_ok());
+        assert!(request.check_if_match(Some("\"a,b\"")).is_ok());
+        
+        // Weak tags never satisfy If-Match
+        assert_eq!(request.check_if_match(Some("W/\"v2\"")).unwrap_err().status, 412);
+        assert_eq!(request.check_if_match(Some("\"v3\"")).unwrap_err().status, 412);
+        
+        request.headers.insert("if-match".to_string(), "*".to_string());
+        assert!(request.check_if_match(Some("\"v3\"")).is_ok());
+        assert!(request.check_if_match(None).is_err());
+        
+        request.headers.insert("if-none-match".to_string(), "W/\"v9\"".to_string());
+        assert_eq!(request.if_none_match().unwrap(), vec!["W/\"v9\""]);
+    }
+
+    #[test]
+    fn test_signed_cookie_roundtrip() {
+        use crate::response::HttpResponse;
+        
+        let response = HttpResponse::ok().with_signed_cookie("session", "user-42", "s3cret", None);
+        let set_cookie = response.headers.get("set-cookie").unwrap().clone();
+        
+        let mut request = HttpRequest::new();
+        request.headers.insert("cookie".to_string(), set_cookie.clone());
+        assert_eq!(request.get_signed_cookie("session", "s3cret"), Some("user-42".to_string()));
+        assert_eq!(request.get_signed_cookie("session", "wrong-secret"), None);
+        
+        // Tampered value keeps the old signature
+        let tampered = set_cookie.replacen("user-42", "user-1", 1);
+        request.headers.insert("cookie".to_string(), tampered);
+        assert_eq!(request.get_signed_cookie("session", "s3cret"), None);
+    }
 }
@@ -1,13 +1,37 @@
 // Enhanced response handling with full HTTP support
 
+use crate::security::sign_cookie_value;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether `HttpResponse::json` pretty-prints; off (minified) by default
+static JSON_PRETTY: AtomicBool = AtomicBool::new(false);
+
+/// Make `HttpResponse::json` pretty-print its output, e.g. in development. Takes effect
+/// for responses built afterwards.
+pub fn set_json_pretty(enabled: bool) {
+    JSON_PRETTY.store(enabled, Ordering::Relaxed);
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponse {
     pub status: u16,
     pub headers: HashMap<String, String>,
     pub body: String,
+    /// Raw bytes sent as-is over HTTP; takes precedence over `body` when set.
+    /// Not serialized - use `into_ffi` to base64 it for the JSON bridge.
+    #[serde(skip)]
+    pub binary_body: Option<Vec<u8>>,
+}
+
+impl Default for HttpResponse {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HttpResponse {
@@ -16,15 +40,31 @@ impl HttpResponse {
             status: 200,
             headers: HashMap::new(),
             body: String::new(),
+            binary_body: None,
         }
     }
 
+    /// JSON body, minified unless `set_json_pretty(true)` is in effect.
     pub fn json(data: &serde_json::Value) -> Self {
+        Self::json_formatted(data, JSON_PRETTY.load(Ordering::Relaxed))
+    }
+
+    /// JSON body, always pretty-printed.
+    pub fn json_pretty(data: &serde_json::Value) -> Self {
+        Self::json_formatted(data, true)
+    }
+
+    fn json_formatted(data: &serde_json::Value, pretty: bool) -> Self {
         let mut response = Self::new();
         response
             .headers
             .insert("content-type".to_string(), "application/json".to_string());
-        response.body = data.to_string();
+        response.body = if pretty {
+            // Serializing a Value can't fail
+            serde_json::to_string_pretty(data).unwrap_or_else(|_| data.to_string())
+        } else {
+            data.to_string()
+        };
         response
     }
 
@@ -48,9 +88,47 @@ impl HttpResponse {
         response
     }
 
+    /// Serialize `data` as JSON or XML depending on the request's `Accept` header.
+    /// A missing header or `*/*` yields JSON; if the client accepts neither format
+    /// the result is 406 Not Acceptable.
+    pub fn negotiate(data: &serde_json::Value, accept_header: Option<&str>) -> Self {
+        match negotiate_format(accept_header.unwrap_or("*/*")) {
+            Some(NegotiatedFormat::Json) => Self::json(data),
+            Some(NegotiatedFormat::Xml) => {
+                let mut response = Self::new();
+                response.headers.insert(
+                    "content-type".to_string(),
+                    "application/xml; charset=utf-8".to_string(),
+                );
+                response.body = format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}",
+                    value_to_xml("response", data)
+                );
+                response
+            }
+            None => Self::not_acceptable("Supported formats: application/json, application/xml"),
+        }
+    }
+
+    /// 302 Found. Clients may replay a POST as a GET; use the 307/308 variants when the
+    /// method and body must be preserved.
     pub fn redirect(location: &str) -> Self {
+        Self::redirect_with_status(location, 302)
+    }
+
+    /// 308 Permanent Redirect: the method and body are replayed against `location`.
+    pub fn redirect_permanent(location: &str) -> Self {
+        Self::redirect_with_status(location, 308)
+    }
+
+    /// 307 Temporary Redirect: the method and body are replayed against `location`.
+    pub fn redirect_temporary_preserve(location: &str) -> Self {
+        Self::redirect_with_status(location, 307)
+    }
+
+    fn redirect_with_status(location: &str, status: u16) -> Self {
         let mut response = Self::new();
-        response.status = 302;
+        response.status = status;
         response
             .headers
             .insert("location".to_string(), location.to_string());
@@ -71,13 +149,65 @@ impl HttpResponse {
         }
 
         // Convert binary content to base64 for JSON transport
-        response.body = base64::encode(content);
+        response.body = BASE64.encode(content);
         response
             .headers
             .insert("x-binary-content".to_string(), "base64".to_string());
         response
     }
 
+    /// Binary response carried as raw bytes (no base64 inflation) for direct HTTP delivery.
+    pub fn bytes(content: Vec<u8>, content_type: &str) -> Self {
+        let mut response = Self::new();
+        response
+            .headers
+            .insert("content-type".to_string(), content_type.to_string());
+        response.binary_body = Some(content);
+        response
+    }
+
+    /// Prepare for the Python FFI JSON bridge: raw bytes become a base64 string body
+    /// flagged with `x-binary-content: base64`, matching `file`.
+    pub fn into_ffi(mut self) -> Self {
+        if let Some(content) = self.binary_body.take() {
+            self.body = BASE64.encode(content);
+            self.headers
+                .insert("x-binary-content".to_string(), "base64".to_string());
+        }
+        self
+    }
+
+    /// Convert to an axum response, writing `binary_body` bytes verbatim when present.
+    /// 204 and 304 responses are sent without a body, `Content-Type` or `Content-Length`.
+    pub fn into_axum_response(self) -> axum::response::Response {
+        let mut response = axum::response::Response::builder().status(self.status);
+        let bodiless = matches!(self.status, 204 | 304);
+
+        for (key, value) in &self.headers {
+            if bodiless
+                && (key.eq_ignore_ascii_case("content-type")
+                    || key.eq_ignore_ascii_case("content-length"))
+            {
+                continue;
+            }
+            response = response.header(key, value);
+        }
+
+        if bodiless {
+            return response.body(axum::body::Body::empty()).unwrap();
+        }
+
+        let body = match self.binary_body {
+            Some(bytes) => {
+                response = response.header("content-length", bytes.len());
+                axum::body::Body::from(bytes)
+            }
+            None => axum::body::Body::from(self.body),
+        };
+
+        response.body(body).unwrap()
+    }
+
     pub fn with_status(mut self, status: u16) -> Self {
         self.status = status;
         self
@@ -88,6 +218,39 @@ impl HttpResponse {
         self
     }
 
+    /// Replace the `Content-Type` header, e.g. `text/csv` or `application/vnd.api+json`.
+    pub fn with_content_type(mut self, mime: &str) -> Self {
+        self.headers
+            .insert("content-type".to_string(), mime.to_string());
+        self
+    }
+
+    /// Set the `charset` parameter on the current content type, replacing any existing
+    /// one: `HttpResponse::text(..).with_charset("iso-8859-1")` yields
+    /// `text/plain; charset=iso-8859-1`, and `json(..).with_charset("utf-8")` adds the
+    /// charset JSON omits by default. Only the header changes; bodies in a charset other
+    /// than UTF-8 should be encoded by the caller and sent with `bytes`.
+    pub fn with_charset(mut self, charset: &str) -> Self {
+        let mime = self
+            .headers
+            .get("content-type")
+            .map(|content_type| {
+                content_type
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|param| !param.to_ascii_lowercase().starts_with("charset="))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .unwrap_or_else(|| "text/plain".to_string());
+
+        self.headers.insert(
+            "content-type".to_string(),
+            format!("{}; charset={}", mime, charset),
+        );
+        self
+    }
+
     pub fn with_cookie(mut self, name: &str, value: &str, options: Option<CookieOptions>) -> Self {
         let mut cookie = format!("{}={}", name, value);
 
@@ -124,6 +287,19 @@ impl HttpResponse {
         self
     }
 
+    /// Like `with_cookie`, but appends an HMAC signature so tampering can be detected
+    /// with `HttpRequest::get_signed_cookie`.
+    pub fn with_signed_cookie(
+        self,
+        name: &str,
+        value: &str,
+        secret: &str,
+        options: Option<CookieOptions>,
+    ) -> Self {
+        let signed = sign_cookie_value(name, value, secret);
+        self.with_cookie(name, &signed, options)
+    }
+
     pub fn with_cors(mut self) -> Self {
         self.headers
             .insert("access-control-allow-origin".to_string(), "*".to_string());
@@ -136,6 +312,12 @@ impl HttpResponse {
         self
     }
 
+    /// Set the `ETag` header; `etag` is sent as given, so quote it (`"v42"`, `W/"v42"`).
+    pub fn with_etag(mut self, etag: &str) -> Self {
+        self.headers.insert("etag".to_string(), etag.to_string());
+        self
+    }
+
     pub fn with_cache_control(mut self, directive: &str) -> Self {
         self.headers
             .insert("cache-control".to_string(), directive.to_string());
@@ -154,6 +336,97 @@ impl HttpResponse {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NegotiatedFormat {
+    Json,
+    Xml,
+}
+
+// Pick the highest-q format we can produce; ties prefer JSON
+fn negotiate_format(accept: &str) -> Option<NegotiatedFormat> {
+    let mut json_q = 0.0f32;
+    let mut xml_q = 0.0f32;
+
+    for entry in accept.split(',') {
+        let mut parts = entry.split(';');
+        let media_type = parts.next().unwrap_or("").trim().to_lowercase();
+        let q = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        match media_type.as_str() {
+            "application/json" => json_q = json_q.max(q),
+            "application/xml" | "text/xml" => xml_q = xml_q.max(q),
+            "*/*" | "application/*" => {
+                json_q = json_q.max(q);
+                xml_q = xml_q.max(q);
+            }
+            _ => {}
+        }
+    }
+
+    if json_q <= 0.0 && xml_q <= 0.0 {
+        None
+    } else if json_q >= xml_q {
+        Some(NegotiatedFormat::Json)
+    } else {
+        Some(NegotiatedFormat::Xml)
+    }
+}
+
+fn value_to_xml(tag: &str, value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => format!("<{}/>", tag),
+        Value::Bool(b) => format!("<{0}>{1}</{0}>", tag, b),
+        Value::Number(n) => format!("<{0}>{1}</{0}>", tag, n),
+        Value::String(s) => format!("<{0}>{1}</{0}>", tag, escape_xml(s)),
+        Value::Array(items) => {
+            let children: String = items
+                .iter()
+                .map(|item| value_to_xml("item", item))
+                .collect();
+            format!("<{0}>{1}</{0}>", tag, children)
+        }
+        Value::Object(map) => {
+            let children: String = map
+                .iter()
+                .map(|(key, item)| value_to_xml(&xml_tag_name(key), item))
+                .collect();
+            format!("<{0}>{1}</{0}>", tag, children)
+        }
+    }
+}
+
+// JSON keys can contain characters that aren't valid in XML element names
+fn xml_tag_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn escape_xml(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 #[derive(Debug, Clone)]
 pub struct CookieOptions {
     pub max_age: Option<i64>,
@@ -211,10 +484,33 @@ impl HttpResponse {
         Self::json(&serde_json::json!({"error": message})).with_status(405)
     }
 
+    pub fn not_acceptable(message: &str) -> Self {
+        Self::json(&serde_json::json!({"error": message})).with_status(406)
+    }
+
     pub fn conflict(message: &str) -> Self {
         Self::json(&serde_json::json!({"error": message})).with_status(409)
     }
 
+    pub fn precondition_failed(message: &str) -> Self {
+        Self::json(&serde_json::json!({"error": message})).with_status(412)
+    }
+
+    pub fn payload_too_large(message: &str) -> Self {
+        Self::json(&serde_json::json!({"error": message})).with_status(413)
+    }
+
+    pub fn unsupported_media_type(message: &str) -> Self {
+        Self::json(&serde_json::json!({"error": message})).with_status(415)
+    }
+
+    pub fn unsupported_encoding(encoding: &str) -> Self {
+        Self::json(&serde_json::json!({
+            "error": format!("Unsupported Content-Encoding: {}", encoding)
+        }))
+        .with_status(415)
+    }
+
     pub fn unprocessable_entity(message: &str) -> Self {
         Self::json(&serde_json::json!({"error": message})).with_status(422)
     }
@@ -236,6 +532,108 @@ impl HttpResponse {
     }
 }
 
+// Standard API envelope: `{"success": true, "data": ...}` or
+// `{"success": false, "error": {"code", "message", "details"}}`
+impl HttpResponse {
+    /// 200 with `data` wrapped in the success envelope.
+    pub fn api_ok(data: serde_json::Value) -> Self {
+        Self::json(&serde_json::json!({"success": true, "data": data}))
+    }
+
+    /// Error envelope sent with status `code`; `details` (e.g. per-field validation
+    /// errors) is omitted when `None`.
+    pub fn api_error(code: u16, message: &str, details: Option<serde_json::Value>) -> Self {
+        let mut error = serde_json::json!({"code": code, "message": message});
+        if let Some(details) = details {
+            error["details"] = details;
+        }
+        Self::json(&serde_json::json!({"success": false, "error": error})).with_status(code)
+    }
+}
+
+impl HttpResponse {
+    /// Stream newline-delimited JSON: each `Value` received is written as one line and
+    /// sent as its own chunk, and the response ends when every sender is dropped. Use a
+    /// bounded channel; senders then wait while a slow client catches up rather than
+    /// queueing records in memory.
+    pub fn ndjson_stream(
+        receiver: tokio::sync::mpsc::Receiver<serde_json::Value>,
+    ) -> axum::response::Response {
+        use tokio_stream::StreamExt;
+
+        let lines = tokio_stream::wrappers::ReceiverStream::new(receiver).map(|value| {
+            let mut line = serde_json::to_vec(&value).unwrap_or_default();
+            line.push(b'\n');
+            Ok::<_, std::convert::Infallible>(line)
+        });
+
+        axum::response::Response::builder()
+            .status(200)
+            .header("content-type", "application/x-ndjson")
+            .header("cache-control", "no-cache")
+            .body(axum::body::Body::from_stream(lines))
+            .unwrap()
+    }
+}
+
+/// Stream a file from disk as a download. The body is read in chunks rather than
+/// buffered, `Content-Type` is guessed from `suggested_name` (falling back to the path),
+/// and a missing file yields a JSON 404.
+pub async fn download_file(
+    path: impl AsRef<Path>,
+    suggested_name: &str,
+) -> axum::response::Response {
+    let path = path.as_ref();
+
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(_) => return HttpResponse::not_found("File not found").into_axum_response(),
+    };
+    let metadata = match file.metadata().await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return HttpResponse::not_found("File not found").into_axum_response(),
+    };
+
+    let content_type = mime_guess::from_path(suggested_name)
+        .first()
+        .or_else(|| mime_guess::from_path(path).first())
+        .map(|mime| mime.to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(file));
+
+    axum::response::Response::builder()
+        .status(200)
+        .header("content-type", content_type)
+        .header("content-length", metadata.len())
+        .header("content-disposition", content_disposition(suggested_name))
+        .body(body)
+        .unwrap()
+}
+
+// `attachment` with an ASCII fallback `filename` plus an RFC 5987 `filename*` so
+// non-ASCII names survive intact in browsers that support it
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+
+    if fallback == filename {
+        format!("attachment; filename=\"{}\"", filename)
+    } else {
+        format!(
+            "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+            fallback,
+            urlencoding::encode(filename)
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +651,23 @@ mod tests {
         assert!(response.body.contains("Hello, World!"));
     }
 
+    #[test]
+    fn test_json_pretty_response() {
+        let data = serde_json::json!({"user": {"id": 1}});
+
+        let pretty = HttpResponse::json_pretty(&data);
+        assert_eq!(pretty.body, "{\n  \"user\": {\n    \"id\": 1\n  }\n}");
+        assert_eq!(
+            pretty.headers.get("content-type"),
+            Some(&"application/json".to_string())
+        );
+
+        assert_eq!(
+            HttpResponse::json_formatted(&data, false).body,
+            r#"{"user":{"id":1}}"#
+        );
+    }
+
     #[test]
     fn test_html_response() {
         let response = HttpResponse::html("<h1>Hello</h1>");
@@ -265,6 +680,29 @@ mod tests {
         assert_eq!(response.body, "<h1>Hello</h1>");
     }
 
+    #[test]
+    fn test_content_type_and_charset() {
+        let response = HttpResponse::text("caf\u{e9}").with_charset("iso-8859-1");
+        assert_eq!(
+            response.headers.get("content-type"),
+            Some(&"text/plain; charset=iso-8859-1".to_string())
+        );
+
+        let response = HttpResponse::json(&serde_json::json!({})).with_charset("utf-8");
+        assert_eq!(
+            response.headers.get("content-type"),
+            Some(&"application/json; charset=utf-8".to_string())
+        );
+
+        let response = HttpResponse::text("a,b")
+            .with_content_type("text/csv; header=present")
+            .with_charset("utf-8");
+        assert_eq!(
+            response.headers.get("content-type"),
+            Some(&"text/csv; header=present; charset=utf-8".to_string())
+        );
+    }
+
     #[test]
     fn test_redirect_response() {
         let response = HttpResponse::redirect("/home");
@@ -273,6 +711,23 @@ mod tests {
         assert_eq!(response.headers.get("location"), Some(&"/home".to_string()));
     }
 
+    #[test]
+    fn test_method_preserving_redirects() {
+        let permanent = HttpResponse::redirect_permanent("/v2/orders");
+        assert_eq!(permanent.status, 308);
+        assert_eq!(
+            permanent.headers.get("location"),
+            Some(&"/v2/orders".to_string())
+        );
+
+        let temporary = HttpResponse::redirect_temporary_preserve("/v2/orders");
+        assert_eq!(temporary.status, 307);
+        assert_eq!(
+            temporary.headers.get("location"),
+            Some(&"/v2/orders".to_string())
+        );
+    }
+
     #[test]
     fn test_response_chaining() {
         let response = HttpResponse::ok()
@@ -314,4 +769,148 @@ mod tests {
         assert_eq!(response.status, 500);
         assert!(response.body.contains("Something went wrong"));
     }
+
+    #[tokio::test]
+    async fn test_no_content_has_no_body_or_content_type() {
+        let response = HttpResponse::json(&serde_json::json!({"deleted": true}))
+            .with_status(204)
+            .with_header("x-request-id", "abc")
+            .into_axum_response();
+        assert_eq!(response.status(), 204);
+        assert!(!response.headers().contains_key("content-type"));
+        assert!(!response.headers().contains_key("content-length"));
+        assert_eq!(response.headers()["x-request-id"], "abc");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        let response = HttpResponse::bytes(vec![1, 2, 3], "application/octet-stream")
+            .with_status(304)
+            .into_axum_response();
+        assert!(!response.headers().contains_key("content-type"));
+        assert!(!response.headers().contains_key("content-length"));
+
+        let response = HttpResponse::no_content().into_axum_response();
+        assert!(!response.headers().contains_key("content-type"));
+    }
+
+    #[test]
+    fn test_api_envelopes() {
+        let response = HttpResponse::api_ok(serde_json::json!({"id": 7}));
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response.body).unwrap(),
+            serde_json::json!({"success": true, "data": {"id": 7}})
+        );
+
+        let response = HttpResponse::api_error(
+            422,
+            "Validation failed",
+            Some(serde_json::json!({"email": "required"})),
+        );
+        assert_eq!(response.status, 422);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response.body).unwrap(),
+            serde_json::json!({
+                "success": false,
+                "error": {"code": 422, "message": "Validation failed", "details": {"email": "required"}}
+            })
+        );
+
+        let response = HttpResponse::api_error(404, "Not found", None);
+        assert!(!response.body.contains("details"));
+    }
+
+    #[test]
+    fn test_content_negotiation() {
+        let data = serde_json::json!({"user": {"name": "Ada & Co", "tags": ["a", "b"]}});
+
+        let response = HttpResponse::negotiate(&data, None);
+        assert_eq!(
+            response.headers.get("content-type"),
+            Some(&"application/json".to_string())
+        );
+
+        let response = HttpResponse::negotiate(&data, Some("application/xml"));
+        assert_eq!(
+            response.headers.get("content-type"),
+            Some(&"application/xml; charset=utf-8".to_string())
+        );
+        assert!(response.body.contains(
+            "<user><name>Ada &amp; Co</name><tags><item>a</item><item>b</item></tags></user>"
+        ));
+
+        let response =
+            HttpResponse::negotiate(&data, Some("application/xml;q=0.5, application/json"));
+        assert!(response.body.starts_with('{'));
+
+        let response = HttpResponse::negotiate(&data, Some("text/html"));
+        assert_eq!(response.status, 406);
+    }
+
+    #[test]
+    fn test_bytes_response() {
+        let payload = vec![0u8, 159, 146, 150, 255];
+        let response = HttpResponse::bytes(payload.clone(), "application/octet-stream");
+        assert_eq!(response.binary_body.as_deref(), Some(&payload[..]));
+
+        let axum_response = response.clone().into_axum_response();
+        assert_eq!(axum_response.headers()["content-length"], "5");
+
+        let ffi = response.into_ffi();
+        assert_eq!(ffi.body, BASE64.encode(&payload));
+        assert_eq!(
+            ffi.headers.get("x-binary-content"),
+            Some(&"base64".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_file_streams_with_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        std::fs::write(&path, "id,name\n1,Zoë\n").unwrap();
+
+        let response = download_file(&path, "report-Zoë.csv").await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/csv");
+        assert_eq!(response.headers()["content-length"], "15");
+        assert_eq!(
+            response.headers()["content-disposition"],
+            "attachment; filename=\"report-Zo_.csv\"; filename*=UTF-8''report-Zo%C3%AB.csv"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], "id,name\n1,Zoë\n".as_bytes());
+
+        let missing = download_file(dir.path().join("nope.csv"), "nope.csv").await;
+        assert_eq!(missing.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_stream_writes_one_line_per_value() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let response = HttpResponse::ndjson_stream(receiver);
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+        tokio::spawn(async move {
+            for id in 1..=3 {
+                sender
+                    .send(serde_json::json!({"id": id, "name": "row"}))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            "{\"id\":1,\"name\":\"row\"}\n{\"id\":2,\"name\":\"row\"}\n{\"id\":3,\"name\":\"row\"}\n"
+        );
+    }
 }
@@ -2,6 +2,7 @@
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,13 +21,32 @@ pub struct RoutePattern {
     pub param_types: HashMap<String, ParamType>,
 }
 
+// Range and length bounds are inclusive; `None` leaves that side unconstrained
 #[derive(Debug, Clone)]
 pub enum ParamType {
-    String,
-    Integer,
-    Float,
+    String {
+        min_len: Option<usize>,
+        max_len: Option<usize>,
+    },
+    Integer {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    Float {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
     Uuid,
     Slug,
+    // Custom pattern from the spec, e.g. `{year:\d{4}}`; anchored to the whole segment value
+    Regex(Regex),
+}
+
+impl ParamType {
+    const UNBOUNDED_STRING: ParamType = ParamType::String {
+        min_len: None,
+        max_len: None,
+    };
 }
 
 impl RoutePattern {
@@ -44,15 +64,14 @@ impl RoutePattern {
             regex_pattern.push_str(&regex::escape(&path[current_pos..abs_start]));
 
             // Find end of parameter
-            if let Some(end) = path[abs_start..].find('}') {
+            if let Some(end) = find_param_end(&path[abs_start..]) {
                 let abs_end = abs_start + end;
                 let param_spec = &path[abs_start + 1..abs_end];
 
-                let (param_name, param_type) = if param_spec.contains(':') {
-                    let parts: Vec<&str> = param_spec.split(':').collect();
-                    (parts[0], parse_param_type(parts[1]))
-                } else {
-                    (param_spec, ParamType::String)
+                // Split on the first ':' only, regex specs may contain more
+                let (param_name, param_type) = match param_spec.split_once(':') {
+                    Some((name, type_str)) => (name, parse_param_type(type_str)),
+                    None => (param_spec, ParamType::UNBOUNDED_STRING),
                 };
 
                 param_names.push(param_name.to_string());
@@ -60,13 +79,13 @@ impl RoutePattern {
 
                 // Add regex pattern for parameter type
                 let type_pattern = match param_type {
-                    ParamType::Integer => r"(-?\d+)",
-                    ParamType::Float => r"(-?\d+\.?\d*)",
+                    ParamType::Integer { .. } => r"(-?\d+)",
+                    ParamType::Float { .. } => r"(-?\d+\.?\d*)",
                     ParamType::Uuid => {
                         r"([0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12})"
                     }
                     ParamType::Slug => r"([\w\-]+)",
-                    ParamType::String => r"([^/]+)",
+                    ParamType::String { .. } | ParamType::Regex(_) => r"([^/]+)",
                 };
                 regex_pattern.push_str(type_pattern);
 
@@ -97,32 +116,140 @@ impl RoutePattern {
     }
 }
 
+// Accepts `int`, `float`, `uuid`, `slug` and `str`, optionally with constraints:
+// `int(1..1000)`, `float(0..)`, `str(max=50)`, `str(min=2,max=50)`.
+// A malformed constraint falls back to the unconstrained type. Anything else is compiled
+// as a regex; if it doesn't compile the parameter is treated as a plain string.
+// Brace depth is tracked so regex quantifiers like `\d{4}` stay inside the spec.
+// `spec` starts at the opening '{'; returns the offset of the matching '}'.
+fn find_param_end(spec: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, ch) in spec.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
 fn parse_param_type(type_str: &str) -> ParamType {
-    match type_str {
-        "int" => ParamType::Integer,
-        "float" => ParamType::Float,
+    let (name, constraint) = match type_str.split_once('(') {
+        Some((name, rest)) => (name, rest.strip_suffix(')')),
+        None => (type_str, None),
+    };
+
+    match name {
+        "int" => {
+            let (min, max) = constraint
+                .and_then(parse_range::<i64>)
+                .unwrap_or((None, None));
+            ParamType::Integer { min, max }
+        }
+        "float" => {
+            let (min, max) = constraint
+                .and_then(parse_range::<f64>)
+                .unwrap_or((None, None));
+            ParamType::Float { min, max }
+        }
         "uuid" => ParamType::Uuid,
         "slug" => ParamType::Slug,
-        _ => ParamType::String,
+        "str" | "string" => {
+            let (min_len, max_len) = constraint.and_then(parse_length).unwrap_or((None, None));
+            ParamType::String { min_len, max_len }
+        }
+        _ => match Regex::new(&format!("^(?:{})$", type_str)) {
+            Ok(regex) => ParamType::Regex(regex),
+            Err(_) => ParamType::UNBOUNDED_STRING,
+        },
     }
 }
 
+// "1..1000", "1..=1000", "..10" or "5.."; both bounds are inclusive
+fn parse_range<T>(spec: &str) -> Option<(Option<T>, Option<T>)>
+where
+    T: std::str::FromStr + PartialOrd,
+{
+    let (low, high) = spec.split_once("..")?;
+    let high = high.strip_prefix('=').unwrap_or(high);
+
+    let parse_bound = |bound: &str| -> Option<Option<T>> {
+        let bound = bound.trim();
+        if bound.is_empty() {
+            Some(None)
+        } else {
+            bound.parse().ok().map(Some)
+        }
+    };
+
+    let (min, max) = (parse_bound(low)?, parse_bound(high)?);
+    if let (Some(min), Some(max)) = (&min, &max) {
+        if min > max {
+            return None;
+        }
+    }
+    Some((min, max))
+}
+
+// "max=50" or "min=2,max=50"
+fn parse_length(spec: &str) -> Option<(Option<usize>, Option<usize>)> {
+    let mut min_len = None;
+    let mut max_len = None;
+
+    for part in spec.split(',') {
+        let (key, value) = part.split_once('=')?;
+        let value: usize = value.trim().parse().ok()?;
+        match key.trim() {
+            "min" => min_len = Some(value),
+            "max" => max_len = Some(value),
+            _ => return None,
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min_len, max_len) {
+        if min > max {
+            return None;
+        }
+    }
+    Some((min_len, max_len))
+}
+
+fn within_bounds<T: PartialOrd>(value: T, min: &Option<T>, max: &Option<T>) -> bool {
+    let above_min = match min {
+        Some(min) => value >= *min,
+        None => true,
+    };
+    let below_max = match max {
+        Some(max) => value <= *max,
+        None => true,
+    };
+    above_min && below_max
+}
+
 pub fn extract_path_params(pattern: &RoutePattern, path: &str) -> Option<HashMap<String, String>> {
     if let Some(captures) = pattern.regex.captures(path) {
         let mut params = HashMap::new();
 
         for (i, param_name) in pattern.param_names.iter().enumerate() {
             if let Some(captured) = captures.get(i + 1) {
-                let value = captured.as_str();
+                // Matching runs on the raw path so an encoded '/' can't split a segment;
+                // only the captured value is decoded
+                let value = decode_segment(captured.as_str());
 
                 // Validate parameter type
                 if let Some(param_type) = pattern.param_types.get(param_name) {
-                    if !validate_param_type(value, param_type) {
+                    if !validate_param_type(&value, param_type) {
                         return None;
                     }
                 }
 
-                params.insert(param_name.clone(), value.to_string());
+                params.insert(param_name.clone(), value);
             }
         }
 
@@ -132,10 +259,25 @@ pub fn extract_path_params(pattern: &RoutePattern, path: &str) -> Option<HashMap
     }
 }
 
+// Invalid percent sequences stay literal; if the decoded bytes aren't UTF-8 the raw
+// value is kept
+fn decode_segment(value: &str) -> String {
+    if !value.contains('%') {
+        return value.to_string();
+    }
+    urlencoding::decode(value)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or_else(|_| value.to_string())
+}
+
 fn validate_param_type(value: &str, param_type: &ParamType) -> bool {
     match param_type {
-        ParamType::Integer => value.parse::<i64>().is_ok(),
-        ParamType::Float => value.parse::<f64>().is_ok(),
+        ParamType::Integer { min, max } => value
+            .parse::<i64>()
+            .is_ok_and(|n| within_bounds(n, min, max)),
+        ParamType::Float { min, max } => value
+            .parse::<f64>()
+            .is_ok_and(|n| within_bounds(n, min, max)),
         ParamType::Uuid => {
             // Basic UUID format validation
             value.len() == 36 && value.chars().filter(|&c| c == '-').count() == 4
@@ -144,7 +286,235 @@ fn validate_param_type(value: &str, param_type: &ParamType) -> bool {
             // Alphanumeric characters and hyphens only
             value.chars().all(|c| c.is_alphanumeric() || c == '-')
         }
-        ParamType::String => true, // Any string is valid
+        ParamType::Regex(regex) => regex.is_match(value),
+        ParamType::String { min_len, max_len } => {
+            within_bounds(value.chars().count(), min_len, max_len)
+        }
+    }
+}
+
+/// Routes sharing a path prefix and middleware list.
+///
+/// `group("/api/v1").middleware("auth").route("GET", "/users", "list_users")` produces a
+/// `GET /api/v1/users` definition with `auth` applied. Nested groups concatenate prefixes
+/// and inherit the parent's middleware ahead of their own.
+#[derive(Debug, Clone, Default)]
+pub struct RouteGroup {
+    prefix: String,
+    middleware: Vec<String>,
+    routes: Vec<RouteDefinition>,
+}
+
+pub fn group(prefix: &str) -> RouteGroup {
+    RouteGroup::new(prefix)
+}
+
+impl RouteGroup {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: join_paths("", prefix),
+            ..Self::default()
+        }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Applies to routes added after this call, including nested groups.
+    pub fn middleware(mut self, name: &str) -> Self {
+        if !self.middleware.iter().any(|m| m == name) {
+            self.middleware.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn route(self, method: &str, subpath: &str, handler_name: &str) -> Self {
+        self.route_with_middleware(method, subpath, handler_name, &[])
+    }
+
+    /// Route-specific middleware runs after the group's own.
+    pub fn route_with_middleware(
+        mut self,
+        method: &str,
+        subpath: &str,
+        handler_name: &str,
+        middleware: &[&str],
+    ) -> Self {
+        let path = join_paths(&self.prefix, subpath);
+        let mut merged = self.middleware.clone();
+        for name in middleware {
+            if !merged.iter().any(|m| m == name) {
+                merged.push(name.to_string());
+            }
+        }
+
+        self.routes.push(RouteDefinition {
+            method: method.to_uppercase(),
+            params: param_type_names(&path),
+            path,
+            handler_name: handler_name.to_string(),
+            middleware: merged,
+        });
+        self
+    }
+
+    /// Build a nested group under `prefix` and merge its routes into this one.
+    pub fn nest(mut self, prefix: &str, build: impl FnOnce(RouteGroup) -> RouteGroup) -> Self {
+        let child = RouteGroup {
+            prefix: join_paths(&self.prefix, prefix),
+            middleware: self.middleware.clone(),
+            routes: Vec::new(),
+        };
+        self.routes.extend(build(child).routes);
+        self
+    }
+
+    pub fn routes(&self) -> &[RouteDefinition] {
+        &self.routes
+    }
+
+    pub fn into_routes(self) -> Vec<RouteDefinition> {
+        self.routes
+    }
+}
+
+fn join_paths(prefix: &str, subpath: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    let subpath = subpath.trim_start_matches('/');
+
+    match (prefix.is_empty(), subpath.is_empty()) {
+        (true, true) => "/".to_string(),
+        (_, true) => prefix.to_string(),
+        _ => format!("{}/{}", prefix, subpath),
+    }
+}
+
+// Parameter name -> declared type ("string" when untyped), as stored in RouteDefinition
+fn param_type_names(path: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut rest = path;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = find_param_end(&rest[start..]) else {
+            break;
+        };
+        let spec = &rest[start + 1..start + end];
+        let (name, type_name) = spec.split_once(':').unwrap_or((spec, "string"));
+        params.insert(name.to_string(), type_name.to_string());
+        rest = &rest[start + end + 1..];
+    }
+
+    params
+}
+
+/// Minimal OpenAPI 3.0 document for `routes`: one operation per method and path, with
+/// typed path parameters. Request and response schemas aren't known here, so every
+/// operation only declares a generic 200.
+pub fn generate_openapi(routes: &[RouteDefinition]) -> Value {
+    let mut paths = Map::new();
+
+    for route in routes {
+        let pattern = RoutePattern::compile(&route.path);
+        let parameters: Vec<Value> = pattern
+            .param_names
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": param_schema(&pattern.param_types[name]),
+                })
+            })
+            .collect();
+
+        let operation = json!({
+            "operationId": route.handler_name,
+            "parameters": parameters,
+            "responses": {"200": {"description": "Successful response"}},
+        });
+
+        let item = paths
+            .entry(openapi_path(&route.path))
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[route.method.to_lowercase()] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {"title": "Sufast API", "version": "1.0.0"},
+        "paths": paths,
+    })
+}
+
+/// Serves `generate_openapi(routes)` at `GET /openapi.json`; merge it into the app router.
+/// The document is built once, so register every route first.
+pub fn openapi_router(routes: &[RouteDefinition]) -> axum::Router {
+    let spec = generate_openapi(routes);
+    axum::Router::new().route(
+        "/openapi.json",
+        axum::routing::get(move || async move { axum::Json(spec) }),
+    )
+}
+
+// OpenAPI templates name parameters without the type: `/users/{id:int}` -> `/users/{id}`
+fn openapi_path(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut rest = path;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = find_param_end(&rest[start..]) else {
+            break;
+        };
+        let spec = &rest[start + 1..start + end];
+        let name = spec.split_once(':').map_or(spec, |(name, _)| name);
+        result.push_str(&rest[..start]);
+        result.push('{');
+        result.push_str(name);
+        result.push('}');
+        rest = &rest[start + end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn param_schema(param_type: &ParamType) -> Value {
+    match param_type {
+        ParamType::String { min_len, max_len } => {
+            let mut schema = json!({"type": "string"});
+            if let Some(min_len) = min_len {
+                schema["minLength"] = json!(min_len);
+            }
+            if let Some(max_len) = max_len {
+                schema["maxLength"] = json!(max_len);
+            }
+            schema
+        }
+        ParamType::Integer { min, max } => {
+            let mut schema = json!({"type": "integer", "format": "int64"});
+            if let Some(min) = min {
+                schema["minimum"] = json!(min);
+            }
+            if let Some(max) = max {
+                schema["maximum"] = json!(max);
+            }
+            schema
+        }
+        ParamType::Float { min, max } => {
+            let mut schema = json!({"type": "number", "format": "double"});
+            if let Some(min) = min {
+                schema["minimum"] = json!(min);
+            }
+            if let Some(max) = max {
+                schema["maximum"] = json!(max);
+            }
+            schema
+        }
+        ParamType::Uuid => json!({"type": "string", "format": "uuid"}),
+        ParamType::Slug => json!({"type": "string", "pattern": r"^[\w\-]+$"}),
+        ParamType::Regex(regex) => json!({"type": "string", "pattern": regex.as_str()}),
     }
 }
 
@@ -211,4 +581,130 @@ mod tests {
         let invalid_params = extract_path_params(&pattern, "/posts/my post!");
         assert!(invalid_params.is_none());
     }
+
+    #[test]
+    fn test_route_group_prefix_and_middleware() {
+        let routes = group("/api/v1/")
+            .middleware("auth")
+            .route("get", "/users", "list_users")
+            .route_with_middleware("POST", "users", "create_user", &["validation"])
+            .into_routes();
+
+        assert_eq!(routes[0].method, "GET");
+        assert_eq!(routes[0].path, "/api/v1/users");
+        assert_eq!(routes[0].middleware, vec!["auth"]);
+        assert_eq!(routes[1].path, "/api/v1/users");
+        assert_eq!(routes[1].middleware, vec!["auth", "validation"]);
+    }
+
+    #[test]
+    fn test_nested_route_groups() {
+        let routes = group("/api")
+            .middleware("logging")
+            .nest("/v1", |v1| {
+                v1.middleware("auth")
+                    .route("GET", "/users/{id:int}", "get_user")
+                    .nest("/admin", |admin| admin.route("DELETE", "/", "reset"))
+            })
+            .route("GET", "/", "index")
+            .into_routes();
+
+        assert_eq!(routes[0].path, "/api/v1/users/{id:int}");
+        assert_eq!(routes[0].middleware, vec!["logging", "auth"]);
+        assert_eq!(routes[0].params.get("id"), Some(&"int".to_string()));
+        assert_eq!(routes[1].path, "/api/v1/admin");
+        assert_eq!(routes[1].middleware, vec!["logging", "auth"]);
+        assert_eq!(routes[2].path, "/api");
+        assert_eq!(routes[2].middleware, vec!["logging"]);
+    }
+
+    #[test]
+    fn test_generate_openapi_paths_and_param_types() {
+        let routes = group("/api")
+            .route("GET", "/users/{id:int(1..)}", "get_user")
+            .route("DELETE", "/users/{id:int(1..)}", "delete_user")
+            .route("GET", "/orders/{order:uuid}/items/{sku}", "get_item")
+            .into_routes();
+
+        let spec = generate_openapi(&routes);
+        assert_eq!(spec["openapi"], "3.0.3");
+
+        let user = &spec["paths"]["/api/users/{id}"];
+        assert_eq!(user["get"]["operationId"], "get_user");
+        assert_eq!(user["delete"]["operationId"], "delete_user");
+        assert_eq!(
+            user["get"]["parameters"][0]["schema"],
+            json!({"type": "integer", "format": "int64", "minimum": 1})
+        );
+
+        let item = &spec["paths"]["/api/orders/{order}/items/{sku}"]["get"];
+        assert_eq!(item["parameters"][0]["name"], "order");
+        assert_eq!(item["parameters"][0]["schema"]["format"], "uuid");
+        assert_eq!(item["parameters"][1]["name"], "sku");
+        assert_eq!(item["parameters"][1]["schema"], json!({"type": "string"}));
+    }
+
+    #[test]
+    fn test_integer_range_constraint() {
+        let pattern = RoutePattern::compile("/page/{n:int(1..1000)}");
+        assert!(extract_path_params(&pattern, "/page/1").is_some());
+        assert!(extract_path_params(&pattern, "/page/1000").is_some());
+        assert!(extract_path_params(&pattern, "/page/0").is_none());
+        assert!(extract_path_params(&pattern, "/page/1001").is_none());
+
+        let pattern = RoutePattern::compile("/ratio/{r:float(0..=1)}");
+        assert!(extract_path_params(&pattern, "/ratio/0.5").is_some());
+        assert!(extract_path_params(&pattern, "/ratio/1.5").is_none());
+    }
+
+    #[test]
+    fn test_string_length_constraint() {
+        let pattern = RoutePattern::compile("/users/{name:str(max=5)}");
+        assert!(extract_path_params(&pattern, "/users/alice").is_some());
+        assert!(extract_path_params(&pattern, "/users/alexander").is_none());
+    }
+
+    #[test]
+    fn test_malformed_constraint_is_unconstrained() {
+        let pattern = RoutePattern::compile("/page/{n:int(10..1)}");
+        assert!(extract_path_params(&pattern, "/page/5000").is_some());
+
+        let pattern = RoutePattern::compile("/page/{n:int(abc)}");
+        assert!(extract_path_params(&pattern, "/page/-3").is_some());
+        assert!(extract_path_params(&pattern, "/page/abc").is_none());
+    }
+
+    #[test]
+    fn test_regex_param_type() {
+        let pattern = RoutePattern::compile(r"/archive/{year:\d{4}}/{cur:[A-Z]{3}}");
+        let params = extract_path_params(&pattern, "/archive/2024/EUR").unwrap();
+        assert_eq!(params.get("year"), Some(&"2024".to_string()));
+        assert_eq!(params.get("cur"), Some(&"EUR".to_string()));
+
+        assert!(extract_path_params(&pattern, "/archive/24/EUR").is_none());
+        assert!(extract_path_params(&pattern, "/archive/2024/euro").is_none());
+    }
+
+    #[test]
+    fn test_invalid_regex_param_falls_back_to_string() {
+        let pattern = RoutePattern::compile("/items/{id:[unclosed}");
+        let params = extract_path_params(&pattern, "/items/anything").unwrap();
+        assert_eq!(params.get("id"), Some(&"anything".to_string()));
+    }
+
+    #[test]
+    fn test_path_params_are_percent_decoded() {
+        let pattern = RoutePattern::compile("/users/{name}/files/{file}");
+        let params = extract_path_params(&pattern, "/users/John%20Doe/files/a%2Fb").unwrap();
+        assert_eq!(params.get("name"), Some(&"John Doe".to_string()));
+        assert_eq!(params.get("file"), Some(&"a/b".to_string()));
+
+        let params = extract_path_params(&pattern, "/users/Jos%C3%A9/files/100%zz").unwrap();
+        assert_eq!(params.get("name"), Some(&"José".to_string()));
+        assert_eq!(params.get("file"), Some(&"100%zz".to_string()));
+
+        // Length constraints apply to the decoded value
+        let pattern = RoutePattern::compile("/tags/{tag:str(max=3)}");
+        assert!(extract_path_params(&pattern, "/tags/a%20b").is_some());
+    }
 }
@@ -1,6 +1,12 @@
 // Security headers and utilities
 use axum::response::Response;
-use axum::http::{HeaderMap, HeaderValue};
+use axum::http::HeaderValue;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug, Clone)]
 pub struct SecurityHeaders {
@@ -13,6 +19,12 @@ pub struct SecurityHeaders {
     pub hsts_max_age: u32,
 }
 
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SecurityHeaders {
     pub fn new() -> Self {
         Self {
@@ -58,3 +70,53 @@ impl SecurityHeaders {
         response
     }
 }
+
+// Cookie signing: "<value>.<base64url(HMAC-SHA256(name=value))>"
+// The cookie name is part of the MAC so a signed value can't be replayed under another name.
+pub fn sign_cookie_value(name: &str, value: &str, secret: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}", value, signature)
+}
+
+// Webhook body signing: hex(HMAC-SHA256(body)), as sent in e.g. X-Hub-Signature-256
+pub fn sign_body(body: &[u8], secret: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// True if `signature_hex` (either case) is the HMAC-SHA256 of `body` under `secret`
+pub fn verify_body_signature(body: &[u8], signature_hex: &str, secret: &str) -> bool {
+    let Ok(signature) = hex::decode(signature_hex) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    
+    // Constant-time comparison
+    mac.verify_slice(&signature).is_ok()
+}
+
+// Returns the original value if the signature is valid, None if missing or tampered
+pub fn verify_cookie_value(name: &str, signed_value: &str, secret: &str) -> Option<String> {
+    let (value, signature) = signed_value.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    
+    // Constant-time comparison
+    mac.verify_slice(&signature).ok()?;
+    Some(value.to_string())
+}
@@ -2,6 +2,9 @@
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 
 #[derive(Debug, Clone)]
@@ -28,7 +31,7 @@ impl TemplateEngine {
         }
         
         let template_content = std::fs::read_to_string(&template_path)
-            .map_err(|e| TemplateError::IoError(e))?;
+            .map_err(TemplateError::IoError)?;
         
         self.render_string(&template_content, context)
     }
@@ -153,6 +156,10 @@ pub struct StaticFileHandler {
     pub static_dirs: HashMap<String, PathBuf>,
     pub cache_max_age: u32,
     pub enable_etag: bool,
+    // Single-page app mode: extensionless paths with no file get this index.html instead
+    // of a 404, except under `spa_exclude_prefixes` (API routes)
+    pub spa_index: Option<PathBuf>,
+    pub spa_exclude_prefixes: Vec<String>,
 }
 
 impl StaticFileHandler {
@@ -161,9 +168,34 @@ impl StaticFileHandler {
             static_dirs: HashMap::new(),
             cache_max_age: 3600, // 1 hour
             enable_etag: true,
+            spa_index: None,
+            spa_exclude_prefixes: vec!["/api".to_string()],
         }
     }
     
+    pub fn with_spa_fallback(mut self, index_file: &str) -> Self {
+        self.spa_index = Some(PathBuf::from(index_file));
+        self
+    }
+    
+    pub fn with_spa_exclude_prefixes(mut self, prefixes: Vec<String>) -> Self {
+        self.spa_exclude_prefixes = prefixes;
+        self
+    }
+    
+    // The SPA index for a request no file matched: client-side routes only, so asset
+    // paths (with an extension) and excluded prefixes still 404
+    fn spa_fallback(&self, request_path: &str) -> Option<&Path> {
+        let index = self.spa_index.as_deref()?;
+        let excluded = self.spa_exclude_prefixes.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            request_path == prefix || request_path.starts_with(&format!("{}/", prefix))
+        });
+        let has_extension = Path::new(request_path).extension().is_some();
+        
+        (!excluded && !has_extension && index.is_file()).then_some(index)
+    }
+    
     pub fn add_directory(&mut self, route_prefix: &str, directory: &str) {
         self.static_dirs.insert(route_prefix.to_string(), PathBuf::from(directory));
     }
@@ -213,6 +245,94 @@ impl StaticFileHandler {
         let result = hasher.finalize();
         format!("\"{}\"", hex::encode(&result[..8]))
     }
+    
+    /// Serve the file mapped to `request_path`, with `ETag` and `Last-Modified` headers.
+    /// Returns 304 when `If-None-Match` matches the ETag or `If-Modified-Since` is at or
+    /// after the file's mtime, and 404 when no file is mapped (or the SPA index, in SPA
+    /// fallback mode).
+    pub fn serve_file(&self, request_path: &str, headers: &HeaderMap) -> Response {
+        match self.get_file_path(request_path) {
+            Some(path) if path.is_file() => {
+                let cache_control = format!("public, max-age={}", self.cache_max_age);
+                self.serve_path(&path, headers, &cache_control)
+            }
+            // Revalidate the index every time so a new deploy is picked up immediately
+            _ => match self.spa_fallback(request_path) {
+                Some(index) => self.serve_path(index, headers, "no-cache"),
+                None => Self::empty_response(StatusCode::NOT_FOUND),
+            },
+        }
+    }
+    
+    fn serve_path(&self, file_path: &Path, headers: &HeaderMap, cache_control: &str) -> Response {
+        let content = match std::fs::read(file_path) {
+            Ok(content) => content,
+            Err(_) => return Self::empty_response(StatusCode::NOT_FOUND),
+        };
+        
+        // HTTP dates have one-second resolution, so compare at that granularity
+        let last_modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|mtime| DateTime::<Utc>::from(mtime).timestamp());
+        let etag = self.enable_etag.then(|| self.generate_etag(&content));
+        
+        let etag_matches = match (&etag, Self::header(headers, "if-none-match")) {
+            (Some(etag), Some(candidates)) => candidates
+                .split(',')
+                .map(|candidate| candidate.trim().trim_start_matches("W/"))
+                .any(|candidate| candidate == "*" || candidate == etag),
+            _ => false,
+        };
+        let not_modified_since = match (last_modified, Self::header(headers, "if-modified-since")) {
+            (Some(mtime), Some(since)) => DateTime::parse_from_rfc2822(since)
+                .map(|since| since.timestamp() >= mtime)
+                .unwrap_or(false),
+            _ => false,
+        };
+        
+        let status = if etag_matches || not_modified_since {
+            StatusCode::NOT_MODIFIED
+        } else {
+            StatusCode::OK
+        };
+        
+        let mut builder = Response::builder()
+            .status(status)
+            .header("cache-control", cache_control);
+        if let Some(etag) = &etag {
+            builder = builder.header("etag", etag);
+        }
+        if let Some(mtime) = last_modified.and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0)) {
+            builder = builder.header("last-modified", format_http_date(mtime));
+        }
+        
+        if status == StatusCode::NOT_MODIFIED {
+            return builder.body(axum::body::Body::empty()).unwrap();
+        }
+        
+        builder
+            .header("content-type", self.get_content_type(file_path))
+            .header("content-length", content.len())
+            .body(axum::body::Body::from(content))
+            .unwrap()
+    }
+    
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+        headers.get(name).and_then(|value| value.to_str().ok())
+    }
+    
+    fn empty_response(status: StatusCode) -> Response {
+        Response::builder()
+            .status(status)
+            .body(axum::body::Body::empty())
+            .unwrap()
+    }
+}
+
+// IMF-fixdate, e.g. "Sun, 06 Nov 1994 08:49:37 GMT" (RFC 9110 section 5.6.7)
+fn format_http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
 }
 
 impl Default for StaticFileHandler {
@@ -290,6 +410,61 @@ mod tests {
         assert!(bad_path.is_none());
     }
 
+    #[test]
+    fn test_serve_file_conditional_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("logo.svg"), "<svg/>").unwrap();
+        
+        let mut handler = StaticFileHandler::new();
+        handler.add_directory("/static", dir.path().to_str().unwrap());
+        
+        let response = handler.serve_file("/static/logo.svg", &HeaderMap::new());
+        assert_eq!(response.status(), StatusCode::OK);
+        let last_modified = response.headers()["last-modified"].to_str().unwrap().to_string();
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        assert!(last_modified.ends_with(" GMT"));
+        
+        let mut headers = HeaderMap::new();
+        headers.insert("if-modified-since", last_modified.parse().unwrap());
+        let response = handler.serve_file("/static/logo.svg", &headers);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["last-modified"], last_modified.as_str());
+        
+        headers.insert("if-modified-since", "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap());
+        assert_eq!(handler.serve_file("/static/logo.svg", &headers).status(), StatusCode::OK);
+        
+        // A matching ETag is enough on its own
+        headers.insert("if-none-match", etag.parse().unwrap());
+        assert_eq!(handler.serve_file("/static/logo.svg", &headers).status(), StatusCode::NOT_MODIFIED);
+        
+        assert_eq!(handler.serve_file("/static/missing.svg", &HeaderMap::new()).status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_spa_fallback_serves_index_for_client_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = dir.path().join("index.html");
+        std::fs::write(&index, "<div id=app></div>").unwrap();
+        std::fs::write(dir.path().join("app.js"), "boot()").unwrap();
+        
+        let mut handler = StaticFileHandler::new().with_spa_fallback(index.to_str().unwrap());
+        handler.add_directory("/", dir.path().to_str().unwrap());
+        
+        let response = handler.serve_file("/dashboard/settings", &HeaderMap::new());
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/html; charset=utf-8");
+        assert_eq!(response.headers()["cache-control"], "no-cache");
+        
+        // Real files are still served as themselves
+        let response = handler.serve_file("/app.js", &HeaderMap::new());
+        assert_eq!(response.headers()["content-type"], "application/javascript");
+        
+        // Missing assets and API routes keep their 404
+        assert_eq!(handler.serve_file("/missing.css", &HeaderMap::new()).status(), StatusCode::NOT_FOUND);
+        assert_eq!(handler.serve_file("/api/users", &HeaderMap::new()).status(), StatusCode::NOT_FOUND);
+        assert_eq!(handler.serve_file("/apiary", &HeaderMap::new()).status(), StatusCode::OK);
+    }
+
     #[test]
     fn test_content_type_detection() {
         let handler = StaticFileHandler::new();